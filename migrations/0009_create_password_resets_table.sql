@@ -0,0 +1,11 @@
+CREATE TABLE password_resets(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  user_id VARCHAR(36) NOT NULL,
+  code VARCHAR(64) NOT NULL UNIQUE, -- sha256 of the code sent to the user
+  expires_at timestamp NOT NULL,
+  used_at timestamp NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
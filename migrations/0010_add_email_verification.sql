@@ -0,0 +1,15 @@
+ALTER TABLE users ADD COLUMN email_verified_at timestamp NULL AFTER password;
+
+-- Accounts created before verification existed are trusted as they are
+UPDATE users SET email_verified_at = created_at;
+
+CREATE TABLE email_verifications(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  user_id VARCHAR(36) NOT NULL,
+  token VARCHAR(64) NOT NULL UNIQUE, -- sha256 of the token sent to the user
+  expires_at timestamp NOT NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+ALTER TABLE tokens
+  ADD COLUMN device_name VARCHAR(100) NULL AFTER value,
+  ADD COLUMN user_agent VARCHAR(255) NULL AFTER device_name,
+  ADD COLUMN ip VARCHAR(45) NULL AFTER user_agent,
+  ADD COLUMN last_used_at timestamp NULL AFTER ip;
@@ -0,0 +1,5 @@
+DROP INDEX tokens_family_id ON tokens;
+
+ALTER TABLE tokens
+  DROP COLUMN family_id,
+  DROP COLUMN rotated_at;
@@ -0,0 +1,12 @@
+-- Rows in tokens are now refresh tokens. Every rotation creates a new row in
+-- the same family and marks the previous one as rotated, so a rotated token
+-- being presented again means it leaked and the whole family is revoked.
+ALTER TABLE tokens
+  ADD COLUMN family_id VARCHAR(36) NULL AFTER user_id,
+  ADD COLUMN rotated_at timestamp NULL AFTER last_used_at;
+
+UPDATE tokens SET family_id = id;
+
+ALTER TABLE tokens MODIFY family_id VARCHAR(36) NOT NULL;
+
+CREATE INDEX tokens_family_id ON tokens (family_id);
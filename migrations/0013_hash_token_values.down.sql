@@ -0,0 +1,3 @@
+-- Digests can't be turned back into token values, everyone has to log in
+-- again.
+DELETE FROM tokens;
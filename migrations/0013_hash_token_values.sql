@@ -0,0 +1,3 @@
+-- Token values are now stored as sha256 digests, convert the existing ones
+-- so current sessions survive the upgrade.
+UPDATE tokens SET value = SHA2(value, 256);
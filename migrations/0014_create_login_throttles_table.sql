@@ -0,0 +1,9 @@
+CREATE TABLE login_throttles(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  throttle_key VARCHAR(150) NOT NULL UNIQUE, -- "email:<email>" or "ip:<ip>"
+  failures int NOT NULL,
+  locked_until timestamp NULL,
+  last_failure_at timestamp NOT NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+);
@@ -0,0 +1,15 @@
+ALTER TABLE users ADD COLUMN totp_secret VARCHAR(64) NULL AFTER email_verified_at;
+ALTER TABLE users ADD COLUMN totp_enabled_at timestamp NULL AFTER totp_secret;
+-- Last time step a code was accepted for, so a code can't be used twice
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT NULL AFTER totp_enabled_at;
+
+CREATE TABLE recovery_codes(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  user_id VARCHAR(36) NOT NULL,
+  code VARCHAR(64) NOT NULL UNIQUE, -- sha256 of the code shown to the user
+  used_at timestamp NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,14 @@
+CREATE TABLE api_keys(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  user_id VARCHAR(36) NOT NULL,
+  name VARCHAR(100) NOT NULL,
+  prefix VARCHAR(16) NOT NULL, -- start of the key, so users can tell them apart
+  value VARCHAR(64) NOT NULL UNIQUE, -- sha256 of the key
+  scopes VARCHAR(255) NOT NULL, -- space separated, e.g. "workouts:read sets:write"
+  last_used_at timestamp NULL,
+  expires_at timestamp NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- Fails while accounts without a password exist, they have to be given one
+-- or deleted first. Runs first as MySQL can't roll back the drops.
+ALTER TABLE users MODIFY password VARCHAR(100) NOT NULL;
+
+DROP TABLE oidc_logins;
+DROP TABLE user_identities;
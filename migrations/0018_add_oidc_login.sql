@@ -0,0 +1,28 @@
+-- Accounts created through an identity provider don't need a password
+ALTER TABLE users MODIFY password VARCHAR(100) NULL;
+
+CREATE TABLE user_identities(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  user_id VARCHAR(36) NOT NULL,
+  issuer VARCHAR(255) NOT NULL,
+  subject VARCHAR(255) NOT NULL,
+  email VARCHAR(100) NULL,
+  last_login_at timestamp NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+  UNIQUE (issuer, subject),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Started but not yet finished authorization code flows
+CREATE TABLE oidc_logins(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  state VARCHAR(64) NOT NULL UNIQUE, -- sha256 of the state sent to the provider
+  nonce VARCHAR(64) NOT NULL,
+  code_verifier VARCHAR(128) NOT NULL,
+  device_name VARCHAR(100) NULL,
+  expires_at timestamp NOT NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+);
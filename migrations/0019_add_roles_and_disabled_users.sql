@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN role ENUM('user', 'admin') NOT NULL DEFAULT 'user' AFTER password;
+ALTER TABLE users ADD COLUMN disabled_at timestamp NULL AFTER role;
@@ -0,0 +1,16 @@
+CREATE TABLE jobs(
+  id VARCHAR(36) NOT NULL PRIMARY KEY DEFAULT (UUID()),
+  kind VARCHAR(64) NOT NULL, -- e.g. "send_mail", also in the payload
+  payload TEXT NOT NULL, -- JSON of the typed payload
+  status ENUM('pending', 'running', 'done', 'dead') NOT NULL DEFAULT 'pending',
+  attempts int NOT NULL DEFAULT 0,
+  max_attempts int NOT NULL,
+  run_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, -- not picked up before this
+  locked_by VARCHAR(64) NULL, -- worker running the job
+  locked_at timestamp NULL,
+  last_error TEXT NULL,
+  created_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+  INDEX jobs_status_run_at (status, run_at)
+);
@@ -0,0 +1,5 @@
+-- Mail jobs used to store the rendered mail, reset codes and verification
+-- links included. Jobs now only reference rows, drop the old ones instead of
+-- keeping those codes around. Pending mails are lost, users can ask for new
+-- codes.
+DELETE FROM jobs WHERE kind = 'send_mail';
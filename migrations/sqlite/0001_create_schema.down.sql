@@ -0,0 +1,18 @@
+-- Children first, foreign keys are enforced
+DROP TABLE jobs;
+DROP TABLE oidc_logins;
+DROP TABLE user_identities;
+DROP TABLE api_keys;
+DROP TABLE email_changes;
+DROP TABLE recovery_codes;
+DROP TABLE login_throttles;
+DROP TABLE email_verifications;
+DROP TABLE password_resets;
+DROP TABLE exercise_target;
+DROP TABLE targets;
+DROP TABLE sets;
+DROP TABLE exercise_workout;
+DROP TABLE workout;
+DROP TABLE exercises;
+DROP TABLE tokens;
+DROP TABLE users;
@@ -0,0 +1,316 @@
+-- The same schema as the MySQL migrations in the parent directory, for
+-- running without a database server. Timestamps are UTC text in the format
+-- the application writes, so they sort and compare as strings.
+
+CREATE TABLE users(
+  id TEXT NOT NULL PRIMARY KEY,
+  email TEXT NOT NULL UNIQUE COLLATE NOCASE,
+  password TEXT NULL,
+  role TEXT NOT NULL DEFAULT 'user' CHECK (role IN ('user', 'admin')),
+  disabled_at TEXT NULL,
+  email_verified_at TEXT NULL,
+  totp_secret TEXT NULL,
+  totp_enabled_at TEXT NULL,
+  totp_last_step INTEGER NULL, -- last time step a code was accepted for
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE TABLE tokens(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  family_id TEXT NOT NULL,
+  value TEXT NOT NULL UNIQUE, -- sha256 of the refresh token
+  device_name TEXT NULL,
+  user_agent TEXT NULL,
+  ip TEXT NULL,
+  last_used_at TEXT NULL,
+  rotated_at TEXT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE exercises(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  name TEXT NOT NULL,
+  exercise_type TEXT NOT NULL CHECK (exercise_type IN ('static', 'distance_over_time', 'weight_over_amount')),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE workout(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'ongoing' CHECK (status IN ('ongoing', 'done')),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE exercise_workout(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  exercise_id TEXT NOT NULL,
+  workout_id TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE,
+  FOREIGN KEY (workout_id) REFERENCES workout(id) ON DELETE CASCADE
+);
+
+CREATE TABLE sets(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  exercise_workout_id TEXT NOT NULL,
+  quality REAL NOT NULL, -- km or kg
+  quantity REAL NOT NULL, -- time(second) or reps
+  note TEXT NULL,
+  set_type TEXT NOT NULL CHECK (set_type IN ('warmup', 'normal')),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY (exercise_workout_id) REFERENCES exercise_workout(id) ON DELETE CASCADE
+);
+
+CREATE TABLE targets(
+  id TEXT NOT NULL PRIMARY KEY,
+  name TEXT NOT NULL,
+  sort INTEGER NOT NULL
+);
+
+CREATE TABLE exercise_target(
+  id TEXT NOT NULL PRIMARY KEY,
+  exercise_id TEXT NOT NULL,
+  target_id TEXT NOT NULL,
+
+  FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE,
+  FOREIGN KEY (target_id) REFERENCES targets(id) ON DELETE CASCADE
+);
+
+CREATE TABLE password_resets(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  code TEXT NOT NULL UNIQUE, -- sha256 of the code sent to the user
+  expires_at TEXT NOT NULL,
+  used_at TEXT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE email_verifications(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  token TEXT NOT NULL UNIQUE, -- sha256 of the token sent to the user
+  expires_at TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE login_throttles(
+  id TEXT NOT NULL PRIMARY KEY,
+  throttle_key TEXT NOT NULL UNIQUE, -- "email:<email>" or "ip:<ip>"
+  failures INTEGER NOT NULL,
+  locked_until TEXT NULL,
+  last_failure_at TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE TABLE recovery_codes(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  code TEXT NOT NULL UNIQUE, -- sha256 of the code shown to the user
+  used_at TEXT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE email_changes(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  new_email TEXT NOT NULL,
+  token TEXT NOT NULL UNIQUE, -- sha256 of the token sent to the new address
+  expires_at TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE api_keys(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  name TEXT NOT NULL,
+  prefix TEXT NOT NULL, -- start of the key, so users can tell them apart
+  value TEXT NOT NULL UNIQUE, -- sha256 of the key
+  scopes TEXT NOT NULL, -- space separated, e.g. "workouts:read sets:write"
+  last_used_at TEXT NULL,
+  expires_at TEXT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE user_identities(
+  id TEXT NOT NULL PRIMARY KEY,
+  user_id TEXT NOT NULL,
+  issuer TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  email TEXT NULL,
+  last_login_at TEXT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+
+  UNIQUE (issuer, subject),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE oidc_logins(
+  id TEXT NOT NULL PRIMARY KEY,
+  state TEXT NOT NULL UNIQUE, -- sha256 of the state sent to the provider
+  nonce TEXT NOT NULL,
+  code_verifier TEXT NOT NULL,
+  device_name TEXT NULL,
+  expires_at TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE TABLE jobs(
+  id TEXT NOT NULL PRIMARY KEY,
+  kind TEXT NOT NULL, -- e.g. "send_mail", also in the payload
+  payload TEXT NOT NULL, -- JSON of the typed payload
+  status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'done', 'dead')),
+  attempts INTEGER NOT NULL DEFAULT 0,
+  max_attempts INTEGER NOT NULL,
+  run_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')), -- not picked up before this
+  locked_by TEXT NULL, -- worker running the job
+  locked_at TEXT NULL,
+  last_error TEXT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now'))
+);
+
+CREATE INDEX tokens_family_id ON tokens (family_id);
+
+CREATE INDEX jobs_status_run_at ON jobs (status, run_at);
+
+INSERT INTO targets (id, name, sort) VALUES ('93f25eb0-6057-4db4-9281-684440aaab77', 'Shoulders', 1),
+  ('f490e053-f337-4c8a-8f04-12d4fef418b2', 'Chest', 2),
+  ('0bb07d10-1cdb-4df8-a6e2-614e3c46d992', 'Upper back', 3),
+  ('722c7081-d7f3-4d20-a236-42cc7e7b63b3', 'Lower back', 4),
+  ('46bd72d3-1035-4616-87d6-820364728211', 'Core', 5),
+  ('8bb01634-0995-4e35-ab10-6e05d7999a35', 'Thighs', 6),
+  ('dbe6e212-80d9-4a41-be09-77e0f67ed943', 'Calfs', 7),
+  ('ec791df4-ccae-43fd-be25-f15033efe958', 'Biceps', 8),
+  ('4694ebc3-1c92-4417-8e4d-2ffea39140f2', 'Triceps', 9),
+  ('62debec8-748c-4757-b65d-0d13f8b7d90f', 'Lower arms', 10),
+  ('bce1c62d-7fb1-431b-9bc4-f7b839a495d0', 'Cardio', 11);
+
+-- MySQL's ON UPDATE CURRENT_TIMESTAMP
+
+CREATE TRIGGER users_updated_at AFTER UPDATE ON users
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE users SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER tokens_updated_at AFTER UPDATE ON tokens
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE tokens SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER exercises_updated_at AFTER UPDATE ON exercises
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE exercises SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER workout_updated_at AFTER UPDATE ON workout
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE workout SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER exercise_workout_updated_at AFTER UPDATE ON exercise_workout
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE exercise_workout SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER sets_updated_at AFTER UPDATE ON sets
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE sets SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER password_resets_updated_at AFTER UPDATE ON password_resets
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE password_resets SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER email_verifications_updated_at AFTER UPDATE ON email_verifications
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE email_verifications SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER login_throttles_updated_at AFTER UPDATE ON login_throttles
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE login_throttles SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER recovery_codes_updated_at AFTER UPDATE ON recovery_codes
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE recovery_codes SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER email_changes_updated_at AFTER UPDATE ON email_changes
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE email_changes SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER api_keys_updated_at AFTER UPDATE ON api_keys
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE api_keys SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER user_identities_updated_at AFTER UPDATE ON user_identities
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE user_identities SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER oidc_logins_updated_at AFTER UPDATE ON oidc_logins
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE oidc_logins SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER jobs_updated_at AFTER UPDATE ON jobs
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+  UPDATE jobs SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id;
+END;
@@ -0,0 +1,2 @@
+-- The deleted jobs can't be restored, and nothing needs to be undone
+SELECT 1;
@@ -0,0 +1,322 @@
+//! Operating the server without raw SQL. Reads the same `.env`, config file
+//! and env variables as the server, so it always talks to the same database.
+
+use std::{
+    io::{self, BufRead, IsTerminal},
+    path::PathBuf,
+    process::ExitCode,
+    time::Instant,
+};
+
+use chrono::{NaiveDate, Utc};
+use clap::{Parser, Subcommand};
+use futures::StreamExt;
+use tokio::io::AsyncWriteExt;
+use tracing_subscriber::EnvFilter;
+use validator::Validate;
+
+use workout_backend::{
+    config::Config,
+    db::{self, Database},
+    dtos::auth::CreateUserPayload,
+    error::Error,
+    export, generator,
+    helpers::security::hash_password,
+    models::{
+        token::Token,
+        user::{Role, User},
+    },
+    seeder,
+};
+
+#[derive(Parser)]
+#[command(name = "workout_admin", about = "Administrative tasks for the workout backend")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Database schema migrations
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Accounts
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Refresh tokens
+    #[command(subcommand)]
+    Tokens(TokensCommand),
+    /// Creates the demo account with a few exercises and workouts
+    Seed,
+    /// Creates users with months or years of training history, for load
+    /// tests and demos. The same options create the same rows.
+    Generate {
+        #[arg(long, default_value_t = 10)]
+        users: u32,
+        #[arg(long, default_value_t = 52)]
+        weeks: u32,
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        /// Last day of the histories, e.g. 2024-06-30. Today when left out.
+        #[arg(long)]
+        until: Option<NaiveDate>,
+    },
+    /// Writes everything stored about a user as JSON
+    Export {
+        /// Email or id of the user
+        #[arg(long)]
+        user: String,
+        /// File to write to instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MigrateCommand {
+    /// Applies the migrations that haven't run yet
+    Run,
+    /// Lists every migration and whether it has been applied
+    Status,
+    /// Undoes the newest applied migration
+    Revert,
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    /// Creates an account with a verified email. Asks for the password, or
+    /// reads it from stdin when that isn't a terminal.
+    Create {
+        email: String,
+        #[arg(long)]
+        admin: bool,
+    },
+    /// Disables the account and signs it out everywhere
+    Disable {
+        /// Email or id of the user
+        user: String,
+    },
+    /// Sets a new password and signs the user out everywhere
+    ResetPassword {
+        /// Email or id of the user
+        user: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokensCommand {
+    /// Deletes refresh tokens older than the configured TTL
+    Purge,
+}
+
+/// Printed as is, without the `Other(...)` wrapping of the API errors
+struct CliError(String);
+
+impl From<Error> for CliError {
+    fn from(err: Error) -> Self {
+        Self(err.to_string())
+    }
+}
+
+type CliResult = Result<(), CliError>;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match dotenvy::dotenv() {
+        // env variables might come from os env
+        Err(dotenvy::Error::Io(error)) if error.kind() == io::ErrorKind::NotFound => (),
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+        Ok(_) => (),
+    }
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // stdout is for output like exports, logs go to stderr
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(&config.log.filter))
+        .with_writer(io::stderr)
+        .init();
+
+    let db = match db::connect(&config.database).await {
+        Ok(db) => db,
+        Err(err) => {
+            eprintln!("Failed to connect to the database: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = run(cli.command, &config, &db).await;
+    db.close().await;
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError(message)) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command, config: &Config, db: &std::sync::Arc<dyn Database>) -> CliResult {
+    match command {
+        Command::Migrate(MigrateCommand::Run) => {
+            let pending = db.pending_migrations().await?;
+            db.migrate().await?;
+            println!("Applied {pending} migration(s)");
+        }
+        Command::Migrate(MigrateCommand::Status) => {
+            for migration in db.migrations().await? {
+                println!(
+                    "{:>4}  {:<8} {}{}",
+                    migration.version,
+                    if migration.applied { "applied" } else { "pending" },
+                    migration.description,
+                    if migration.reversible { "" } else { " (irreversible)" },
+                );
+            }
+        }
+        Command::Migrate(MigrateCommand::Revert) => match db.revert_migration().await? {
+            Some(migration) => println!("Reverted {} {}", migration.version, migration.description),
+            None => println!("No migrations applied"),
+        },
+        Command::User(UserCommand::Create { email, admin }) => {
+            let password = read_password()?;
+            let payload = CreateUserPayload { email, password };
+            payload.validate().map_err(|err| CliError(err.to_string()))?;
+
+            if User::find_by_email(db, &payload.email).await?.is_some() {
+                return Err(CliError(format!("{} is already registered", payload.email)));
+            }
+
+            let tx = db.begin().await?;
+            let mut user = User::create(&*tx, payload.email, hash_password(&payload.password)?).await?;
+            user.mark_email_verified(&*tx).await?;
+            if admin {
+                user.set_role(&*tx, Role::Admin).await?;
+            }
+            tx.commit().await?;
+
+            println!("Created {} ({}) as {}", user.email, user.id, user.role);
+        }
+        Command::User(UserCommand::Disable { user }) => {
+            let mut user = find_user(db, &user).await?;
+
+            let tx = db.begin().await?;
+            user.disable(&*tx).await?;
+            Token::delete_all_by_user_id(&*tx, user.id.clone()).await?;
+            tx.commit().await?;
+
+            println!("Disabled {} ({})", user.email, user.id);
+        }
+        Command::User(UserCommand::ResetPassword { user }) => {
+            let mut user = find_user(db, &user).await?;
+
+            let password = read_password()?;
+            CreateUserPayload { email: user.email.clone(), password: password.clone() }
+                .validate()
+                .map_err(|err| CliError(err.to_string()))?;
+
+            user.password = Some(hash_password(&password)?);
+
+            let tx = db.begin().await?;
+            user.save(&*tx).await?;
+            Token::delete_all_by_user_id(&*tx, user.id.clone()).await?;
+            tx.commit().await?;
+
+            println!("Reset the password of {} ({})", user.email, user.id);
+        }
+        Command::Tokens(TokensCommand::Purge) => {
+            Token::delete_expired(db, config.refresh_token_ttl()).await?;
+            println!("Deleted refresh tokens older than {}s", config.auth.refresh_token_ttl_secs);
+        }
+        Command::Seed => {
+            if User::find_by_email(db, seeder::DEMO_EMAIL).await?.is_some() {
+                return Err(CliError(format!("{} already exists", seeder::DEMO_EMAIL)));
+            }
+
+            let user = seeder::seed(db).await?;
+            println!("Seeded {} ({}), the password is \"password\"", user.email, user.id);
+        }
+        Command::Generate { users, weeks, seed, until } => {
+            let options = generator::Options {
+                users,
+                weeks,
+                seed,
+                until: until.unwrap_or_else(|| Utc::now().date_naive()),
+            };
+
+            let started = Instant::now();
+            let summary = generator::generate(db, &options).await?;
+            println!(
+                "Generated {} users, {} exercises, {} workouts and {} sets in {:.1}s",
+                summary.users,
+                summary.exercises,
+                summary.workouts,
+                summary.sets,
+                started.elapsed().as_secs_f64()
+            );
+        }
+        Command::Export { user, output } => {
+            let user = find_user(db, &user).await?;
+
+            let mut out: Box<dyn tokio::io::AsyncWrite + Unpin> = match &output {
+                Some(path) => Box::new(
+                    tokio::fs::File::create(path)
+                        .await
+                        .map_err(|err| CliError(format!("Can't create {}: {err}", path.display())))?,
+                ),
+                None => Box::new(tokio::io::stdout()),
+            };
+
+            let mut chunks = export::stream(db.clone(), user);
+            while let Some(chunk) = chunks.next().await {
+                out.write_all(&chunk?).await.map_err(|err| CliError(format!("Failed to write export: {err}")))?;
+            }
+            out.flush().await.map_err(|err| CliError(format!("Failed to write export: {err}")))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// By email, falling back to the id
+async fn find_user(db: &dyn Database, user: &str) -> Result<User, CliError> {
+    if let Some(found) = User::find_by_email(db, user).await? {
+        return Ok(found);
+    }
+
+    User::find_by_id(db, user.to_string())
+        .await?
+        .ok_or_else(|| CliError(format!("No user with email or id {user}")))
+}
+
+/// Prompts twice on a terminal, otherwise takes the first line of stdin so
+/// scripts can pipe it in
+fn read_password() -> Result<String, CliError> {
+    let failed = |err: io::Error| CliError(format!("Can't read the password: {err}"));
+
+    if !io::stdin().is_terminal() {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).map_err(failed)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let password = rpassword::prompt_password("Password: ").map_err(failed)?;
+    if rpassword::prompt_password("Repeat password: ").map_err(failed)? != password {
+        return Err(CliError("Passwords don't match".to_string()));
+    }
+
+    Ok(password)
+}
@@ -0,0 +1,422 @@
+use std::{net::SocketAddr, path::PathBuf, str::FromStr, time::Duration};
+
+use crate::{db::Backend, helpers::oidc::OidcConfig, middlewares::auth::UnverifiedUsers};
+
+/// Everything an operator can change about the server. Read from the TOML
+/// file in `CONFIG_FILE` (or `./config.toml` when it exists), then from env
+/// variables, which win over the file.
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub mail: MailConfig,
+    /// `None` when OIDC login isn't configured
+    pub oidc: Option<OidcConfig>,
+    pub queue: QueueConfig,
+    pub log: LogConfig,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen_addr: SocketAddr,
+    /// Used for links in emails
+    pub public_url: String,
+    pub static_dir: PathBuf,
+    /// Origins allowed to call the API from a browser, `*` allows any
+    pub cors_allowed_origins: Vec<String>,
+    /// How often expired tokens, codes and throttles are deleted
+    pub cleanup_interval_secs: u64,
+    /// Longest a single background job run may take
+    pub job_timeout_secs: u64,
+    /// How long in-flight requests get to finish after SIGTERM
+    pub shutdown_timeout_secs: u64,
+    /// Scrapers send it as a Bearer token, `/metrics` isn't served without it
+    pub metrics_token: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a request waits for a free connection before failing
+    pub acquire_timeout_secs: u64,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Signs access tokens, use a long random string
+    pub access_token_secret: String,
+    /// How long an access token is valid. Clients use their refresh token
+    /// to get a new one.
+    pub access_token_ttl_secs: u64,
+    /// How long a user has to enter their 2FA code after giving the password
+    pub challenge_ttl_secs: u64,
+    /// How long a session lasts without being refreshed
+    pub refresh_token_ttl_secs: u64,
+    pub unverified_users: UnverifiedUsers,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransport {
+    Stdout,
+    File,
+    Smtp,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    pub transport: MailTransport,
+    /// Required for the `file` transport
+    pub file: Option<PathBuf>,
+    /// Required for the `smtp` transport
+    pub smtp_url: Option<String>,
+    /// Required for the `smtp` transport
+    pub from: Option<String>,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    /// Jobs processed at the same time by this instance
+    pub workers: usize,
+    /// How long an idle worker waits before looking for jobs again
+    pub poll_interval_ms: u64,
+    /// Attempts before a job is given up on
+    pub max_attempts: i32,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(serde::Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// In the `RUST_LOG` format, e.g. `info,sqlx=warn`
+    pub filter: String,
+    pub format: LogFormat,
+}
+
+impl FromStr for MailTransport {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value {
+            "stdout" => Ok(Self::Stdout),
+            "file" => Ok(Self::File),
+            "smtp" => Ok(Self::Smtp),
+            other => Err(format!("unknown mail transport {other}, expected stdout, file or smtp")),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        match value {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown log format {other}, expected text or json")),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            public_url: "http://localhost:3000".to_string(),
+            static_dir: PathBuf::from("./static"),
+            cors_allowed_origins: vec!["*".to_string()],
+            cleanup_interval_secs: 60 * 60,
+            job_timeout_secs: 5 * 60,
+            shutdown_timeout_secs: 30,
+            metrics_token: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            access_token_secret: String::new(),
+            access_token_ttl_secs: 15 * 60,
+            challenge_ttl_secs: 5 * 60,
+            refresh_token_ttl_secs: 7 * 24 * 60 * 60,
+            unverified_users: UnverifiedUsers::Restrict,
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        // stdout so local development works without any setup
+        Self {
+            transport: MailTransport::Stdout,
+            file: None,
+            smtp_url: None,
+            from: None,
+        }
+    }
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            poll_interval_ms: 1000,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+/// Everything wrong with the configuration, so operators can fix it all at
+/// once instead of one restart per mistake.
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(fmt, "Invalid configuration:")?;
+        for problem in &self.0 {
+            writeln!(fmt, "  - {problem}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match config_file() {
+            Some(path) => {
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|err| ConfigError(vec![format!("Can't read {}: {err}", path.display())]))?;
+
+                toml::from_str(&contents)
+                    .map_err(|err| ConfigError(vec![format!("Invalid {}: {err}", path.display())]))?
+            }
+            None => Self::default(),
+        };
+
+        let mut problems = Vec::new();
+        config.apply_env(&mut problems);
+
+        // Depends on public_url, so only known once everything else is
+        if let Some(oidc) = &mut config.oidc {
+            if oidc.redirect_url.is_empty() {
+                oidc.redirect_url = format!("{}/api/auth/oidc/callback", config.server.public_url);
+            }
+        }
+
+        config.validate(&mut problems);
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+
+    fn apply_env(&mut self, problems: &mut Vec<String>) {
+        env("LISTEN_ADDR", &mut self.server.listen_addr, problems);
+        env("PUBLIC_URL", &mut self.server.public_url, problems);
+        env("STATIC_DIR", &mut self.server.static_dir, problems);
+        if let Ok(origins) = std::env::var("CORS_ALLOWED_ORIGINS") {
+            self.server.cors_allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        env("CLEANUP_INTERVAL_SECS", &mut self.server.cleanup_interval_secs, problems);
+        env("JOB_TIMEOUT_SECS", &mut self.server.job_timeout_secs, problems);
+        env("SHUTDOWN_TIMEOUT_SECS", &mut self.server.shutdown_timeout_secs, problems);
+        env_opt("METRICS_TOKEN", &mut self.server.metrics_token, problems);
+
+        env("DATABASE_URL", &mut self.database.url, problems);
+        env("DATABASE_MAX_CONNECTIONS", &mut self.database.max_connections, problems);
+        env("DATABASE_MIN_CONNECTIONS", &mut self.database.min_connections, problems);
+        env("DATABASE_ACQUIRE_TIMEOUT_SECS", &mut self.database.acquire_timeout_secs, problems);
+
+        env("ACCESS_TOKEN_SECRET", &mut self.auth.access_token_secret, problems);
+        env("ACCESS_TOKEN_TTL_SECS", &mut self.auth.access_token_ttl_secs, problems);
+        env("CHALLENGE_TTL_SECS", &mut self.auth.challenge_ttl_secs, problems);
+        env("REFRESH_TOKEN_TTL_SECS", &mut self.auth.refresh_token_ttl_secs, problems);
+        env("UNVERIFIED_USERS", &mut self.auth.unverified_users, problems);
+
+        env("MAILER", &mut self.mail.transport, problems);
+        env_opt("MAILER_FILE", &mut self.mail.file, problems);
+        env_opt("SMTP_URL", &mut self.mail.smtp_url, problems);
+        env_opt("MAIL_FROM", &mut self.mail.from, problems);
+
+        if let (Ok(issuer), Ok(client_id)) = (std::env::var("OIDC_ISSUER"), std::env::var("OIDC_CLIENT_ID")) {
+            self.oidc = Some(OidcConfig {
+                issuer,
+                client_id,
+                ..self.oidc.take().unwrap_or_default()
+            });
+        }
+        if let Some(oidc) = &mut self.oidc {
+            env_opt("OIDC_CLIENT_SECRET", &mut oidc.client_secret, problems);
+            env("OIDC_REDIRECT_URL", &mut oidc.redirect_url, problems);
+            env("OIDC_SCOPES", &mut oidc.scopes, problems);
+            env("OIDC_AUTO_REGISTER", &mut oidc.auto_register, problems);
+        }
+
+        env("QUEUE_WORKERS", &mut self.queue.workers, problems);
+        env("QUEUE_POLL_INTERVAL_MS", &mut self.queue.poll_interval_ms, problems);
+        env("QUEUE_MAX_ATTEMPTS", &mut self.queue.max_attempts, problems);
+
+        env("LOG_FORMAT", &mut self.log.format, problems);
+        env("RUST_LOG", &mut self.log.filter, problems);
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        if self.database.url.is_empty() {
+            problems.push("database.url (DATABASE_URL) is required".to_string());
+        } else if Backend::from_url(&self.database.url).is_none() {
+            problems.push("database.url (DATABASE_URL) must start with mysql:// or sqlite:".to_string());
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            problems.push("database.min_connections can't be more than database.max_connections".to_string());
+        }
+
+        if self.auth.access_token_secret.is_empty() {
+            problems.push("auth.access_token_secret (ACCESS_TOKEN_SECRET) is required".to_string());
+        }
+        if self.auth.access_token_ttl_secs == 0 {
+            problems.push("auth.access_token_ttl_secs must be more than 0".to_string());
+        }
+        if self.auth.challenge_ttl_secs == 0 {
+            problems.push("auth.challenge_ttl_secs must be more than 0".to_string());
+        }
+        if self.auth.refresh_token_ttl_secs == 0 {
+            problems.push("auth.refresh_token_ttl_secs must be more than 0".to_string());
+        }
+
+        if self.server.cleanup_interval_secs == 0 {
+            problems.push("server.cleanup_interval_secs must be more than 0".to_string());
+        }
+        if self.server.job_timeout_secs == 0 {
+            problems.push("server.job_timeout_secs must be more than 0".to_string());
+        }
+        if self.server.shutdown_timeout_secs == 0 {
+            problems.push("server.shutdown_timeout_secs must be more than 0".to_string());
+        }
+        if self.server.metrics_token.as_deref() == Some("") {
+            problems.push("server.metrics_token (METRICS_TOKEN) can't be empty, leave it out instead".to_string());
+        }
+        for origin in &self.server.cors_allowed_origins {
+            if origin != "*" && reqwest::Url::parse(origin).is_err() {
+                problems.push(format!("server.cors_allowed_origins: {origin} is not a valid origin"));
+            }
+        }
+
+        if self.queue.workers == 0 {
+            problems.push("queue.workers must be at least 1".to_string());
+        }
+        if self.queue.poll_interval_ms == 0 {
+            problems.push("queue.poll_interval_ms must be more than 0".to_string());
+        }
+        if self.queue.max_attempts < 1 {
+            problems.push("queue.max_attempts must be at least 1".to_string());
+        }
+
+        match self.mail.transport {
+            MailTransport::File if self.mail.file.is_none() => {
+                problems.push("mail.file (MAILER_FILE) is required for the file transport".to_string())
+            }
+            MailTransport::Smtp if self.mail.smtp_url.is_none() || self.mail.from.is_none() => problems.push(
+                "mail.smtp_url (SMTP_URL) and mail.from (MAIL_FROM) are required for the smtp transport".to_string(),
+            ),
+            _ => (),
+        }
+
+        if let Some(oidc) = &self.oidc {
+            if oidc.issuer.is_empty() || oidc.client_id.is_empty() {
+                problems.push("oidc.issuer and oidc.client_id are required when oidc is configured".to_string());
+            }
+        }
+    }
+
+    pub fn refresh_token_ttl(&self) -> Duration {
+        Duration::from_secs(self.auth.refresh_token_ttl_secs)
+    }
+}
+
+fn config_file() -> Option<PathBuf> {
+    match std::env::var("CONFIG_FILE") {
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(_) => Some(PathBuf::from("./config.toml")).filter(|path| path.exists()),
+    }
+}
+
+/// Overrides `target` with the env variable `name` when it's set
+fn env<T: FromStr>(name: &str, target: &mut T, problems: &mut Vec<String>)
+where
+    T::Err: std::fmt::Display,
+{
+    if let Some(value) = parse_env(name, problems) {
+        *target = value;
+    }
+}
+
+fn env_opt<T: FromStr>(name: &str, target: &mut Option<T>, problems: &mut Vec<String>)
+where
+    T::Err: std::fmt::Display,
+{
+    if let Some(value) = parse_env(name, problems) {
+        *target = Some(value);
+    }
+}
+
+fn parse_env<T: FromStr>(name: &str, problems: &mut Vec<String>) -> Option<T>
+where
+    T::Err: std::fmt::Display,
+{
+    let value = std::env::var(name).ok()?;
+
+    value
+        .parse()
+        .map_err(|err| problems.push(format!("{name}: {err}")))
+        .ok()
+}
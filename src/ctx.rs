@@ -1,23 +1,82 @@
-use crate::models::{token::Token, user::User};
+use axum::http::Method;
 
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    models::{api_key::Scope, user::User},
+};
+
+/// The authenticated caller, built from the claims of the access token or
+/// from an API key.
 pub struct Ctx {
-    user: User,
-    current_token: Token,
+    user_id: String,
+    session_id: String,
+    email_verified: bool,
+    /// `None` for sessions, which may do anything
+    scopes: Option<Vec<Scope>>,
 }
 
 impl Ctx {
-    pub fn new(user: User, current_token: Token) -> Self {
+    pub fn new(user_id: String, session_id: String, email_verified: bool) -> Self {
+        Self {
+            user_id,
+            session_id,
+            email_verified,
+            scopes: None,
+        }
+    }
+
+    pub fn for_api_key(user_id: String, key_id: String, email_verified: bool, scopes: Vec<Scope>) -> Self {
         Self {
-            user,
-            current_token,
+            user_id,
+            session_id: key_id,
+            email_verified,
+            scopes: Some(scopes),
         }
     }
 
-    pub fn user(&self) -> &User {
-        &self.user
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Family id of the refresh token the access token was issued for, or
+    /// the id of the API key
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified
+    }
+
+    pub fn is_api_key(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    /// Whether the caller may make this request. Sessions may do anything,
+    /// API keys need a matching scope.
+    pub fn allows(&self, method: &Method, path: &str) -> bool {
+        let Some(scopes) = &self.scopes else {
+            return true;
+        };
+
+        Scope::required_for(method, path)
+            .is_some_and(|required| scopes.iter().any(|scope| scope.covers(required)))
     }
 
-    pub fn token(&self) -> &Token {
-        &self.current_token
+    /// Loads the full user, for the handlers that need more than the id
+    pub async fn user(&self, db: &dyn Database) -> Result<User> {
+        User::find_by_id(db, self.user_id.clone())
+            .await?
+            .ok_or(Error::WTF("Access token is valid but user doesn't exist".to_string()))
     }
 }
+
+/// Information about the client making the request, stored on tokens so
+/// users can tell their sessions apart.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
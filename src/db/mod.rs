@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use sqlx::migrate::Migrator;
+
+use crate::{
+    config::DatabaseConfig,
+    error::{Error, Result},
+    models::{
+        api_key::ApiKeyRepository, email_change::EmailChangeRepository,
+        email_verification::EmailVerificationRepository, exercise::ExerciseRepository,
+        exercise_target::ExerciseTargetRepository, exercise_workout::ExerciseWorkoutRepository,
+        job::JobRepository, login_throttle::LoginThrottleRepository, oidc_login::OidcLoginRepository,
+        password_reset::PasswordResetRepository, recovery_code::RecoveryCodeRepository,
+        set::SetRepository, target::TargetRepository, token::TokenRepository, user::UserRepository,
+        user_identity::UserIdentityRepository, workout::WorkoutRepository,
+    },
+};
+
+pub mod mysql;
+pub mod sqlite;
+
+pub use mysql::MySqlDatabase;
+pub use sqlite::SqliteDatabase;
+
+/// Rows per statement for bulk inserts. The widest table has 11 columns,
+/// which keeps every statement well under the bind parameter limits of both
+/// backends.
+pub(crate) const BULK_INSERT_ROWS: usize = 1000;
+
+/// Which database a `DATABASE_URL` points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    MySql,
+    Sqlite,
+}
+
+impl Backend {
+    pub fn from_url(url: &str) -> Option<Self> {
+        if url.starts_with("mysql://") || url.starts_with("mariadb://") {
+            Some(Self::MySql)
+        } else if url.starts_with("sqlite:") {
+            Some(Self::Sqlite)
+        } else {
+            None
+        }
+    }
+}
+
+/// Connection pool usage, for the metrics endpoint
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+/// A migration this build knows about and whether it has been applied
+#[derive(Debug, Clone)]
+pub struct MigrationInfo {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    /// Has a `.down.sql`, so it can be reverted
+    pub reversible: bool,
+}
+
+/// Storage for every model. Models get their repository from here, so
+/// handlers don't know or care which backend is behind it.
+#[async_trait::async_trait]
+pub trait Database: Send + Sync {
+    fn backend(&self) -> Backend;
+
+    /// Applies the migrations of this backend that haven't run yet
+    async fn migrate(&self) -> Result<()>;
+    /// Every migration of this backend, oldest first
+    async fn migrations(&self) -> Result<Vec<MigrationInfo>>;
+    /// Undoes the newest applied migration, `None` when nothing is applied
+    async fn revert_migration(&self) -> Result<Option<MigrationInfo>>;
+    /// How many migrations this build knows about haven't been applied
+    async fn pending_migrations(&self) -> Result<usize> {
+        Ok(self.migrations().await?.iter().filter(|migration| !migration.applied).count())
+    }
+    async fn ping(&self) -> Result<()>;
+    fn pool_stats(&self) -> PoolStats;
+    async fn close(&self);
+    /// Starts a transaction. Everything done through the returned database
+    /// is only kept once it's committed, dropping it rolls back. Transactions
+    /// don't nest, and streams always read outside of them.
+    async fn begin(&self) -> Result<Box<dyn Transaction>>;
+
+    fn api_keys(&self) -> &dyn ApiKeyRepository;
+    fn email_changes(&self) -> &dyn EmailChangeRepository;
+    fn email_verifications(&self) -> &dyn EmailVerificationRepository;
+    fn exercises(&self) -> &dyn ExerciseRepository;
+    fn exercise_targets(&self) -> &dyn ExerciseTargetRepository;
+    fn exercise_workouts(&self) -> &dyn ExerciseWorkoutRepository;
+    fn jobs(&self) -> &dyn JobRepository;
+    fn login_throttles(&self) -> &dyn LoginThrottleRepository;
+    fn oidc_logins(&self) -> &dyn OidcLoginRepository;
+    fn password_resets(&self) -> &dyn PasswordResetRepository;
+    fn recovery_codes(&self) -> &dyn RecoveryCodeRepository;
+    fn sets(&self) -> &dyn SetRepository;
+    fn targets(&self) -> &dyn TargetRepository;
+    fn tokens(&self) -> &dyn TokenRepository;
+    fn users(&self) -> &dyn UserRepository;
+    fn user_identities(&self) -> &dyn UserIdentityRepository;
+    fn workouts(&self) -> &dyn WorkoutRepository;
+}
+
+/// A database handle running everything in one transaction, see
+/// `Database::begin`
+#[async_trait::async_trait]
+pub trait Transaction: Database {
+    async fn commit(self: Box<Self>) -> Result<()>;
+}
+
+/// Lets `&state.db` be passed where a `&dyn Database` is expected, the
+/// compiler doesn't deref an `Arc` when coercing to a trait object.
+#[async_trait::async_trait]
+impl Database for Arc<dyn Database> {
+    fn backend(&self) -> Backend {
+        (**self).backend()
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        (**self).migrate().await
+    }
+
+    async fn migrations(&self) -> Result<Vec<MigrationInfo>> {
+        (**self).migrations().await
+    }
+
+    async fn revert_migration(&self) -> Result<Option<MigrationInfo>> {
+        (**self).revert_migration().await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        (**self).ping().await
+    }
+
+    fn pool_stats(&self) -> PoolStats {
+        (**self).pool_stats()
+    }
+
+    async fn close(&self) {
+        (**self).close().await
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Transaction>> {
+        (**self).begin().await
+    }
+
+    fn api_keys(&self) -> &dyn ApiKeyRepository {
+        (**self).api_keys()
+    }
+
+    fn email_changes(&self) -> &dyn EmailChangeRepository {
+        (**self).email_changes()
+    }
+
+    fn email_verifications(&self) -> &dyn EmailVerificationRepository {
+        (**self).email_verifications()
+    }
+
+    fn exercises(&self) -> &dyn ExerciseRepository {
+        (**self).exercises()
+    }
+
+    fn exercise_targets(&self) -> &dyn ExerciseTargetRepository {
+        (**self).exercise_targets()
+    }
+
+    fn exercise_workouts(&self) -> &dyn ExerciseWorkoutRepository {
+        (**self).exercise_workouts()
+    }
+
+    fn jobs(&self) -> &dyn JobRepository {
+        (**self).jobs()
+    }
+
+    fn login_throttles(&self) -> &dyn LoginThrottleRepository {
+        (**self).login_throttles()
+    }
+
+    fn oidc_logins(&self) -> &dyn OidcLoginRepository {
+        (**self).oidc_logins()
+    }
+
+    fn password_resets(&self) -> &dyn PasswordResetRepository {
+        (**self).password_resets()
+    }
+
+    fn recovery_codes(&self) -> &dyn RecoveryCodeRepository {
+        (**self).recovery_codes()
+    }
+
+    fn sets(&self) -> &dyn SetRepository {
+        (**self).sets()
+    }
+
+    fn targets(&self) -> &dyn TargetRepository {
+        (**self).targets()
+    }
+
+    fn tokens(&self) -> &dyn TokenRepository {
+        (**self).tokens()
+    }
+
+    fn users(&self) -> &dyn UserRepository {
+        (**self).users()
+    }
+
+    fn user_identities(&self) -> &dyn UserIdentityRepository {
+        (**self).user_identities()
+    }
+
+    fn workouts(&self) -> &dyn WorkoutRepository {
+        (**self).workouts()
+    }
+}
+
+/// Connects to the database `DATABASE_URL` points at, `mysql://...` or
+/// `sqlite:...` (`sqlite::memory:` for a throwaway in-memory one).
+pub async fn connect(config: &DatabaseConfig) -> Result<Arc<dyn Database>> {
+    match Backend::from_url(&config.url) {
+        Some(Backend::MySql) => Ok(Arc::new(MySqlDatabase::connect(config).await?)),
+        Some(Backend::Sqlite) => Ok(Arc::new(SqliteDatabase::connect(config).await?)),
+        None => Err(Error::Other(format!(
+            "Unsupported database URL {}, expected mysql:// or sqlite:",
+            crate::telemetry::redact_url(&config.url)
+        ))),
+    }
+}
+
+/// Lines up what a migrator knows about with what has been applied. A
+/// `.down.sql` is listed with the migration it undoes, not on its own.
+fn migration_infos(migrator: &Migrator, applied: &[i64]) -> Vec<MigrationInfo> {
+    let reversible = |version| {
+        migrator
+            .iter()
+            .any(|migration| migration.version == version && migration.migration_type.is_down_migration())
+    };
+
+    migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| MigrationInfo {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+            reversible: reversible(migration.version),
+        })
+        .collect()
+}
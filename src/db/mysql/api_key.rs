@@ -0,0 +1,85 @@
+use futures::{stream::BoxStream, StreamExt};
+
+use crate::{
+    error::{self, Result},
+    models::api_key::{ApiKey, ApiKeyRepository, NewApiKey},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl ApiKeyRepository for MySqlDatabase {
+    async fn insert(&self, key: NewApiKey<'_>) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO api_keys(id, user_id, name, prefix, value, scopes, expires_at) VALUE (?, ?, ?, ?, ?, ?, ?)",
+            key.id,
+            key.user_id,
+            key.name,
+            key.prefix,
+            key.hashed_value,
+            key.scopes,
+            key.expires_at
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<ApiKey>> {
+        sqlx::query_as!(ApiKey, "SELECT * FROM api_keys WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<ApiKey>> {
+        sqlx::query_as!(ApiKey, "SELECT * FROM api_keys WHERE user_id = ? ORDER BY created_at", user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn find_valid_by_value(&self, hashed_value: &str) -> Result<Option<ApiKey>> {
+        sqlx::query_as!(
+            ApiKey,
+            "SELECT * FROM api_keys WHERE value = ? AND (expires_at IS NULL OR expires_at > NOW()) LIMIT 1",
+            hashed_value
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_all_by_user_id(&self, user_id: &str) -> Result<Vec<ApiKey>> {
+        sqlx::query_as!(ApiKey, "SELECT * FROM api_keys WHERE user_id = ? ORDER BY created_at DESC", user_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn touch(&self, id: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE api_keys SET last_used_at = NOW() WHERE id = ?",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM api_keys WHERE id = ?",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
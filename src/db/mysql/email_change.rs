@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::email_change::{EmailChange, EmailChangeRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl EmailChangeRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, user_id: &str, new_email: &str, hashed_token: &str, ttl: Duration) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO email_changes(id, user_id, new_email, token, expires_at) VALUE (?, ?, ?, ?, NOW() + INTERVAL ? SECOND)",
+            id,
+            user_id,
+            new_email,
+            hashed_token,
+            ttl.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<EmailChange>> {
+        sqlx::query_as!(EmailChange, "SELECT * FROM email_changes WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_valid_by_token(&self, hashed_token: &str) -> Result<Option<EmailChange>> {
+        sqlx::query_as!(
+            EmailChange,
+            "SELECT * FROM email_changes WHERE token = ? AND expires_at > NOW() LIMIT 1",
+            hashed_token
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM email_changes WHERE user_id = ?",
+            user_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM email_changes WHERE expires_at < NOW()",
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::email_verification::{EmailVerification, EmailVerificationRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl EmailVerificationRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, user_id: &str, hashed_token: &str, ttl: Duration) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO email_verifications(id, user_id, token, expires_at) VALUE (?, ?, ?, NOW() + INTERVAL ? SECOND)",
+            id,
+            user_id,
+            hashed_token,
+            ttl.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<EmailVerification>> {
+        sqlx::query_as!(EmailVerification, "SELECT * FROM email_verifications WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_valid_by_token(&self, hashed_token: &str) -> Result<Option<EmailVerification>> {
+        sqlx::query_as!(
+            EmailVerification,
+            "SELECT * FROM email_verifications WHERE token = ? AND expires_at > NOW() LIMIT 1",
+            hashed_token
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM email_verifications WHERE user_id = ?",
+            user_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM email_verifications WHERE expires_at < NOW()",
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::{Connection, MySql, QueryBuilder};
+
+use crate::{
+    db::BULK_INSERT_ROWS,
+    error::{self, Result},
+    models::exercise_target::{ExerciseTarget, ExerciseTargetRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl ExerciseTargetRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, exercise_id: &str, target_id: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO exercise_target (id, exercise_id, target_id) VALUES (?, ?, ?)",
+            id,
+            exercise_id,
+            target_id,
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn insert_many(&self, exercise_targets: &[ExerciseTarget]) -> Result<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        for chunk in exercise_targets.chunks(BULK_INSERT_ROWS) {
+            QueryBuilder::<MySql>::new("INSERT INTO exercise_target(id, exercise_id, target_id) ")
+                .push_values(chunk, |mut row, exercise_target| {
+                    row.push_bind(&exercise_target.id)
+                        .push_bind(&exercise_target.exercise_id)
+                        .push_bind(&exercise_target.target_id);
+                })
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(error::from_sqlx_error)?;
+        }
+
+        tx.commit().await.map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<ExerciseTarget>> {
+        sqlx::query_as!(
+            ExerciseTarget,
+            "SELECT * FROM exercise_target WHERE id = ?",
+            id
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<ExerciseTarget>> {
+        sqlx::query_as!(ExerciseTarget, "SELECT * FROM exercise_target WHERE exercise_id IN (SELECT id FROM exercises WHERE user_id = ?)", user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn delete_by_exercise_id(&self, exercise_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM exercise_target WHERE exercise_id = ?",
+            exercise_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::Connection;
+
+use crate::{
+    error::{self, Result},
+    models::job::{Job, JobRepository, JobStatus},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl JobRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, kind: &str, payload: &str, max_attempts: i32) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO jobs(id, kind, payload, max_attempts) VALUE (?, ?, ?, ?)",
+            id,
+            kind,
+            payload,
+            max_attempts
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Job>> {
+        sqlx::query_as!(Job, "SELECT id, kind, payload, status AS `status: JobStatus`, attempts, max_attempts, run_at, locked_by, locked_at, last_error, created_at, updated_at FROM jobs WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn search(&self, status: Option<String>, limit: i64, offset: i64) -> Result<Vec<Job>> {
+        sqlx::query_as!(
+            Job,
+            "SELECT id, kind, payload, status AS `status: JobStatus`, attempts, max_attempts, run_at, locked_by, locked_at, last_error, created_at, updated_at FROM jobs WHERE (? IS NULL OR status = ?) ORDER BY created_at DESC LIMIT ? OFFSET ?",
+            status,
+            status,
+            limit,
+            offset
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn claim(&self, worker: &str) -> Result<Option<Job>> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        let job = sqlx::query_as!(
+            Job,
+            "SELECT id, kind, payload, status AS `status: JobStatus`, attempts, max_attempts, run_at, locked_by, locked_at, last_error, created_at, updated_at FROM jobs WHERE status = 'pending' AND run_at <= NOW() ORDER BY run_at LIMIT 1 FOR UPDATE SKIP LOCKED"
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        let Some(mut job) = job else {
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1, locked_by = ?, locked_at = NOW() WHERE id = ?",
+            worker,
+            job.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        tx.commit().await.map_err(error::from_sqlx_error)?;
+
+        job.status = JobStatus::Running;
+        job.attempts += 1;
+        job.locked_by = Some(worker.to_string());
+        job.locked_at = Some(Utc::now());
+
+        Ok(Some(job))
+    }
+
+    async fn complete(&self, id: &str, worker: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE jobs SET status = 'done', locked_by = NULL, locked_at = NULL WHERE id = ? AND locked_by = ?",
+            id,
+            worker
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn fail(&self, id: &str, worker: &str, status: JobStatus, delay: Duration, reason: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE jobs SET status = ?, run_at = NOW() + INTERVAL ? SECOND, last_error = ?, locked_by = NULL, locked_at = NULL WHERE id = ? AND locked_by = ?",
+            status.to_string(),
+            delay.as_secs(),
+            reason,
+            id,
+            worker
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn retry(&self, id: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE jobs SET status = 'pending', attempts = 0, run_at = NOW() WHERE id = ?",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn requeue_stuck(&self, older_than: Duration) -> Result<()> {
+        sqlx::query!(
+            "UPDATE jobs SET status = IF(attempts >= max_attempts, 'dead', 'pending'), last_error = 'Worker stopped responding', locked_by = NULL, locked_at = NULL WHERE status = 'running' AND locked_at < NOW() - INTERVAL ? SECOND",
+            older_than.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_finished(&self, older_than: Duration) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM jobs WHERE status = 'done' AND updated_at < NOW() - INTERVAL ? SECOND",
+            older_than.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::login_throttle::{LoginThrottle, LoginThrottleRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl LoginThrottleRepository for MySqlDatabase {
+    async fn find_by_key(&self, key: &str) -> Result<Option<LoginThrottle>> {
+        sqlx::query_as!(LoginThrottle, "SELECT * FROM login_throttles WHERE throttle_key = ? LIMIT 1", key)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn add_failure(&self, id: &str, key: &str, window: Duration) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO login_throttles(id, throttle_key, failures, last_failure_at) VALUE (?, ?, 1, NOW()) ON DUPLICATE KEY UPDATE failures = IF(last_failure_at < NOW() - INTERVAL ? SECOND, 1, failures + 1), last_failure_at = NOW()",
+            id,
+            key,
+            window.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn lock(&self, id: &str, lockout: Duration) -> Result<()> {
+        sqlx::query!(
+            "UPDATE login_throttles SET locked_until = NOW() + INTERVAL ? SECOND WHERE id = ?",
+            lockout.as_secs(),
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_by_key(&self, key: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM login_throttles WHERE throttle_key = ?",
+            key
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_stale(&self, older_than: Duration) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM login_throttles WHERE last_failure_at < NOW() - INTERVAL ? SECOND",
+            older_than.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
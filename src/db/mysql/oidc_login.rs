@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::oidc_login::{OidcLogin, OidcLoginRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl OidcLoginRepository for MySqlDatabase {
+    async fn insert(
+        &self,
+        id: &str,
+        hashed_state: &str,
+        nonce: &str,
+        code_verifier: &str,
+        device_name: Option<&str>,
+        ttl: Duration,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO oidc_logins(id, state, nonce, code_verifier, device_name, expires_at) VALUE (?, ?, ?, ?, ?, NOW() + INTERVAL ? SECOND)",
+            id,
+            hashed_state,
+            nonce,
+            code_verifier,
+            device_name,
+            ttl.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<OidcLogin>> {
+        sqlx::query_as!(OidcLogin, "SELECT * FROM oidc_logins WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_valid_by_state(&self, hashed_state: &str) -> Result<Option<OidcLogin>> {
+        sqlx::query_as!(
+            OidcLogin,
+            "SELECT * FROM oidc_logins WHERE state = ? AND expires_at > NOW() LIMIT 1",
+            hashed_state
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM oidc_logins WHERE id = ?",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_expired(&self) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM oidc_logins WHERE expires_at < NOW()",
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::password_reset::{PasswordReset, PasswordResetRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl PasswordResetRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, user_id: &str, hashed_code: &str, ttl: Duration) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO password_resets(id, user_id, code, expires_at) VALUE (?, ?, ?, NOW() + INTERVAL ? SECOND)",
+            id,
+            user_id,
+            hashed_code,
+            ttl.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<PasswordReset>> {
+        sqlx::query_as!(PasswordReset, "SELECT * FROM password_resets WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_valid_by_code(&self, hashed_code: &str) -> Result<Option<PasswordReset>> {
+        sqlx::query_as!(
+            PasswordReset,
+            "SELECT * FROM password_resets WHERE code = ? AND used_at IS NULL AND expires_at > NOW() LIMIT 1",
+            hashed_code
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn mark_used(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE password_resets SET used_at = NOW() WHERE id = ? AND used_at IS NULL",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM password_resets WHERE user_id = ?",
+            user_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM password_resets WHERE expires_at < NOW() OR used_at IS NOT NULL",
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{
+    error::{self, Result},
+    models::recovery_code::{RecoveryCode, RecoveryCodeRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl RecoveryCodeRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, user_id: &str, hashed_code: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO recovery_codes(id, user_id, code) VALUE (?, ?, ?)",
+            id,
+            user_id,
+            hashed_code
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_unused_by_user_id(&self, user_id: &str) -> Result<Vec<RecoveryCode>> {
+        sqlx::query_as!(
+            RecoveryCode,
+            "SELECT * FROM recovery_codes WHERE user_id = ? AND used_at IS NULL",
+            user_id
+        )
+        .fetch_all(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn mark_used(&self, user_id: &str, hashed_code: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE recovery_codes SET used_at = NOW() WHERE user_id = ? AND code = ? AND used_at IS NULL",
+            user_id,
+            hashed_code
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM recovery_codes WHERE user_id = ?",
+            user_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::{Connection, MySql, QueryBuilder};
+
+use crate::{
+    db::BULK_INSERT_ROWS,
+    error::{self, Result},
+    models::set::{Set, SetRepository, SetType},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl SetRepository for MySqlDatabase {
+    async fn insert(
+        &self,
+        id: &str,
+        user_id: &str,
+        exercise_workout_id: &str,
+        quality: f32,
+        quantity: f32,
+        set_type: &SetType,
+    ) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO sets(id, user_id, exercise_workout_id, quality, quantity, set_type) VALUE (?, ?, ?, ?, ?, ?)",
+            id,
+            user_id,
+            exercise_workout_id,
+            quality,
+            quantity,
+            set_type.to_string()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn insert_many(&self, sets: &[Set]) -> Result<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        for chunk in sets.chunks(BULK_INSERT_ROWS) {
+            QueryBuilder::<MySql>::new("INSERT INTO sets(id, user_id, exercise_workout_id, quality, quantity, note, set_type, created_at, updated_at) ")
+                .push_values(chunk, |mut row, set| {
+                    row.push_bind(&set.id)
+                        .push_bind(&set.user_id)
+                        .push_bind(&set.exercise_workout_id)
+                        .push_bind(set.quality)
+                        .push_bind(set.quantity)
+                        .push_bind(&set.note)
+                        .push_bind(set.set_type.to_string())
+                        .push_bind(set.created_at)
+                        .push_bind(set.updated_at);
+                })
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(error::from_sqlx_error)?;
+        }
+
+        tx.commit().await.map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Set>> {
+        sqlx::query_as!(Set, "SELECT * FROM sets WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Set>> {
+        sqlx::query_as!(Set, "SELECT * FROM sets WHERE user_id = ? ORDER BY created_at", user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn find_all_by_exercise_workout_id(&self, exercise_workout_id: &str) -> Result<Vec<Set>> {
+        sqlx::query_as!(Set, "SELECT * FROM sets WHERE exercise_workout_id = ? ORDER BY set_type ASC, created_at ASC", exercise_workout_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_all_by_user_id(&self, user_id: &str) -> Result<Vec<Set>> {
+        sqlx::query_as!(Set, "SELECT * FROM sets WHERE user_id = ? ORDER BY set_type ASC, created_at ASC", user_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn update(&self, set: &Set) -> Result<()> {
+        sqlx::query!(
+            "UPDATE sets SET quality = ?, quantity = ?, set_type = ? WHERE id = ?",
+            set.quality,
+            set.quantity,
+            set.set_type.to_string(),
+            set.id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM sets WHERE id = ?",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
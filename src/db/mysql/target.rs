@@ -0,0 +1,30 @@
+use crate::{
+    error::{self, Result},
+    models::target::{Target, TargetRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl TargetRepository for MySqlDatabase {
+    async fn all(&self) -> Result<Vec<Target>> {
+        sqlx::query_as!(Target, "SELECT * FROM targets ORDER BY sort ASC")
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Target>> {
+        sqlx::query_as!(Target, "SELECT * FROM targets WHERE id = ?", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn all_by_exercise_id(&self, exercise_id: &str) -> Result<Vec<Target>> {
+        sqlx::query_as!(Target, "SELECT * FROM targets WHERE id IN (SELECT target_id FROM exercise_target WHERE exercise_id = ?) ORDER BY sort ASC", exercise_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use futures::{stream::BoxStream, StreamExt};
+
+use crate::{
+    ctx::ClientInfo,
+    error::{self, Result},
+    models::token::{Token, TokenRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl TokenRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, user_id: &str, family_id: &str, hashed_value: &str, client: &ClientInfo) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO tokens(id, user_id, family_id, value, device_name, user_agent, ip, last_used_at) VALUE (?, ?, ?, ?, ?, ?, ?, NOW())",
+            id,
+            user_id,
+            family_id,
+            hashed_value,
+            client.device_name,
+            client.user_agent,
+            client.ip
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Token>> {
+        sqlx::query_as!(Token, "SELECT * FROM tokens WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Token>> {
+        sqlx::query_as!(Token, "SELECT * FROM tokens WHERE user_id = ? ORDER BY created_at", user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn find_by_value(&self, hashed_value: &str, ttl: Duration) -> Result<Option<Token>> {
+        sqlx::query_as!(Token, "SELECT * FROM tokens WHERE value = ? AND created_at > (NOW() - INTERVAL ? SECOND) LIMIT 1", hashed_value, ttl.as_secs())
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_active_by_family_id(&self, family_id: &str, ttl: Duration) -> Result<Option<Token>> {
+        sqlx::query_as!(Token, "SELECT * FROM tokens WHERE family_id = ? AND rotated_at IS NULL AND created_at > (NOW() - INTERVAL ? SECOND) LIMIT 1", family_id, ttl.as_secs())
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_all_active_by_user_id(&self, user_id: &str, ttl: Duration) -> Result<Vec<Token>> {
+        sqlx::query_as!(Token, "SELECT * FROM tokens WHERE user_id = ? AND rotated_at IS NULL AND created_at > (NOW() - INTERVAL ? SECOND) ORDER BY last_used_at DESC", user_id, ttl.as_secs())
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn mark_rotated(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE tokens SET rotated_at = NOW() WHERE id = ? AND rotated_at IS NULL",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn is_family_active(&self, family_id: &str, ttl: Duration) -> Result<bool> {
+        let active = sqlx::query_scalar!(
+            "SELECT tokens.id FROM tokens JOIN users ON users.id = tokens.user_id WHERE tokens.family_id = ? AND tokens.rotated_at IS NULL AND tokens.created_at > (NOW() - INTERVAL ? SECOND) AND users.disabled_at IS NULL LIMIT 1",
+            family_id,
+            ttl.as_secs()
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(active.is_some())
+    }
+
+    async fn touch_family(&self, family_id: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE tokens SET last_used_at = NOW() WHERE family_id = ? AND rotated_at IS NULL",
+            family_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_family(&self, family_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM tokens WHERE family_id = ?",
+            family_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_all_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM tokens WHERE user_id = ?",
+            user_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_all_by_user_id_except_family(&self, user_id: &str, family_id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM tokens WHERE user_id = ? AND family_id != ?",
+            user_id,
+            family_id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self, ttl: Duration) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM tokens WHERE created_at < (NOW() - INTERVAL ? SECOND)",
+            ttl.as_secs()
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
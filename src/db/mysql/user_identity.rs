@@ -0,0 +1,65 @@
+use futures::{stream::BoxStream, StreamExt};
+
+use crate::{
+    error::{self, Result},
+    models::user_identity::{UserIdentity, UserIdentityRepository},
+};
+
+use super::MySqlDatabase;
+
+#[async_trait::async_trait]
+impl UserIdentityRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, user_id: &str, issuer: &str, subject: &str, email: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO user_identities(id, user_id, issuer, subject, email, last_login_at) VALUE (?, ?, ?, ?, ?, NOW())",
+            id,
+            user_id,
+            issuer,
+            subject,
+            email
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<UserIdentity>> {
+        sqlx::query_as!(UserIdentity, "SELECT * FROM user_identities WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<UserIdentity>> {
+        sqlx::query_as!(UserIdentity, "SELECT * FROM user_identities WHERE user_id = ? ORDER BY created_at", user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn find_by_subject(&self, issuer: &str, subject: &str) -> Result<Option<UserIdentity>> {
+        sqlx::query_as!(
+            UserIdentity,
+            "SELECT * FROM user_identities WHERE issuer = ? AND subject = ? LIMIT 1",
+            issuer,
+            subject
+        )
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn touch(&self, id: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE user_identities SET last_login_at = NOW() WHERE id = ?",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
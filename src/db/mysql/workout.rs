@@ -0,0 +1,146 @@
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::{Connection, MySql, QueryBuilder};
+
+use crate::{
+    db::BULK_INSERT_ROWS,
+    error::{self, Result},
+    models::workout::{Workout, WorkoutRepository, WorkoutSort},
+    pagination::ListParams,
+};
+
+use super::{push_page, push_range, MySqlDatabase};
+
+#[async_trait::async_trait]
+impl WorkoutRepository for MySqlDatabase {
+    async fn insert(&self, id: &str, user_id: &str) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO workout(id, user_id) VALUE (?, ?)",
+            id,
+            user_id,
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn insert_many(&self, workouts: &[Workout]) -> Result<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        for chunk in workouts.chunks(BULK_INSERT_ROWS) {
+            QueryBuilder::<MySql>::new("INSERT INTO workout(id, user_id, status, created_at, updated_at) ")
+                .push_values(chunk, |mut row, workout| {
+                    row.push_bind(&workout.id)
+                        .push_bind(&workout.user_id)
+                        .push_bind(workout.status.to_string())
+                        .push_bind(workout.created_at)
+                        .push_bind(workout.updated_at);
+                })
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(error::from_sqlx_error)?;
+        }
+
+        tx.commit().await.map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Workout>> {
+        sqlx::query_as!(Workout, "SELECT * FROM workout WHERE id = ? LIMIT 1", id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Workout>> {
+        sqlx::query_as!(Workout, "SELECT * FROM workout WHERE user_id = ? ORDER BY created_at", user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn list_done_by_user_id(&self, user_id: &str, params: &ListParams<WorkoutSort>) -> Result<(Vec<Workout>, i64)> {
+        let filter = |query: &mut QueryBuilder<'_, MySql>| {
+            query.push(" WHERE user_id = ").push_bind(user_id.to_string()).push(" AND status = 'done'");
+            push_range(query, params);
+        };
+
+        let mut count = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM workout");
+        filter(&mut count);
+        let total = count
+            .build_query_scalar()
+            .fetch_one(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        let mut query = QueryBuilder::<MySql>::new("SELECT * FROM workout");
+        filter(&mut query);
+        push_page(&mut query, params);
+        let workouts = query
+            .build_query_as()
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok((workouts, total))
+    }
+
+    async fn find_current_by_user_id(&self, user_id: &str) -> Result<Option<Workout>> {
+        sqlx::query_as!(Workout, "SELECT * FROM workout WHERE user_id = ? and status = 'ongoing'", user_id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn finish(&self, id: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE workout SET status = 'done' WHERE id = ?",
+            id,
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn list_where_exercise_is_used(&self, exercise_id: &str, params: &ListParams<WorkoutSort>) -> Result<(Vec<Workout>, i64)> {
+        let filter = |query: &mut QueryBuilder<'_, MySql>| {
+            query.push(" WHERE id IN (SELECT workout_id FROM exercise_workout WHERE exercise_id = ").push_bind(exercise_id.to_string()).push(") AND status = 'done'");
+            push_range(query, params);
+        };
+
+        let mut count = QueryBuilder::<MySql>::new("SELECT COUNT(*) FROM workout");
+        filter(&mut count);
+        let total = count
+            .build_query_scalar()
+            .fetch_one(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        let mut query = QueryBuilder::<MySql>::new("SELECT * FROM workout");
+        filter(&mut query);
+        push_page(&mut query, params);
+        let workouts = query
+            .build_query_as()
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok((workouts, total))
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM workout WHERE id = ?",
+            id
+        )
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
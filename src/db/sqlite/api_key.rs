@@ -0,0 +1,83 @@
+use futures::{stream::BoxStream, StreamExt};
+
+use crate::{
+    error::{self, Result},
+    models::api_key::{ApiKey, ApiKeyRepository, NewApiKey},
+};
+
+use super::{now, timestamp, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl ApiKeyRepository for SqliteDatabase {
+    async fn insert(&self, key: NewApiKey<'_>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO api_keys(id, user_id, name, prefix, value, scopes, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(key.id)
+        .bind(key.user_id)
+        .bind(key.name)
+        .bind(key.prefix)
+        .bind(key.hashed_value)
+        .bind(key.scopes)
+        .bind(key.expires_at.map(timestamp))
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<ApiKey>> {
+        sqlx::query_as("SELECT * FROM api_keys WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<ApiKey>> {
+        sqlx::query_as("SELECT * FROM api_keys WHERE user_id = ? ORDER BY created_at")
+            .bind(user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn find_valid_by_value(&self, hashed_value: &str) -> Result<Option<ApiKey>> {
+        sqlx::query_as("SELECT * FROM api_keys WHERE value = ? AND (expires_at IS NULL OR expires_at > ?) LIMIT 1")
+            .bind(hashed_value)
+            .bind(now())
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_all_by_user_id(&self, user_id: &str) -> Result<Vec<ApiKey>> {
+        sqlx::query_as("SELECT * FROM api_keys WHERE user_id = ? ORDER BY created_at DESC")
+            .bind(user_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn touch(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE id = ?")
+            .bind(now())
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM api_keys WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::email_change::{EmailChange, EmailChangeRepository},
+};
+
+use super::{from_now, now, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl EmailChangeRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, user_id: &str, new_email: &str, hashed_token: &str, ttl: Duration) -> Result<()> {
+        sqlx::query("INSERT INTO email_changes(id, user_id, new_email, token, expires_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(id)
+            .bind(user_id)
+            .bind(new_email)
+            .bind(hashed_token)
+            .bind(from_now(ttl))
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<EmailChange>> {
+        sqlx::query_as("SELECT * FROM email_changes WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_valid_by_token(&self, hashed_token: &str) -> Result<Option<EmailChange>> {
+        sqlx::query_as("SELECT * FROM email_changes WHERE token = ? AND expires_at > ? LIMIT 1")
+            .bind(hashed_token)
+            .bind(now())
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM email_changes WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> Result<()> {
+        sqlx::query("DELETE FROM email_changes WHERE expires_at < ?")
+            .bind(now())
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::{Connection, QueryBuilder, Sqlite};
+
+use crate::{
+    db::BULK_INSERT_ROWS,
+    error::{self, Result},
+    models::exercise::{Exercise, ExerciseRepository, ExerciseSort, ExerciseType},
+    pagination::ListParams,
+};
+
+use super::{push_page, push_range, timestamp, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl ExerciseRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, user_id: &str, name: &str, exercise_type: &ExerciseType) -> Result<()> {
+        sqlx::query("INSERT INTO exercises(id, user_id, name, exercise_type) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(user_id)
+            .bind(name)
+            .bind(exercise_type.to_string())
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn insert_many(&self, exercises: &[Exercise]) -> Result<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        for chunk in exercises.chunks(BULK_INSERT_ROWS) {
+            QueryBuilder::<Sqlite>::new("INSERT INTO exercises(id, user_id, name, exercise_type, created_at, updated_at) ")
+                .push_values(chunk, |mut row, exercise| {
+                    row.push_bind(&exercise.id)
+                        .push_bind(&exercise.user_id)
+                        .push_bind(&exercise.name)
+                        .push_bind(exercise.exercise_type.to_string())
+                        .push_bind(timestamp(exercise.created_at))
+                        .push_bind(timestamp(exercise.updated_at));
+                })
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(error::from_sqlx_error)?;
+        }
+
+        tx.commit().await.map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Exercise>> {
+        sqlx::query_as("SELECT * FROM exercises WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Exercise>> {
+        sqlx::query_as("SELECT * FROM exercises WHERE user_id = ? ORDER BY created_at")
+            .bind(user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn list_by_user_id(&self, user_id: &str, params: &ListParams<ExerciseSort>) -> Result<(Vec<Exercise>, i64)> {
+        let filter = |query: &mut QueryBuilder<'_, Sqlite>| {
+            query.push(" WHERE user_id = ").push_bind(user_id.to_string());
+            push_range(query, params);
+        };
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM exercises");
+        filter(&mut count);
+        let total = count
+            .build_query_scalar()
+            .fetch_one(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM exercises");
+        filter(&mut query);
+        push_page(&mut query, params);
+        let exercises = query
+            .build_query_as()
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok((exercises, total))
+    }
+
+    async fn update(&self, exercise: &Exercise) -> Result<()> {
+        sqlx::query("UPDATE exercises SET name = ?, exercise_type = ? WHERE id = ?")
+            .bind(&exercise.name)
+            .bind(exercise.exercise_type.to_string())
+            .bind(&exercise.id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM exercises WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::{Connection, QueryBuilder, Sqlite};
+
+use crate::{
+    db::BULK_INSERT_ROWS,
+    error::{self, Result},
+    models::exercise_workout::{ExerciseWorkout, ExerciseWorkoutRepository},
+};
+
+use super::{timestamp, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl ExerciseWorkoutRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, user_id: &str, exercise_id: &str, workout_id: &str) -> Result<()> {
+        sqlx::query("INSERT INTO exercise_workout(id, user_id, exercise_id, workout_id) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(user_id)
+            .bind(exercise_id)
+            .bind(workout_id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn insert_many(&self, exercise_workouts: &[ExerciseWorkout]) -> Result<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        for chunk in exercise_workouts.chunks(BULK_INSERT_ROWS) {
+            QueryBuilder::<Sqlite>::new("INSERT INTO exercise_workout(id, user_id, exercise_id, workout_id, created_at, updated_at) ")
+                .push_values(chunk, |mut row, exercise_workout| {
+                    row.push_bind(&exercise_workout.id)
+                        .push_bind(&exercise_workout.user_id)
+                        .push_bind(&exercise_workout.exercise_id)
+                        .push_bind(&exercise_workout.workout_id)
+                        .push_bind(timestamp(exercise_workout.created_at))
+                        .push_bind(timestamp(exercise_workout.updated_at));
+                })
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(error::from_sqlx_error)?;
+        }
+
+        tx.commit().await.map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<ExerciseWorkout>> {
+        sqlx::query_as("SELECT * FROM exercise_workout WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<ExerciseWorkout>> {
+        sqlx::query_as("SELECT * FROM exercise_workout WHERE user_id = ? ORDER BY created_at")
+            .bind(user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn find_all_by_exercise_and_workout_id(&self, exercise_id: &str, workout_id: &str) -> Result<Vec<ExerciseWorkout>> {
+        sqlx::query_as("SELECT * FROM exercise_workout WHERE exercise_id = ? AND workout_id = ?")
+            .bind(exercise_id)
+            .bind(workout_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_all_by_workout_id(&self, workout_id: &str) -> Result<Vec<ExerciseWorkout>> {
+        sqlx::query_as("SELECT * FROM exercise_workout WHERE workout_id = ? ORDER BY created_at DESC")
+            .bind(workout_id)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM exercise_workout WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
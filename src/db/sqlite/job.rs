@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::job::{Job, JobRepository, JobStatus},
+};
+
+use super::{ago, from_now, now, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl JobRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, kind: &str, payload: &str, max_attempts: i32) -> Result<()> {
+        sqlx::query("INSERT INTO jobs(id, kind, payload, max_attempts) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(kind)
+            .bind(payload)
+            .bind(max_attempts)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Job>> {
+        sqlx::query_as("SELECT * FROM jobs WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn search(&self, status: Option<String>, limit: i64, offset: i64) -> Result<Vec<Job>> {
+        sqlx::query_as(
+            "SELECT * FROM jobs WHERE (?1 IS NULL OR status = ?1) ORDER BY created_at DESC LIMIT ?2 OFFSET ?3",
+        )
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    // SQLite has no row locks, but writers are serialized, so picking and
+    // marking the job in one statement is enough to keep workers apart
+    async fn claim(&self, worker: &str) -> Result<Option<Job>> {
+        let now = now();
+
+        sqlx::query_as(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1, locked_by = ?1, locked_at = ?2 WHERE id = (SELECT id FROM jobs WHERE status = 'pending' AND run_at <= ?2 ORDER BY run_at LIMIT 1) RETURNING *",
+        )
+        .bind(worker)
+        .bind(now)
+        .fetch_optional(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)
+    }
+
+    async fn complete(&self, id: &str, worker: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE jobs SET status = 'done', locked_by = NULL, locked_at = NULL WHERE id = ? AND locked_by = ?")
+            .bind(id)
+            .bind(worker)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn fail(&self, id: &str, worker: &str, status: JobStatus, delay: Duration, reason: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE jobs SET status = ?, run_at = ?, last_error = ?, locked_by = NULL, locked_at = NULL WHERE id = ? AND locked_by = ?",
+        )
+        .bind(status.to_string())
+        .bind(from_now(delay))
+        .bind(reason)
+        .bind(id)
+        .bind(worker)
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn retry(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE jobs SET status = 'pending', attempts = 0, run_at = ? WHERE id = ?")
+            .bind(now())
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn requeue_stuck(&self, older_than: Duration) -> Result<()> {
+        sqlx::query(
+            "UPDATE jobs SET status = CASE WHEN attempts >= max_attempts THEN 'dead' ELSE 'pending' END, last_error = 'Worker stopped responding', locked_by = NULL, locked_at = NULL WHERE status = 'running' AND locked_at < ?",
+        )
+        .bind(ago(older_than))
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_finished(&self, older_than: Duration) -> Result<()> {
+        sqlx::query("DELETE FROM jobs WHERE status = 'done' AND updated_at < ?")
+            .bind(ago(older_than))
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::login_throttle::{LoginThrottle, LoginThrottleRepository},
+};
+
+use super::{ago, from_now, now, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl LoginThrottleRepository for SqliteDatabase {
+    async fn find_by_key(&self, key: &str) -> Result<Option<LoginThrottle>> {
+        sqlx::query_as("SELECT * FROM login_throttles WHERE throttle_key = ? LIMIT 1")
+            .bind(key)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn add_failure(&self, id: &str, key: &str, window: Duration) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO login_throttles(id, throttle_key, failures, last_failure_at) VALUES (?, ?, 1, ?) ON CONFLICT(throttle_key) DO UPDATE SET failures = CASE WHEN last_failure_at < ? THEN 1 ELSE failures + 1 END, last_failure_at = excluded.last_failure_at",
+        )
+        .bind(id)
+        .bind(key)
+        .bind(now())
+        .bind(ago(window))
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn lock(&self, id: &str, lockout: Duration) -> Result<()> {
+        sqlx::query("UPDATE login_throttles SET locked_until = ? WHERE id = ?")
+            .bind(from_now(lockout))
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_by_key(&self, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM login_throttles WHERE throttle_key = ?")
+            .bind(key)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_stale(&self, older_than: Duration) -> Result<()> {
+        sqlx::query("DELETE FROM login_throttles WHERE last_failure_at < ?")
+            .bind(ago(older_than))
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
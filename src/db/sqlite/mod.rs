@@ -0,0 +1,356 @@
+use std::{
+    ops::{Deref, DerefMut},
+    str::FromStr,
+    time::Duration,
+};
+
+use chrono::Utc;
+use sqlx::{
+    migrate::Migrator,
+    pool::PoolConnection,
+    sqlite::{SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePoolOptions},
+    Connection, Pool, QueryBuilder, Sqlite,
+};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{
+    config::DatabaseConfig,
+    error::{self, Error, Result},
+    models::{
+        api_key::ApiKeyRepository, email_change::EmailChangeRepository,
+        email_verification::EmailVerificationRepository, exercise::ExerciseRepository,
+        exercise_target::ExerciseTargetRepository, exercise_workout::ExerciseWorkoutRepository,
+        job::JobRepository, login_throttle::LoginThrottleRepository, oidc_login::OidcLoginRepository,
+        password_reset::PasswordResetRepository, recovery_code::RecoveryCodeRepository,
+        set::SetRepository, target::TargetRepository, token::TokenRepository, user::UserRepository,
+        user_identity::UserIdentityRepository, workout::WorkoutRepository,
+    },
+    pagination::{ListParams, Order, SortField, SortValue},
+};
+
+use super::{migration_infos, Backend, Database, MigrationInfo, PoolStats, Transaction};
+
+mod api_key;
+mod email_change;
+mod email_verification;
+mod exercise;
+mod exercise_target;
+mod exercise_workout;
+mod job;
+mod login_throttle;
+mod oidc_login;
+mod password_reset;
+mod recovery_code;
+mod set;
+mod target;
+mod token;
+mod user;
+mod user_identity;
+mod workout;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+/// SQLite, for running the API on a laptop or in CI without a database
+/// server. Either a file (`sqlite://workout.db`) or `sqlite::memory:`.
+pub struct SqliteDatabase {
+    pool: Pool<Sqlite>,
+    /// Set on the handles `begin` returns, queries then run in it
+    tx: Option<Mutex<sqlx::Transaction<'static, Sqlite>>>,
+}
+
+/// The connection a query runs on
+enum Conn<'a> {
+    Pool(PoolConnection<Sqlite>),
+    Tx(MutexGuard<'a, sqlx::Transaction<'static, Sqlite>>),
+}
+
+impl Deref for Conn<'_> {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Pool(conn) => conn,
+            Self::Tx(tx) => tx,
+        }
+    }
+}
+
+impl DerefMut for Conn<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Pool(conn) => conn,
+            Self::Tx(tx) => tx,
+        }
+    }
+}
+
+impl SqliteDatabase {
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(&config.url)
+            .map_err(error::from_sqlx_error)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+
+        // Every connection to `sqlite::memory:` would otherwise see its own
+        // empty database, and the data is gone once the last one closes
+        let in_memory = config.url.contains(":memory:") || config.url.contains("mode=memory");
+        let pool = if in_memory {
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            SqlitePoolOptions::new()
+                .max_connections(config.max_connections)
+                .min_connections(config.min_connections)
+        };
+
+        let options = if in_memory {
+            options
+        } else {
+            options.journal_mode(SqliteJournalMode::Wal)
+        };
+
+        let pool = pool
+            .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+            .connect_with(options)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(Self { pool, tx: None })
+    }
+
+    /// The open transaction of this handle, or a connection from the pool
+    async fn conn(&self) -> Result<Conn<'_>> {
+        match &self.tx {
+            Some(tx) => Ok(Conn::Tx(tx.lock().await)),
+            None => self.pool.acquire().await.map(Conn::Pool).map_err(error::from_sqlx_error),
+        }
+    }
+}
+
+/// SQLite has no date type, timestamps are stored as text in this format so
+/// they compare correctly. It's also what the column defaults write.
+fn timestamp(at: chrono::DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+fn now() -> String {
+    timestamp(Utc::now())
+}
+
+fn from_now(duration: Duration) -> String {
+    timestamp(Utc::now() + duration)
+}
+
+fn ago(duration: Duration) -> String {
+    timestamp(Utc::now() - duration)
+}
+
+/// Appends the `from`/`to` filters of a list, after a `WHERE`
+fn push_range<S>(query: &mut QueryBuilder<'_, Sqlite>, params: &ListParams<S>) {
+    if let Some(from) = params.from {
+        query.push(" AND created_at >= ").push_bind(timestamp(from));
+    }
+    if let Some(to) = params.to {
+        query.push(" AND created_at <= ").push_bind(timestamp(to));
+    }
+}
+
+/// Appends the cursor, ordering and limit of a list. Ties on the sort
+/// column are broken by id, so no row is skipped or repeated between pages.
+fn push_page<S: SortField>(query: &mut QueryBuilder<'_, Sqlite>, params: &ListParams<S>) {
+    let column = params.sort.column();
+    let (cmp, dir) = match params.order {
+        Order::Asc => (">", "ASC"),
+        Order::Desc => ("<", "DESC"),
+    };
+
+    if let Some((value, id)) = &params.after {
+        let value = match value {
+            SortValue::Time(at) => timestamp(*at),
+            SortValue::Text(text) => text.clone(),
+        };
+
+        query
+            .push(format!(" AND ({column} {cmp} "))
+            .push_bind(value.clone())
+            .push(format!(" OR ({column} = "))
+            .push_bind(value)
+            .push(format!(" AND id {cmp} "))
+            .push_bind(id.clone())
+            .push("))");
+    }
+
+    query
+        .push(format!(" ORDER BY {column} {dir}, id {dir} LIMIT "))
+        .push_bind(params.limit + 1);
+}
+
+#[async_trait::async_trait]
+impl Database for SqliteDatabase {
+    fn backend(&self) -> Backend {
+        Backend::Sqlite
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|err| Error::Other(format!("Failed to run migrations: {err}")))
+    }
+
+    async fn migrations(&self) -> Result<Vec<MigrationInfo>> {
+        // Only reads, the readiness probe calls this and must not create
+        // the migrations table on a database that was never migrated
+        let mut conn = self.pool.acquire().await.map_err(error::from_sqlx_error)?;
+        let tables: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(error::from_sqlx_error)?;
+        let applied: Vec<i64> = if tables == 0 {
+            Vec::new()
+        } else {
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations")
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(|err| Error::Other(format!("Can't read applied migrations: {err}")))?
+        };
+
+        Ok(migration_infos(&MIGRATOR, &applied))
+    }
+
+    async fn revert_migration(&self) -> Result<Option<MigrationInfo>> {
+        let migrations = self.migrations().await?;
+        let Some(last) = migrations.iter().rev().find(|migration| migration.applied).cloned() else {
+            return Ok(None);
+        };
+        if !last.reversible {
+            return Err(Error::Other(format!("Migration {} has no down migration", last.version)));
+        }
+
+        // Undoes everything applied after the target, which is just `last`
+        let target = migrations
+            .iter()
+            .rev()
+            .find(|migration| migration.applied && migration.version < last.version)
+            .map_or(0, |migration| migration.version);
+        MIGRATOR
+            .undo(&self.pool, target)
+            .await
+            .map_err(|err| Error::Other(format!("Failed to revert migration {}: {err}", last.version)))?;
+
+        Ok(Some(last))
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let mut conn = self.pool.acquire().await.map_err(error::from_sqlx_error)?;
+
+        conn.ping().await.map_err(error::from_sqlx_error)
+    }
+
+    fn pool_stats(&self) -> PoolStats {
+        PoolStats {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max: self.pool.options().get_max_connections(),
+        }
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Transaction>> {
+        if self.tx.is_some() {
+            return Err(Error::WTF("Transactions don't nest".to_string()));
+        }
+
+        let tx = self.pool.begin().await.map_err(error::from_sqlx_error)?;
+
+        Ok(Box::new(Self {
+            pool: self.pool.clone(),
+            tx: Some(Mutex::new(tx)),
+        }))
+    }
+
+    fn api_keys(&self) -> &dyn ApiKeyRepository {
+        self
+    }
+
+    fn email_changes(&self) -> &dyn EmailChangeRepository {
+        self
+    }
+
+    fn email_verifications(&self) -> &dyn EmailVerificationRepository {
+        self
+    }
+
+    fn exercises(&self) -> &dyn ExerciseRepository {
+        self
+    }
+
+    fn exercise_targets(&self) -> &dyn ExerciseTargetRepository {
+        self
+    }
+
+    fn exercise_workouts(&self) -> &dyn ExerciseWorkoutRepository {
+        self
+    }
+
+    fn jobs(&self) -> &dyn JobRepository {
+        self
+    }
+
+    fn login_throttles(&self) -> &dyn LoginThrottleRepository {
+        self
+    }
+
+    fn oidc_logins(&self) -> &dyn OidcLoginRepository {
+        self
+    }
+
+    fn password_resets(&self) -> &dyn PasswordResetRepository {
+        self
+    }
+
+    fn recovery_codes(&self) -> &dyn RecoveryCodeRepository {
+        self
+    }
+
+    fn sets(&self) -> &dyn SetRepository {
+        self
+    }
+
+    fn targets(&self) -> &dyn TargetRepository {
+        self
+    }
+
+    fn tokens(&self) -> &dyn TokenRepository {
+        self
+    }
+
+    fn users(&self) -> &dyn UserRepository {
+        self
+    }
+
+    fn user_identities(&self) -> &dyn UserIdentityRepository {
+        self
+    }
+
+    fn workouts(&self) -> &dyn WorkoutRepository {
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Transaction for SqliteDatabase {
+    async fn commit(self: Box<Self>) -> Result<()> {
+        let Some(tx) = self.tx else {
+            return Err(Error::WTF("Committed a database that isn't a transaction".to_string()));
+        };
+
+        tx.into_inner().commit().await.map_err(error::from_sqlx_error)
+    }
+}
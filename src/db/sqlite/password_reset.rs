@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use crate::{
+    error::{self, Result},
+    models::password_reset::{PasswordReset, PasswordResetRepository},
+};
+
+use super::{from_now, now, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl PasswordResetRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, user_id: &str, hashed_code: &str, ttl: Duration) -> Result<()> {
+        sqlx::query("INSERT INTO password_resets(id, user_id, code, expires_at) VALUES (?, ?, ?, ?)")
+            .bind(id)
+            .bind(user_id)
+            .bind(hashed_code)
+            .bind(from_now(ttl))
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<PasswordReset>> {
+        sqlx::query_as("SELECT * FROM password_resets WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_valid_by_code(&self, hashed_code: &str) -> Result<Option<PasswordReset>> {
+        sqlx::query_as("SELECT * FROM password_resets WHERE code = ? AND used_at IS NULL AND expires_at > ? LIMIT 1")
+            .bind(hashed_code)
+            .bind(now())
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn mark_used(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE password_resets SET used_at = ? WHERE id = ? AND used_at IS NULL")
+            .bind(now())
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM password_resets WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> Result<()> {
+        sqlx::query("DELETE FROM password_resets WHERE expires_at < ? OR used_at IS NOT NULL")
+            .bind(now())
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
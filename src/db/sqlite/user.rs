@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use sqlx::{Connection, QueryBuilder, Sqlite};
+
+use crate::{
+    db::BULK_INSERT_ROWS,
+    error::{self, Result},
+    models::user::{Role, User, UserRepository, UserUsage},
+};
+
+use super::{now, timestamp, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl UserRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, email: &str, hashed_password: Option<&str>) -> Result<()> {
+        sqlx::query("INSERT INTO users(id, email, password) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(email)
+            .bind(hashed_password)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn insert_many(&self, users: &[User]) -> Result<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        for chunk in users.chunks(BULK_INSERT_ROWS) {
+            QueryBuilder::<Sqlite>::new("INSERT INTO users(id, email, password, role, disabled_at, email_verified_at, totp_secret, totp_enabled_at, totp_last_step, created_at, updated_at) ")
+                .push_values(chunk, |mut row, user| {
+                    row.push_bind(&user.id)
+                        .push_bind(&user.email)
+                        .push_bind(&user.password)
+                        .push_bind(user.role.to_string())
+                        .push_bind(user.disabled_at.map(timestamp))
+                        .push_bind(user.email_verified_at.map(timestamp))
+                        .push_bind(&user.totp_secret)
+                        .push_bind(user.totp_enabled_at.map(timestamp))
+                        .push_bind(user.totp_last_step)
+                        .push_bind(timestamp(user.created_at))
+                        .push_bind(timestamp(user.updated_at));
+                })
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(error::from_sqlx_error)?;
+        }
+
+        tx.commit().await.map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>> {
+        sqlx::query_as("SELECT * FROM users WHERE email = ? LIMIT 1")
+            .bind(email)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>> {
+        sqlx::query_as("SELECT * FROM users WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn search(&self, pattern: &str, limit: i64, offset: i64) -> Result<Vec<User>> {
+        sqlx::query_as("SELECT * FROM users WHERE email LIKE ? ESCAPE '\\' ORDER BY created_at DESC LIMIT ? OFFSET ?")
+            .bind(pattern)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn update(&self, user: &User) -> Result<()> {
+        sqlx::query("UPDATE users SET email = ?, password = ? WHERE id = ?")
+            .bind(&user.email)
+            .bind(&user.password)
+            .bind(&user.id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM users WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn mark_email_verified(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET email_verified_at = ? WHERE id = ?")
+            .bind(now())
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn set_role(&self, id: &str, role: Role) -> Result<()> {
+        sqlx::query("UPDATE users SET role = ? WHERE id = ?")
+            .bind(role.to_string())
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn set_disabled(&self, id: &str, disabled: bool) -> Result<()> {
+        sqlx::query("UPDATE users SET disabled_at = ? WHERE id = ?")
+            .bind(disabled.then(now))
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn usage(&self, ids: &[String]) -> Result<HashMap<String, UserUsage>> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT user_id, SUM(workouts), SUM(sets) FROM (\
+             SELECT user_id, 1 AS workouts, 0 AS sets FROM workout WHERE user_id IN ",
+        );
+        push_ids(&mut query, ids);
+        query.push(" UNION ALL SELECT user_id, 0, 1 FROM sets WHERE user_id IN ");
+        push_ids(&mut query, ids);
+        query.push(") AS counts GROUP BY user_id");
+
+        let rows: Vec<(String, i64, i64)> = query
+            .build_query_as()
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, workouts, sets)| (id, UserUsage { workouts, sets }))
+            .collect())
+    }
+
+    async fn set_totp_secret(&self, id: &str, secret: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET totp_secret = ?, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = ?")
+            .bind(secret)
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn enable_totp(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET totp_enabled_at = ? WHERE id = ?")
+            .bind(now())
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn disable_totp(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn record_totp_step(&self, id: &str, step: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET totp_last_step = ? WHERE id = ? AND (totp_last_step IS NULL OR totp_last_step < ?)",
+        )
+        .bind(step)
+        .bind(id)
+        .bind(step)
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+fn push_ids(query: &mut QueryBuilder<'_, Sqlite>, ids: &[String]) {
+    query.push("(");
+    let mut separated = query.separated(", ");
+    for id in ids {
+        separated.push_bind(id.clone());
+    }
+    separated.push_unseparated(")");
+}
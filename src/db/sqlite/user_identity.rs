@@ -0,0 +1,64 @@
+use futures::{stream::BoxStream, StreamExt};
+
+use crate::{
+    error::{self, Result},
+    models::user_identity::{UserIdentity, UserIdentityRepository},
+};
+
+use super::{now, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl UserIdentityRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, user_id: &str, issuer: &str, subject: &str, email: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO user_identities(id, user_id, issuer, subject, email, last_login_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(issuer)
+        .bind(subject)
+        .bind(email)
+        .bind(now())
+        .execute(&mut *self.conn().await?)
+        .await
+        .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<UserIdentity>> {
+        sqlx::query_as("SELECT * FROM user_identities WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<UserIdentity>> {
+        sqlx::query_as("SELECT * FROM user_identities WHERE user_id = ? ORDER BY created_at")
+            .bind(user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn find_by_subject(&self, issuer: &str, subject: &str) -> Result<Option<UserIdentity>> {
+        sqlx::query_as("SELECT * FROM user_identities WHERE issuer = ? AND subject = ? LIMIT 1")
+            .bind(issuer)
+            .bind(subject)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn touch(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE user_identities SET last_login_at = ? WHERE id = ?")
+            .bind(now())
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
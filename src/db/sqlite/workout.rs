@@ -0,0 +1,143 @@
+use futures::{stream::BoxStream, StreamExt};
+use sqlx::{Connection, QueryBuilder, Sqlite};
+
+use crate::{
+    db::BULK_INSERT_ROWS,
+    error::{self, Result},
+    models::workout::{Workout, WorkoutRepository, WorkoutSort},
+    pagination::ListParams,
+};
+
+use super::{push_page, push_range, timestamp, SqliteDatabase};
+
+#[async_trait::async_trait]
+impl WorkoutRepository for SqliteDatabase {
+    async fn insert(&self, id: &str, user_id: &str) -> Result<()> {
+        sqlx::query("INSERT INTO workout(id, user_id) VALUES (?, ?)")
+            .bind(id)
+            .bind(user_id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn insert_many(&self, workouts: &[Workout]) -> Result<()> {
+        let mut conn = self.conn().await?;
+        let mut tx = conn.begin().await.map_err(error::from_sqlx_error)?;
+
+        for chunk in workouts.chunks(BULK_INSERT_ROWS) {
+            QueryBuilder::<Sqlite>::new("INSERT INTO workout(id, user_id, status, created_at, updated_at) ")
+                .push_values(chunk, |mut row, workout| {
+                    row.push_bind(&workout.id)
+                        .push_bind(&workout.user_id)
+                        .push_bind(workout.status.to_string())
+                        .push_bind(timestamp(workout.created_at))
+                        .push_bind(timestamp(workout.updated_at));
+                })
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(error::from_sqlx_error)?;
+        }
+
+        tx.commit().await.map_err(error::from_sqlx_error)
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Workout>> {
+        sqlx::query_as("SELECT * FROM workout WHERE id = ? LIMIT 1")
+            .bind(id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Workout>> {
+        sqlx::query_as("SELECT * FROM workout WHERE user_id = ? ORDER BY created_at")
+            .bind(user_id)
+            .fetch(&self.pool)
+            .map(|row| row.map_err(error::from_sqlx_error))
+            .boxed()
+    }
+
+    async fn list_done_by_user_id(&self, user_id: &str, params: &ListParams<WorkoutSort>) -> Result<(Vec<Workout>, i64)> {
+        let filter = |query: &mut QueryBuilder<'_, Sqlite>| {
+            query.push(" WHERE user_id = ").push_bind(user_id.to_string()).push(" AND status = 'done'");
+            push_range(query, params);
+        };
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM workout");
+        filter(&mut count);
+        let total = count
+            .build_query_scalar()
+            .fetch_one(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM workout");
+        filter(&mut query);
+        push_page(&mut query, params);
+        let workouts = query
+            .build_query_as()
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok((workouts, total))
+    }
+
+    async fn find_current_by_user_id(&self, user_id: &str) -> Result<Option<Workout>> {
+        sqlx::query_as("SELECT * FROM workout WHERE user_id = ? AND status = 'ongoing'")
+            .bind(user_id)
+            .fetch_optional(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)
+    }
+
+    async fn finish(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE workout SET status = 'done' WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn list_where_exercise_is_used(&self, exercise_id: &str, params: &ListParams<WorkoutSort>) -> Result<(Vec<Workout>, i64)> {
+        let filter = |query: &mut QueryBuilder<'_, Sqlite>| {
+            query.push(" WHERE id IN (SELECT workout_id FROM exercise_workout WHERE exercise_id = ").push_bind(exercise_id.to_string()).push(") AND status = 'done'");
+            push_range(query, params);
+        };
+
+        let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM workout");
+        filter(&mut count);
+        let total = count
+            .build_query_scalar()
+            .fetch_one(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM workout");
+        filter(&mut query);
+        push_page(&mut query, params);
+        let workouts = query
+            .build_query_as()
+            .fetch_all(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok((workouts, total))
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM workout WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.conn().await?)
+            .await
+            .map_err(error::from_sqlx_error)?;
+
+        Ok(())
+    }
+}
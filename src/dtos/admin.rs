@@ -0,0 +1,96 @@
+use chrono::Utc;
+use validator::Validate;
+
+use crate::models::{
+    job::{Job, JobStatus},
+    user::{Role, User, UserUsage},
+};
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct SearchUsersQuery {
+    /// Part of the email
+    #[validate(length(max = 100, message = "must be at most 100 characters"))]
+    pub search: Option<String>,
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0, message = "must not be negative"))]
+    pub offset: Option<i64>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct SetRolePayload {
+    pub role: Role,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct AdminUserResponse {
+    pub id: String,
+    pub email: String,
+    pub role: Role,
+    pub email_verified_at: Option<chrono::DateTime<Utc>>,
+    pub two_factor_enabled: bool,
+    pub disabled_at: Option<chrono::DateTime<Utc>>,
+    pub usage: UserUsage,
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+impl AdminUserResponse {
+    pub fn new(user: User, usage: UserUsage) -> Self {
+        Self {
+            two_factor_enabled: user.is_totp_enabled(),
+            id: user.id,
+            email: user.email,
+            role: user.role,
+            email_verified_at: user.email_verified_at,
+            disabled_at: user.disabled_at,
+            usage,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct SearchJobsQuery {
+    pub status: Option<JobStatus>,
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0, message = "must not be negative"))]
+    pub offset: Option<i64>,
+}
+
+/// A queued job without its payload, which holds ids and email addresses
+/// that the admin view doesn't need.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct JobResponse {
+    pub id: String,
+    pub kind: String,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: chrono::DateTime<Utc>,
+    pub locked_by: Option<String>,
+    pub locked_at: Option<chrono::DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+impl From<Job> for JobResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id,
+            kind: job.kind,
+            status: job.status,
+            attempts: job.attempts,
+            max_attempts: job.max_attempts,
+            run_at: job.run_at,
+            locked_by: job.locked_by,
+            locked_at: job.locked_at,
+            last_error: job.last_error,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
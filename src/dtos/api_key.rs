@@ -0,0 +1,56 @@
+use chrono::Utc;
+use validator::{Validate, ValidationError};
+
+use crate::models::api_key::{ApiKey, Scope};
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct CreateApiKeyPayload {
+    #[validate(length(min = 1, max = 100, message = "must be between 1 and 100 characters"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "must have at least one scope"))]
+    pub scopes: Vec<Scope>,
+    /// Never expires when left out
+    #[validate(custom(function = "in_the_future"))]
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+}
+
+fn in_the_future(expires_at: &chrono::DateTime<Utc>) -> Result<(), ValidationError> {
+    if *expires_at <= Utc::now() {
+        return Err(ValidationError::new("in_the_future").with_message("must be in the future".into()));
+    }
+
+    Ok(())
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub last_used_at: Option<chrono::DateTime<Utc>>,
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(value: ApiKey) -> Self {
+        Self {
+            scopes: value.scopes(),
+            id: value.id,
+            name: value.name,
+            prefix: value.prefix,
+            last_used_at: value.last_used_at,
+            expires_at: value.expires_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct CreatedApiKeyResponse {
+    /// Shown once, only the hash is stored
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
@@ -1,19 +1,112 @@
 use chrono::Utc;
+use validator::Validate;
 
-use crate::models::user::User;
+use crate::models::{
+    token::Token,
+    user::{Role, User},
+};
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
 pub struct CreateUserPayload {
+    #[validate(email(message = "must be a valid email"), length(max = 100, message = "must be at most 100 characters"))]
+    pub email: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    pub password: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct LoginPayload {
+    #[validate(length(max = 100, message = "must be at most 100 characters"))]
+    pub email: String,
+    #[validate(length(max = 128, message = "must be at most 128 characters"))]
+    pub password: String,
+    /// Shown in the session list
+    #[validate(length(max = 100, message = "must be at most 100 characters"))]
+    pub device_name: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct RefreshTokenPayload {
+    pub refresh_token: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct ForgotPasswordPayload {
+    pub email: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct ResendVerificationPayload {
     pub email: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct ResetPasswordPayload {
+    pub code: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    pub password: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct ChangePasswordPayload {
+    /// Left out by accounts without a password, which set their first one
+    #[serde(default)]
+    pub current_password: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    pub new_password: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct ChangeEmailPayload {
+    /// The new address, only used once it has been confirmed
+    #[validate(email(message = "must be a valid email"), length(max = 100, message = "must be at most 100 characters"))]
+    pub email: String,
+    /// Left out by accounts without a password
+    #[serde(default)]
+    pub password: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct LoginTwoFactorPayload {
+    pub challenge: String,
+    /// Code from the authenticator app or one of the recovery codes
+    pub code: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct TwoFactorCodePayload {
+    pub code: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct DisableTwoFactorPayload {
+    /// Left out by accounts without a password
+    #[serde(default)]
     pub password: String,
+    pub code: String,
 }
 
-pub type LoginPayload = CreateUserPayload;
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct OidcAuthorizeQuery {
+    #[validate(length(max = 100, message = "must be at most 100 characters"))]
+    pub device_name: Option<String>,
+}
+
+/// Sent by the identity provider, either `code` and `state` or `error`
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct UserResponse {
     id: String,
     email: String,
+    role: Role,
+    email_verified_at: Option<chrono::DateTime<Utc>>,
+    two_factor_enabled: bool,
     created_at: chrono::DateTime<Utc>,
     updated_at: chrono::DateTime<Utc>,
 }
@@ -21,8 +114,11 @@ pub struct UserResponse {
 impl From<User> for UserResponse {
     fn from(value: User) -> Self {
         Self {
+            two_factor_enabled: value.is_totp_enabled(),
             id: value.id.to_string(),
             email: value.email,
+            role: value.role,
+            email_verified_at: value.email_verified_at,
             created_at: value.created_at,
             updated_at: value.updated_at,
         }
@@ -31,6 +127,71 @@ impl From<User> for UserResponse {
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct LoginResponse {
+    /// Short-lived access token, sent in the `Authorization` header
     pub token: String,
+    pub token_expires_at: chrono::DateTime<Utc>,
+    /// Single-use token for `/api/auth/refresh`
+    pub refresh_token: String,
     pub user: UserResponse,
 }
+
+/// Returned by `/api/auth/login` instead of tokens when the user has 2FA
+/// enabled. The challenge is exchanged at `/api/auth/login/2fa`.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct LoginChallengeResponse {
+    pub challenge: String,
+    pub challenge_expires_at: chrono::DateTime<Utc>,
+    pub two_factor_required: bool,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[serde(untagged)]
+pub enum LoginResult {
+    Tokens(LoginResponse),
+    Challenge(LoginChallengeResponse),
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct TwoFactorSetupResponse {
+    /// Base32 secret, for apps that can't scan the URI
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    pub enabled_at: Option<chrono::DateTime<Utc>>,
+    pub recovery_codes_left: usize,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct RecoveryCodesResponse {
+    /// Shown once, only hashes are stored
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct SessionResponse {
+    pub id: String,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub current: bool,
+    pub last_used_at: Option<chrono::DateTime<Utc>>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+impl SessionResponse {
+    pub fn from_token(token: Token, current_session_id: &str) -> Self {
+        Self {
+            current: token.family_id == current_session_id,
+            id: token.family_id,
+            device_name: token.device_name,
+            user_agent: token.user_agent,
+            ip: token.ip,
+            last_used_at: token.last_used_at,
+            created_at: token.created_at,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::Utc;
+use validator::Validate;
 
 use crate::models::{
     exercise::{Exercise, ExerciseType},
@@ -8,14 +9,16 @@ use crate::models::{
 
 use super::target::TargetResponse;
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
 pub struct CreateExercisePayload {
+    #[validate(length(min = 1, max = 64, message = "must be between 1 and 64 characters"))]
     pub name: String,
     pub exercise_type: ExerciseType,
+    #[validate(length(max = 20, message = "must have at most 20 targets"))]
     pub targets: Vec<String>, // ids
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
 pub struct ExerciseHistoryPayload {
     pub workout_id: String,
     pub workout_date: chrono::DateTime<Utc>,
@@ -23,7 +26,7 @@ pub struct ExerciseHistoryPayload {
     pub groups: Vec<ExerciseGroupHistoryPayload>,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
 pub struct ExerciseGroupHistoryPayload {
     pub start_date: chrono::DateTime<Utc>,
     pub sets: Vec<Set>,
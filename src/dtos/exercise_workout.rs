@@ -1,4 +1,6 @@
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+use validator::Validate;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
 pub struct CreateExerciseWorkoutPayload {
     pub exercise_id: String,
 }
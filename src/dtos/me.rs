@@ -0,0 +1,42 @@
+use chrono::Utc;
+use validator::Validate;
+
+use crate::models::token::Token;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct DeleteAccountPayload {
+    /// Left out by accounts without a password
+    #[serde(default)]
+    pub password: String,
+    /// Required when 2FA is enabled
+    pub code: Option<String>,
+}
+
+/// A refresh token in the data export. The token value is left out, it's a
+/// credential and not personal data.
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct TokenExport {
+    pub id: String,
+    pub session_id: String,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub last_used_at: Option<chrono::DateTime<Utc>>,
+    pub rotated_at: Option<chrono::DateTime<Utc>>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+impl From<Token> for TokenExport {
+    fn from(value: Token) -> Self {
+        Self {
+            id: value.id,
+            session_id: value.family_id,
+            device_name: value.device_name,
+            user_agent: value.user_agent,
+            ip: value.ip,
+            last_used_at: value.last_used_at,
+            rotated_at: value.rotated_at,
+            created_at: value.created_at,
+        }
+    }
+}
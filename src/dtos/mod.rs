@@ -1,6 +1,10 @@
+pub mod admin;
+pub mod api_key;
 pub mod auth;
 pub mod exercise;
 pub mod exercise_workout;
+pub mod me;
+pub mod pagination;
 pub mod set;
 pub mod target;
 pub mod workout;
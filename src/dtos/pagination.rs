@@ -0,0 +1,78 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use validator::Validate;
+
+use crate::{
+    error::{Error, Result},
+    pagination::{self, ListParams, Order, SortField, DEFAULT_LIMIT},
+    response::FieldErrors,
+};
+
+/// Query string of list endpoints, e.g.
+/// `?limit=20&from=2024-01-01&sort=created_at&order=asc&cursor=...`
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
+pub struct ListQuery<S> {
+    #[validate(range(min = 1, max = 100, message = "must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// RFC 3339 timestamp or a date, which starts at midnight UTC
+    pub from: Option<String>,
+    /// RFC 3339 timestamp or a date, which includes the whole day
+    pub to: Option<String>,
+    pub sort: Option<S>,
+    pub order: Option<Order>,
+}
+
+impl<S: SortField + Default> ListQuery<S> {
+    pub fn into_params(self) -> Result<ListParams<S>> {
+        let mut errors = FieldErrors::new();
+
+        let from = self.from.as_deref().and_then(|from| {
+            let parsed = parse_time(from, NaiveTime::MIN);
+            if parsed.is_none() {
+                errors.insert("from".to_string(), vec!["must be a date or an RFC 3339 timestamp".to_string()]);
+            }
+            parsed
+        });
+        let to = self.to.as_deref().and_then(|to| {
+            let parsed = parse_time(to, NaiveTime::from_hms_milli_opt(23, 59, 59, 999).unwrap());
+            if parsed.is_none() {
+                errors.insert("to".to_string(), vec!["must be a date or an RFC 3339 timestamp".to_string()]);
+            }
+            parsed
+        });
+
+        let sort = self.sort.unwrap_or_default();
+        let after = self.cursor.as_deref().and_then(|cursor| {
+            let decoded = pagination::decode_cursor(sort, cursor);
+            if decoded.is_none() {
+                errors.insert("cursor".to_string(), vec!["is invalid for this sort".to_string()]);
+            }
+            decoded
+        });
+
+        if !errors.is_empty() {
+            return Err(Error::Validation(errors));
+        }
+
+        Ok(ListParams {
+            limit: self.limit.unwrap_or(DEFAULT_LIMIT),
+            after,
+            from,
+            to,
+            sort,
+            order: self.order.unwrap_or(sort.default_order()),
+        })
+    }
+}
+
+/// A timestamp as is, a date at `time` of that day
+fn parse_time(value: &str, time: NaiveTime) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Some(at.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_time(time).and_utc())
+}
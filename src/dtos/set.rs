@@ -1,16 +1,22 @@
+use validator::Validate;
+
 use crate::models::set::SetType;
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
 pub struct CreateSetPayload {
     pub exercise_workout_id: String,
+    #[validate(range(min = 0.0, max = 100000.0, message = "must be between 0 and 100000"))]
     pub quality: f32,
+    #[validate(range(min = 0.0, max = 100000.0, message = "must be between 0 and 100000"))]
     pub quantity: f32,
     pub set_type: SetType,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
 pub struct UpdateSetPayload {
+    #[validate(range(min = 0.0, max = 100000.0, message = "must be between 0 and 100000"))]
     pub quality: f32,
+    #[validate(range(min = 0.0, max = 100000.0, message = "must be between 0 and 100000"))]
     pub quantity: f32,
     pub set_type: SetType,
 }
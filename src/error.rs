@@ -1,23 +1,86 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
-use serde_json::json;
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 
-use crate::response::Response;
+use crate::response::{FieldErrors, Response};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// What a missing or foreign item is, so clients can tell a missing
+/// workout from a missing exercise without reading the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Workout,
+    ExerciseWorkout,
+    Exercise,
+    Set,
+    ApiKey,
+    Session,
+    User,
+    OidcProvider,
+    Job,
+}
+
+impl Resource {
+    /// First part of the error code, e.g. `workout` in `workout.not_found`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Workout => "workout",
+            Self::ExerciseWorkout => "exercise_workout",
+            Self::Exercise => "exercise",
+            Self::Set => "set",
+            Self::ApiKey => "api_key",
+            Self::Session => "session",
+            Self::User => "user",
+            Self::OidcProvider => "oidc",
+            Self::Job => "job",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum AuthError {
     LoginFailed,
+    WrongPassword,
+    AccountDisabled,
+    AdminOnly,
+    CannotModifySelf,
+    /// Seconds until the next attempt is allowed
+    TooManyLoginAttempts(u64),
+    /// Too many mails asked for, seconds until the next request is allowed
+    TooManyMailRequests(u64),
     EmailAlreadyInUse(String),
     InvalidToken,
-    NotYourItem,
+    TokenExpired,
+    RefreshTokenReused,
+    InvalidResetCode,
+    InvalidVerificationToken,
+    EmailNotVerified,
+    InvalidChallenge,
+    TwoFactorFailed,
+    TwoFactorAlreadyEnabled,
+    TwoFactorNotEnabled,
+    MissingScope,
+    OidcFailed,
+    RegistrationClosed,
+    NotYourItem(Resource),
 }
 
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Error {
     Sql(sqlx::error::Error),
     AuthError(AuthError),
-    NotFound(String),
+    /// The resource and a description of what was looked up
+    NotFound(Resource, String),
+    /// The item exists but can't be changed like that right now
+    InvalidState(Resource, String),
+    /// The request body was readable but didn't pass validation
+    Validation(FieldErrors),
+    /// The request body couldn't be read at all, e.g. broken JSON
+    BadRequest(String),
     Other(String),
     WTF(String),
 }
@@ -31,10 +94,31 @@ impl Error {
         match self {
             Self::AuthError(AuthError::EmailAlreadyInUse(_)) => StatusCode::CONFLICT,
             Self::AuthError(AuthError::LoginFailed) => StatusCode::UNAUTHORIZED,
+            Self::AuthError(AuthError::WrongPassword) => StatusCode::FORBIDDEN,
+            Self::AuthError(AuthError::AccountDisabled) => StatusCode::FORBIDDEN,
+            Self::AuthError(AuthError::AdminOnly) => StatusCode::FORBIDDEN,
+            Self::AuthError(AuthError::CannotModifySelf) => StatusCode::CONFLICT,
+            Self::AuthError(AuthError::TooManyLoginAttempts(_)) => StatusCode::TOO_MANY_REQUESTS,
+            Self::AuthError(AuthError::TooManyMailRequests(_)) => StatusCode::TOO_MANY_REQUESTS,
             Self::AuthError(AuthError::InvalidToken) => StatusCode::UNAUTHORIZED,
-            Self::AuthError(AuthError::NotYourItem) => StatusCode::FORBIDDEN,
+            Self::AuthError(AuthError::TokenExpired) => StatusCode::UNAUTHORIZED,
+            Self::AuthError(AuthError::RefreshTokenReused) => StatusCode::UNAUTHORIZED,
+            Self::AuthError(AuthError::InvalidResetCode) => StatusCode::BAD_REQUEST,
+            Self::AuthError(AuthError::InvalidVerificationToken) => StatusCode::BAD_REQUEST,
+            Self::AuthError(AuthError::EmailNotVerified) => StatusCode::FORBIDDEN,
+            Self::AuthError(AuthError::InvalidChallenge) => StatusCode::UNAUTHORIZED,
+            Self::AuthError(AuthError::TwoFactorFailed) => StatusCode::UNAUTHORIZED,
+            Self::AuthError(AuthError::TwoFactorAlreadyEnabled) => StatusCode::CONFLICT,
+            Self::AuthError(AuthError::TwoFactorNotEnabled) => StatusCode::BAD_REQUEST,
+            Self::AuthError(AuthError::MissingScope) => StatusCode::FORBIDDEN,
+            Self::AuthError(AuthError::OidcFailed) => StatusCode::UNAUTHORIZED,
+            Self::AuthError(AuthError::RegistrationClosed) => StatusCode::FORBIDDEN,
+            Self::AuthError(AuthError::NotYourItem(_)) => StatusCode::FORBIDDEN,
             Self::Sql(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::NotFound(..) => StatusCode::NOT_FOUND,
+            Self::InvalidState(..) => StatusCode::CONFLICT,
+            Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
             Self::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::WTF(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -43,31 +127,211 @@ impl Error {
     pub fn message(&self) -> &'static str {
         match self {
             Self::AuthError(AuthError::LoginFailed) => "Unauthorized",
+            Self::AuthError(AuthError::WrongPassword) => "Wrong password",
+            Self::AuthError(AuthError::AccountDisabled) => "Account disabled",
+            Self::AuthError(AuthError::AdminOnly) => "Only admins can do this",
+            Self::AuthError(AuthError::CannotModifySelf) => "Admins can't do this to their own account",
+            Self::AuthError(AuthError::TooManyLoginAttempts(_)) => "Too many login attempts",
+            Self::AuthError(AuthError::TooManyMailRequests(_)) => "Too many mails requested",
             Self::AuthError(AuthError::InvalidToken) => "Missing token",
+            Self::AuthError(AuthError::TokenExpired) => "Token expired",
+            Self::AuthError(AuthError::RefreshTokenReused) => "Refresh token already used, session revoked",
+            Self::AuthError(AuthError::InvalidResetCode) => "Invalid or expired reset code",
+            Self::AuthError(AuthError::InvalidVerificationToken) => "Invalid or expired verification token",
+            Self::AuthError(AuthError::EmailNotVerified) => "Email not verified",
+            Self::AuthError(AuthError::InvalidChallenge) => "Invalid or expired login challenge",
+            Self::AuthError(AuthError::TwoFactorFailed) => "Invalid two-factor code",
+            Self::AuthError(AuthError::TwoFactorAlreadyEnabled) => "Two-factor authentication already enabled",
+            Self::AuthError(AuthError::TwoFactorNotEnabled) => "Two-factor authentication not set up",
             Self::AuthError(AuthError::EmailAlreadyInUse(_)) => "Email already in use",
-            Self::AuthError(AuthError::NotYourItem) => "You do not own this entity",
-            Self::NotFound(_) => "Not Found",
+            Self::AuthError(AuthError::MissingScope) => "API key is not allowed to do this",
+            Self::AuthError(AuthError::OidcFailed) => "External login failed",
+            Self::AuthError(AuthError::RegistrationClosed) => "No account is linked to this identity",
+            Self::AuthError(AuthError::NotYourItem(_)) => "You do not own this entity",
+            Self::NotFound(..) => "Not Found",
+            Self::InvalidState(..) => "Not possible in the current state",
+            Self::Validation(_) => "Validation failed",
+            Self::BadRequest(_) => "Malformed request body",
             Self::Sql(_) | Self::Other(_) | Self::WTF(_) => "Internal server error",
         }
     }
+
+    /// Stable, machine readable identifier of the error. Unlike `message`
+    /// these never change once released.
+    pub fn code(&self) -> String {
+        let code = match self {
+            Self::AuthError(AuthError::LoginFailed) => "auth.login_failed",
+            Self::AuthError(AuthError::WrongPassword) => "auth.wrong_password",
+            Self::AuthError(AuthError::AccountDisabled) => "auth.account_disabled",
+            Self::AuthError(AuthError::AdminOnly) => "auth.admin_only",
+            Self::AuthError(AuthError::CannotModifySelf) => "auth.cannot_modify_self",
+            Self::AuthError(AuthError::TooManyLoginAttempts(_)) => "auth.too_many_login_attempts",
+            Self::AuthError(AuthError::TooManyMailRequests(_)) => "auth.too_many_mail_requests",
+            Self::AuthError(AuthError::EmailAlreadyInUse(_)) => "auth.email_already_in_use",
+            Self::AuthError(AuthError::InvalidToken) => "auth.invalid_token",
+            Self::AuthError(AuthError::TokenExpired) => "auth.token_expired",
+            Self::AuthError(AuthError::RefreshTokenReused) => "auth.refresh_token_reused",
+            Self::AuthError(AuthError::InvalidResetCode) => "auth.invalid_reset_code",
+            Self::AuthError(AuthError::InvalidVerificationToken) => "auth.invalid_verification_token",
+            Self::AuthError(AuthError::EmailNotVerified) => "auth.email_not_verified",
+            Self::AuthError(AuthError::InvalidChallenge) => "auth.invalid_challenge",
+            Self::AuthError(AuthError::TwoFactorFailed) => "auth.two_factor_failed",
+            Self::AuthError(AuthError::TwoFactorAlreadyEnabled) => "auth.two_factor_already_enabled",
+            Self::AuthError(AuthError::TwoFactorNotEnabled) => "auth.two_factor_not_enabled",
+            Self::AuthError(AuthError::MissingScope) => "auth.missing_scope",
+            Self::AuthError(AuthError::OidcFailed) => "auth.oidc_failed",
+            Self::AuthError(AuthError::RegistrationClosed) => "auth.registration_closed",
+            Self::AuthError(AuthError::NotYourItem(resource)) => return format!("{}.not_owned", resource.code()),
+            Self::NotFound(resource, _) => return format!("{}.not_found", resource.code()),
+            Self::InvalidState(resource, _) => return format!("{}.invalid_state", resource.code()),
+            Self::Validation(_) => "request.invalid",
+            Self::BadRequest(_) => "request.malformed",
+            Self::Sql(_) | Self::Other(_) | Self::WTF(_) => "internal",
+        };
+
+        code.to_string()
+    }
+
+    /// Specifics of this occurrence of the error that are safe to show the
+    /// client. Internal errors never have any.
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            Self::AuthError(AuthError::EmailAlreadyInUse(email)) => Some(format!("Email {email} already in use")),
+            Self::AuthError(AuthError::TooManyLoginAttempts(seconds) | AuthError::TooManyMailRequests(seconds)) => {
+                Some(format!("Try again in {seconds} seconds"))
+            }
+            Self::NotFound(_, what) => Some(format!("{what} not found")),
+            Self::InvalidState(_, reason) => Some(reason.clone()),
+            Self::BadRequest(reason) => Some(reason.clone()),
+            _ => None,
+        }
+    }
+
+    /// Per-field problems to send back with the error
+    pub fn field_errors(&self) -> Option<FieldErrors> {
+        match self {
+            Self::Validation(errors) => Some(errors.clone()),
+            Self::BadRequest(reason) => Some(FieldErrors::from([("body".to_string(), vec![reason.clone()])])),
+            _ => None,
+        }
+    }
+
+    /// Value for the `Retry-After` header, in seconds
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::AuthError(AuthError::TooManyLoginAttempts(seconds) | AuthError::TooManyMailRequests(seconds)) => {
+                Some(*seconds)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
-        println!("Returning error: {self:?}");
+        // Client mistakes are routine, only server side failures are errors
+        if self.status_code().is_server_error() {
+            tracing::error!(error = %self, "Request failed");
+        } else {
+            tracing::debug!(error = %self, "Request rejected");
+        }
+
+        let problem = Problem::from(&self);
+
+        let mut body = Response::<()>::failure(self.message()).with_code(problem.code.clone());
+        if let Some(detail) = problem.detail.clone() {
+            body = body.with_detail(detail);
+        }
+        if let Some(errors) = problem.errors.clone() {
+            body = body.with_errors(errors);
+        }
 
-        (
-            self.status_code(),
-            Json(Response::<()>::failure(&self.message())),
-        )
-            .into_response()
+        let mut response = (self.status_code(), Json(body)).into_response();
+        // Swapped in for the body by the `problem_json` middleware when the
+        // client asks for it
+        response.extensions_mut().insert(problem);
+
+        if let Some(seconds) = self.retry_after() {
+            response.headers_mut().insert(RETRY_AFTER, seconds.into());
+        }
+
+        response
+    }
+}
+
+/// RFC 7807 problem details for an error, sent as `application/problem+json`
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<FieldErrors>,
+}
+
+impl From<&Error> for Problem {
+    fn from(error: &Error) -> Self {
+        let code = error.code();
+
+        Self {
+            kind: format!("urn:workout-backend:error:{code}"),
+            title: error.message().to_string(),
+            status: error.status_code().as_u16(),
+            code,
+            detail: error.detail(),
+            errors: error.field_errors(),
+        }
+    }
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmailAlreadyInUse(email) => write!(fmt, "Email {email} already in use"),
+            _ => write!(fmt, "{self:?}"),
+        }
     }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(fmt, "{self:?}")
+        match self {
+            Self::Sql(err) => write!(fmt, "Sql({err})"),
+            Self::AuthError(err) => write!(fmt, "AuthError({err})"),
+            Self::NotFound(resource, what) => write!(fmt, "NotFound({}, {what})", resource.code()),
+            Self::InvalidState(resource, reason) => write!(fmt, "InvalidState({}, {reason})", resource.code()),
+            Self::Validation(errors) => write!(fmt, "Validation({errors:?})"),
+            Self::BadRequest(reason) => write!(fmt, "BadRequest({reason})"),
+            Self::Other(message) => write!(fmt, "Other({message})"),
+            Self::WTF(message) => write!(fmt, "WTF({message})"),
+        }
     }
 }
 
 impl std::error::Error for Error {}
+
+impl From<validator::ValidationErrors> for Error {
+    fn from(value: validator::ValidationErrors) -> Self {
+        let errors = value
+            .field_errors()
+            .into_iter()
+            .map(|(field, errors)| {
+                let messages = errors
+                    .iter()
+                    .map(|error| match &error.message {
+                        Some(message) => message.to_string(),
+                        None => error.code.to_string(),
+                    })
+                    .collect();
+
+                (field.to_string(), messages)
+            })
+            .collect();
+
+        Self::Validation(errors)
+    }
+}
@@ -0,0 +1,100 @@
+//! The "download your data" document, served by `GET /api/me/export` and
+//! written by `workout_admin export`.
+
+use std::sync::Arc;
+
+use futures::{channel::mpsc, stream::BoxStream, SinkExt, StreamExt};
+use tracing::Instrument;
+
+use crate::{
+    db::Database,
+    dtos::{api_key::ApiKeyResponse, auth::UserResponse, me::TokenExport},
+    error::{Error, Result},
+    models::{
+        api_key::ApiKey, exercise::Exercise, exercise_target::ExerciseTarget,
+        exercise_workout::ExerciseWorkout, set::Set, token::Token, user::User,
+        user_identity::UserIdentity, workout::Workout,
+    },
+};
+
+pub type Chunk = std::result::Result<Vec<u8>, Error>;
+
+/// Everything stored about `user` as one JSON document. Rows are written
+/// out as they are read, so large histories never have to fit in memory.
+/// A failure ends the stream with an error instead of truncated JSON.
+pub fn stream(db: Arc<dyn Database>, user: User) -> mpsc::Receiver<Chunk> {
+    let (mut tx, rx) = mpsc::channel::<Chunk>(16);
+
+    // Keeps the export's queries and errors in the caller's span
+    let span = tracing::Span::current();
+    tokio::spawn(
+        async move {
+            if let Err(err) = write_export(&db, user, &mut tx).await {
+                tracing::error!(error = %err, "Failed to export user data");
+                let _ = tx.send(Err(err)).await;
+            }
+        }
+        .instrument(span),
+    );
+
+    rx
+}
+
+async fn write_export(db: &dyn Database, user: User, tx: &mut mpsc::Sender<Chunk>) -> Result<()> {
+    let user_id = user.id.clone();
+
+    send(tx, b"{\"exported_at\":".to_vec()).await?;
+    send(tx, to_json(&chrono::Utc::now())?).await?;
+    send(tx, b",\"user\":".to_vec()).await?;
+    send(tx, to_json(&UserResponse::from(user))?).await?;
+
+    write_section(tx, "exercises", Exercise::stream_by_user_id(db, user_id.clone())).await?;
+    write_section(tx, "exercise_targets", ExerciseTarget::stream_by_user_id(db, user_id.clone())).await?;
+    write_section(tx, "workouts", Workout::stream_by_user_id(db, user_id.clone())).await?;
+    write_section(tx, "exercise_workouts", ExerciseWorkout::stream_by_user_id(db, user_id.clone())).await?;
+    write_section(tx, "sets", Set::stream_by_user_id(db, user_id.clone())).await?;
+    write_section(
+        tx,
+        "sessions",
+        Token::stream_by_user_id(db, user_id.clone()).map(|token| token.map(TokenExport::from)).boxed(),
+    )
+    .await?;
+    write_section(tx, "identities", UserIdentity::stream_by_user_id(db, user_id.clone())).await?;
+    write_section(
+        tx,
+        "api_keys",
+        ApiKey::stream_by_user_id(db, user_id).map(|key| key.map(ApiKeyResponse::from)).boxed(),
+    )
+    .await?;
+
+    send(tx, b"}".to_vec()).await
+}
+
+/// Writes `,"name":[...]` with one row at a time
+async fn write_section<T: serde::Serialize>(
+    tx: &mut mpsc::Sender<Chunk>,
+    name: &str,
+    mut rows: BoxStream<'_, Result<T>>,
+) -> Result<()> {
+    send(tx, format!(",\"{name}\":[").into_bytes()).await?;
+
+    let mut first = true;
+    while let Some(row) = rows.next().await {
+        let mut chunk = if first { Vec::new() } else { b",".to_vec() };
+        chunk.extend(to_json(&row?)?);
+        send(tx, chunk).await?;
+        first = false;
+    }
+
+    send(tx, b"]".to_vec()).await
+}
+
+async fn send(tx: &mut mpsc::Sender<Chunk>, chunk: Vec<u8>) -> Result<()> {
+    tx.send(Ok(chunk))
+        .await
+        .map_err(|_| Error::Other("Reader went away during export".to_string()))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|err| Error::Other(format!("Failed to serialize export: {err}")))
+}
@@ -0,0 +1,432 @@
+//! Months or years of plausible training for many users at once, for load
+//! tests and demos. The same options always produce the same rows, ids
+//! included, so runs against different builds can be compared.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeZone, Utc, Weekday};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    models::{
+        exercise::{Exercise, ExerciseType},
+        exercise_target::ExerciseTarget,
+        exercise_workout::ExerciseWorkout,
+        set::{Set, SetType},
+        target::Target,
+        user::{Role, User},
+        workout::{Workout, WorkoutStatus},
+    },
+    seeder::PASSWORD_HASH,
+};
+
+/// Sets buffered before everything is written out
+const FLUSH_SETS: usize = 50_000;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub users: u32,
+    /// How far back every history goes
+    pub weeks: u32,
+    pub seed: u64,
+    /// Last day of the histories. Timestamps are derived from it, so the
+    /// same rows need the same day.
+    pub until: NaiveDate,
+}
+
+/// Rows written per table
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub users: usize,
+    pub exercises: usize,
+    pub workouts: usize,
+    pub exercise_workouts: usize,
+    pub sets: usize,
+}
+
+/// How an exercise is loaded and how that load grows
+enum Load {
+    /// Working weight in kg, raised by `step` after a session where every
+    /// set hit the target reps
+    Weight { kg: (f32, f32), step: f32 },
+    /// Distance in km at a pace in seconds per km, both improve slowly
+    Distance { km: (f32, f32), pace: (f32, f32) },
+    /// Weight in kg held for a number of seconds, the hold gets longer
+    Hold { kg: (f32, f32), seconds: (f32, f32) },
+}
+
+struct Template {
+    name: &'static str,
+    targets: &'static [&'static str],
+    load: Load,
+}
+
+impl Template {
+    fn exercise_type(&self) -> ExerciseType {
+        match self.load {
+            Load::Weight { .. } => ExerciseType::WeightOverAmount,
+            Load::Distance { .. } => ExerciseType::DistanceOverTime,
+            Load::Hold { .. } => ExerciseType::Static,
+        }
+    }
+}
+
+const TEMPLATES: &[Template] = &[
+    Template { name: "Bench press", targets: &["Chest", "Triceps"], load: Load::Weight { kg: (30.0, 70.0), step: 2.5 } },
+    Template { name: "Squats", targets: &["Thighs"], load: Load::Weight { kg: (40.0, 90.0), step: 2.5 } },
+    Template { name: "Deadlift", targets: &["Lower back", "Thighs"], load: Load::Weight { kg: (50.0, 100.0), step: 5.0 } },
+    Template { name: "Overhead press", targets: &["Shoulders", "Triceps"], load: Load::Weight { kg: (20.0, 45.0), step: 2.5 } },
+    Template { name: "Barbell row", targets: &["Upper back", "Biceps"], load: Load::Weight { kg: (30.0, 60.0), step: 2.5 } },
+    Template { name: "Pull-down", targets: &["Upper back", "Biceps"], load: Load::Weight { kg: (30.0, 55.0), step: 2.5 } },
+    Template { name: "Biceps curl", targets: &["Biceps", "Lower arms"], load: Load::Weight { kg: (8.0, 16.0), step: 1.0 } },
+    Template { name: "Triceps extension", targets: &["Triceps"], load: Load::Weight { kg: (10.0, 25.0), step: 1.0 } },
+    Template { name: "Calf raises", targets: &["Calfs"], load: Load::Weight { kg: (30.0, 60.0), step: 5.0 } },
+    Template { name: "Running", targets: &["Cardio"], load: Load::Distance { km: (3.0, 8.0), pace: (300.0, 420.0) } },
+    Template { name: "Cycling", targets: &["Cardio", "Thighs"], load: Load::Distance { km: (10.0, 30.0), pace: (120.0, 180.0) } },
+    Template { name: "Rowing", targets: &["Cardio", "Upper back"], load: Load::Distance { km: (2.0, 5.0), pace: (240.0, 300.0) } },
+    Template { name: "Plank", targets: &["Core"], load: Load::Hold { kg: (0.0, 0.0), seconds: (30.0, 60.0) } },
+    Template { name: "Counter rotation", targets: &["Core"], load: Load::Hold { kg: (10.0, 25.0), seconds: (20.0, 40.0) } },
+    Template { name: "Wall sit", targets: &["Thighs"], load: Load::Hold { kg: (0.0, 0.0), seconds: (30.0, 60.0) } },
+];
+
+/// Sessions a user cycles through on their training days. A skipped day
+/// doesn't skip a session, the next training day picks it up.
+struct Split {
+    days: &'static [Weekday],
+    sessions: &'static [&'static [&'static str]],
+}
+
+const SPLITS: &[Split] = &[
+    // Full body
+    Split {
+        days: &[Weekday::Mon, Weekday::Wed, Weekday::Fri],
+        sessions: &[
+            &["Squats", "Bench press", "Barbell row", "Plank"],
+            &["Deadlift", "Overhead press", "Pull-down", "Counter rotation"],
+        ],
+    },
+    // Upper / lower
+    Split {
+        days: &[Weekday::Mon, Weekday::Tue, Weekday::Thu, Weekday::Fri],
+        sessions: &[
+            &["Bench press", "Barbell row", "Overhead press", "Biceps curl"],
+            &["Squats", "Deadlift", "Calf raises", "Plank"],
+        ],
+    },
+    // Push / pull / legs
+    Split {
+        days: &[Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Fri, Weekday::Sat],
+        sessions: &[
+            &["Bench press", "Overhead press", "Triceps extension"],
+            &["Deadlift", "Barbell row", "Pull-down", "Biceps curl"],
+            &["Squats", "Calf raises", "Wall sit"],
+        ],
+    },
+    // Endurance
+    Split {
+        days: &[Weekday::Tue, Weekday::Thu, Weekday::Sat, Weekday::Sun],
+        sessions: &[&["Running"], &["Cycling", "Plank"], &["Running"], &["Rowing", "Counter rotation"]],
+    },
+];
+
+/// Where a user stands on one exercise
+enum Progress {
+    Weight { kg: f32, step: f32, sets: u32, reps: u32, misses: u32 },
+    Distance { km: f32, max_km: f32, pace: f32, min_pace: f32 },
+    Hold { kg: f32, seconds: f32, sets: u32 },
+}
+
+struct UserExercise {
+    id: String,
+    name: &'static str,
+    progress: Progress,
+}
+
+#[derive(Default)]
+struct Batch {
+    users: Vec<User>,
+    exercises: Vec<Exercise>,
+    exercise_targets: Vec<ExerciseTarget>,
+    workouts: Vec<Workout>,
+    exercise_workouts: Vec<ExerciseWorkout>,
+    sets: Vec<Set>,
+}
+
+impl Batch {
+    /// Parents first, so every foreign key is already there
+    async fn flush(&mut self, db: &dyn Database, summary: &mut Summary) -> Result<()> {
+        User::insert_many(db, &self.users).await?;
+        Exercise::insert_many(db, &self.exercises).await?;
+        ExerciseTarget::insert_many(db, &self.exercise_targets).await?;
+        Workout::insert_many(db, &self.workouts).await?;
+        ExerciseWorkout::insert_many(db, &self.exercise_workouts).await?;
+        Set::insert_many(db, &self.sets).await?;
+
+        summary.users += self.users.len();
+        summary.exercises += self.exercises.len();
+        summary.workouts += self.workouts.len();
+        summary.exercise_workouts += self.exercise_workouts.len();
+        summary.sets += self.sets.len();
+
+        *self = Self::default();
+
+        Ok(())
+    }
+}
+
+/// Creates `options.users` verified accounts with the password "password",
+/// each training on a split for `options.weeks` weeks up to `options.until`
+pub async fn generate(db: &dyn Database, options: &Options) -> Result<Summary> {
+    let targets = Target::all(db).await?;
+    let target_id = |name: &str| {
+        targets
+            .iter()
+            .find(|target| target.name == name)
+            .map(|target| target.id.clone())
+            .ok_or_else(|| Error::Other(format!("Target {name} is missing, are the migrations applied?")))
+    };
+
+    let first_day = options.until - Days::new(u64::from(options.weeks) * 7) + Days::new(1);
+    let mut summary = Summary::default();
+    let mut batch = Batch::default();
+
+    for index in 0..options.users {
+        // Every user gets their own stream, so a user's history doesn't
+        // depend on how many were generated before them
+        let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+        rng.set_stream(index.into());
+
+        let created_at = at(first_day - Days::new(rng.gen_range(1..30)), rng.gen_range(8..22), rng.gen_range(0..60));
+        let user = User {
+            id: id(&mut rng),
+            email: format!("generated-{}-{index}@example.com", options.seed),
+            password: Some(PASSWORD_HASH.to_string()),
+            role: Role::User,
+            disabled_at: None,
+            email_verified_at: Some(created_at),
+            totp_secret: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            created_at,
+            updated_at: created_at,
+        };
+
+        let split = SPLITS.choose(&mut rng).unwrap();
+        // How likely a training day is actually trained on
+        let consistency = rng.gen_range(0.6..0.95);
+
+        let mut exercises: Vec<UserExercise> = Vec::new();
+        for name in split.sessions.iter().flat_map(|session| session.iter()) {
+            if exercises.iter().any(|exercise| exercise.name == *name) {
+                continue;
+            }
+
+            let template = TEMPLATES.iter().find(|template| template.name == *name).unwrap();
+            let exercise = Exercise {
+                id: id(&mut rng),
+                user_id: user.id.clone(),
+                name: template.name.to_string(),
+                exercise_type: template.exercise_type(),
+                created_at: created_at + minutes(exercises.len() as u64 + 1),
+                updated_at: created_at + minutes(exercises.len() as u64 + 1),
+            };
+            for target in template.targets {
+                batch.exercise_targets.push(ExerciseTarget {
+                    id: id(&mut rng),
+                    exercise_id: exercise.id.clone(),
+                    target_id: target_id(target)?,
+                });
+            }
+
+            exercises.push(UserExercise {
+                id: exercise.id.clone(),
+                name: template.name,
+                progress: start(&template.load, &mut rng),
+            });
+            batch.exercises.push(exercise);
+        }
+        batch.users.push(user.clone());
+
+        let mut next_session = 0;
+        let mut day = first_day;
+        while day <= options.until {
+            if split.days.contains(&day.weekday()) && rng.gen_bool(consistency) {
+                let session = split.sessions[next_session % split.sessions.len()];
+                next_session += 1;
+
+                train(&mut batch, &mut rng, &user.id, day, session, &mut exercises);
+
+                if batch.sets.len() >= FLUSH_SETS {
+                    batch.flush(db, &mut summary).await?;
+                }
+            }
+
+            day = day + Days::new(1);
+        }
+    }
+
+    batch.flush(db, &mut summary).await?;
+
+    Ok(summary)
+}
+
+/// One finished workout on `day` doing every exercise of `session`
+fn train(
+    batch: &mut Batch,
+    rng: &mut ChaCha8Rng,
+    user_id: &str,
+    day: NaiveDate,
+    session: &[&str],
+    exercises: &mut [UserExercise],
+) {
+    let started_at = at(day, rng.gen_range(6..21), rng.gen_range(0..60));
+    let workout_id = id(rng);
+    let mut clock = started_at + minutes(rng.gen_range(3..10));
+
+    for name in session {
+        let exercise = exercises.iter_mut().find(|exercise| exercise.name == *name).unwrap();
+
+        let exercise_workout = ExerciseWorkout {
+            id: id(rng),
+            user_id: user_id.to_string(),
+            exercise_id: exercise.id.clone(),
+            workout_id: workout_id.clone(),
+            created_at: clock,
+            updated_at: clock,
+        };
+
+        for (quality, quantity, set_type, takes) in perform(&mut exercise.progress, rng) {
+            clock += takes;
+            batch.sets.push(Set {
+                id: id(rng),
+                user_id: user_id.to_string(),
+                exercise_workout_id: exercise_workout.id.clone(),
+                quality,
+                quantity,
+                note: None,
+                set_type,
+                created_at: clock,
+                updated_at: clock,
+            });
+        }
+
+        batch.exercise_workouts.push(exercise_workout);
+        clock += minutes(rng.gen_range(2..6));
+    }
+
+    batch.workouts.push(Workout {
+        id: workout_id,
+        user_id: user_id.to_string(),
+        status: WorkoutStatus::Done,
+        created_at: started_at,
+        updated_at: clock,
+    });
+}
+
+fn start(load: &Load, rng: &mut ChaCha8Rng) -> Progress {
+    match *load {
+        Load::Weight { kg, step } => Progress::Weight {
+            kg: round_to(rng.gen_range(kg.0..=kg.1), step),
+            step,
+            sets: rng.gen_range(3..=5),
+            reps: *[5, 8, 10, 12].choose(rng).unwrap(),
+            misses: 0,
+        },
+        Load::Distance { km, pace } => {
+            let km = round_to(rng.gen_range(km.0..=km.1), 0.1);
+            let pace = rng.gen_range(pace.0..=pace.1);
+            Progress::Distance { km, max_km: km * 2.0, pace, min_pace: pace * 0.75 }
+        }
+        Load::Hold { kg, seconds } => Progress::Hold {
+            kg: round_to(rng.gen_range(kg.0..=kg.1), 2.5),
+            seconds: rng.gen_range(seconds.0..=seconds.1).round(),
+            sets: rng.gen_range(2..=4),
+        },
+    }
+}
+
+/// The sets of one exercise as quality, quantity, type and how long the set
+/// and the rest before it took. Moves `progress` on for the next session.
+fn perform(progress: &mut Progress, rng: &mut ChaCha8Rng) -> Vec<(f32, f32, SetType, Duration)> {
+    let mut sets = Vec::new();
+
+    match progress {
+        Progress::Weight { kg, step, sets: working_sets, reps, misses } => {
+            if *kg >= 20.0 {
+                for share in [0.5, 0.75] {
+                    let warmup_reps = rng.gen_range(6..=10) as f32;
+                    sets.push((round_to(*kg * share, *step), warmup_reps, SetType::Warmup, rest(rng, 60..120)));
+                }
+            }
+
+            let mut all_done = true;
+            for set in 0..*working_sets {
+                // Later sets fail more often
+                let failed = rng.gen_bool(0.1 + 0.08 * f64::from(set));
+                let done = if failed { reps.saturating_sub(rng.gen_range(1..=2)).max(1) } else { *reps };
+                all_done &= done == *reps;
+                sets.push((*kg, done as f32, SetType::Normal, rest(rng, 90..210)));
+            }
+
+            if all_done {
+                *misses = 0;
+                if rng.gen_bool(0.6) {
+                    *kg += *step;
+                }
+            } else {
+                *misses += 1;
+                // Stuck for a while, back off and build up again
+                if *misses >= 3 {
+                    *kg = round_to(*kg * 0.9, *step);
+                    *misses = 0;
+                }
+            }
+        }
+        Progress::Distance { km, max_km, pace, min_pace } => {
+            let distance = round_to(*km * rng.gen_range(0.8..1.25), 0.1);
+            let seconds = (distance * *pace * rng.gen_range(0.97..1.04)).round();
+            sets.push((distance, seconds, SetType::Normal, Duration::from_secs(seconds as u64)));
+
+            *km = (*km + 0.05).min(*max_km);
+            *pace = (*pace * 0.998).max(*min_pace);
+        }
+        Progress::Hold { kg, seconds, sets: holds } => {
+            let mut all_held = true;
+            for _ in 0..*holds {
+                let held = (*seconds * rng.gen_range(0.85..1.05)).round();
+                all_held &= held >= *seconds;
+                sets.push((*kg, held, SetType::Normal, rest(rng, 45..90) + Duration::from_secs(held as u64)));
+            }
+
+            if all_held || rng.gen_bool(0.3) {
+                *seconds = (*seconds + 5.0).min(180.0);
+            }
+        }
+    }
+
+    sets
+}
+
+fn rest(rng: &mut ChaCha8Rng, seconds: std::ops::Range<u64>) -> Duration {
+    Duration::from_secs(rng.gen_range(seconds))
+}
+
+fn minutes(minutes: u64) -> Duration {
+    Duration::from_secs(minutes * 60)
+}
+
+fn round_to(value: f32, step: f32) -> f32 {
+    (value / step).round() * step
+}
+
+fn at(day: NaiveDate, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&day.and_hms_opt(hour, minute, 0).unwrap())
+}
+
+/// A v4 UUID from the generator's stream instead of the OS
+fn id(rng: &mut ChaCha8Rng) -> String {
+    uuid::Builder::from_random_bytes(rng.gen()).into_uuid().to_string()
+}
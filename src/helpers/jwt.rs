@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+
+use crate::{
+    config::AuthConfig,
+    error::{AuthError, Error, Result},
+};
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct AccessClaims {
+    /// User id
+    pub sub: String,
+    /// Session (token family) the access token was issued for
+    pub sid: String,
+    pub email_verified: bool,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Proof that the password was correct, exchanged together with a 2FA code
+/// for real tokens.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct ChallengeClaims {
+    /// User id
+    pub sub: String,
+    pub device_name: Option<String>,
+    /// Always `true`, keeps a challenge from ever passing as an access token
+    /// and the other way around
+    pub two_factor: bool,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Signs and verifies the short-lived access tokens, which can be checked
+/// without touching the database.
+pub struct AccessTokens {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    ttl: Duration,
+    challenge_ttl: Duration,
+}
+
+impl AccessTokens {
+    pub fn new(config: &AuthConfig) -> Self {
+        let secret = config.access_token_secret.as_bytes();
+
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            ttl: Duration::from_secs(config.access_token_ttl_secs),
+            challenge_ttl: Duration::from_secs(config.challenge_ttl_secs),
+        }
+    }
+
+    pub fn issue(
+        &self,
+        user_id: String,
+        session_id: String,
+        email_verified: bool,
+    ) -> Result<(String, chrono::DateTime<Utc>)> {
+        let now = Utc::now();
+        let expires_at = now + self.ttl;
+
+        let claims = AccessClaims {
+            sub: user_id,
+            sid: session_id,
+            email_verified,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)
+            .map_err(|err| Error::Other(format!("Failed to sign access token: {err}")))?;
+
+        Ok((token, expires_at))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<AccessClaims> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+
+        jsonwebtoken::decode::<AccessClaims>(token, &self.decoding, &validation)
+            .map(|data| data.claims)
+            .map_err(|err| match err.kind() {
+                ErrorKind::ExpiredSignature => Error::AuthError(AuthError::TokenExpired),
+                _ => Error::AuthError(AuthError::InvalidToken),
+            })
+    }
+
+    pub fn issue_challenge(
+        &self,
+        user_id: String,
+        device_name: Option<String>,
+    ) -> Result<(String, chrono::DateTime<Utc>)> {
+        let now = Utc::now();
+        let expires_at = now + self.challenge_ttl;
+
+        let claims = ChallengeClaims {
+            sub: user_id,
+            device_name,
+            two_factor: true,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)
+            .map_err(|err| Error::Other(format!("Failed to sign challenge: {err}")))?;
+
+        Ok((token, expires_at))
+    }
+
+    pub fn verify_challenge(&self, token: &str) -> Result<ChallengeClaims> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+
+        jsonwebtoken::decode::<ChallengeClaims>(token, &self.decoding, &validation)
+            .ok()
+            .map(|data| data.claims)
+            .filter(|claims| claims.two_factor)
+            .ok_or(Error::AuthError(AuthError::InvalidChallenge))
+    }
+}
@@ -1 +1,5 @@
+pub mod jwt;
+pub mod oidc;
 pub mod security;
+pub mod throttle;
+pub mod totp;
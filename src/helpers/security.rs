@@ -1,8 +1,12 @@
+use std::sync::OnceLock;
+
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
 use rand::RngCore;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 
 use crate::error::{Error, Result};
 
@@ -28,6 +32,24 @@ pub fn verify_password(password: &str, hashed_password: &str) -> Result<bool> {
         .is_ok())
 }
 
+/// Runs a password verification against a throwaway hash, so a login for an
+/// unknown email takes as long as one with a wrong password.
+pub fn verify_dummy_password(password: &str) -> Result<()> {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+    let hash = match DUMMY_HASH.get() {
+        Some(hash) => hash,
+        None => {
+            let hash = hash_password(&generate_token())?;
+            DUMMY_HASH.get_or_init(|| hash)
+        }
+    };
+
+    verify_password(password, hash)?;
+
+    Ok(())
+}
+
 pub fn generate_token() -> String {
     (0..64)
         .map(|_| {
@@ -36,3 +58,14 @@ pub fn generate_token() -> String {
         })
         .collect::<String>()
 }
+
+/// Digest used for secrets that are looked up by value (reset codes etc.),
+/// so the plaintext never has to be stored.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Checks `token` against a digest from `hash_token` in constant time.
+pub fn verify_token_hash(token: &str, hash: &str) -> bool {
+    hash_token(token).as_bytes().ct_eq(hash.as_bytes()).into()
+}
@@ -0,0 +1,43 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Above this many entries, stale ones are dropped before adding another
+const PRUNE_AT: usize = 10_000;
+
+/// Remembers when something was last done per key, in memory, so hot paths
+/// like the auth extractor write bookkeeping at most once per interval.
+/// Every process keeps its own, which is fine for timestamps that only
+/// need to be roughly right.
+pub struct Throttle {
+    interval: Duration,
+    last: Mutex<HashMap<String, Instant>>,
+}
+
+impl Throttle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `key` is due, in which case it counts as done from now on
+    pub fn ready(&self, key: &str) -> bool {
+        let now = Instant::now();
+        let mut last = self.last.lock().unwrap();
+
+        if last.get(key).is_some_and(|at| now.duration_since(*at) < self.interval) {
+            return false;
+        }
+
+        if last.len() >= PRUNE_AT {
+            last.retain(|_, at| now.duration_since(*at) < self.interval);
+        }
+        last.insert(key.to_string(), now);
+
+        true
+    }
+}
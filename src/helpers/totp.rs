@@ -0,0 +1,58 @@
+use subtle::ConstantTimeEq;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::error::{Error, Result};
+
+/// Shown as the account's issuer in authenticator apps
+const ISSUER: &str = "Workout";
+const DIGITS: usize = 6;
+/// Seconds per code
+const STEP: u64 = 30;
+/// Codes from this many steps before or after the current one are accepted
+/// too, to allow for clock drift on the phone.
+const SKEW: i64 = 1;
+
+/// Generates a new base32 encoded secret
+pub fn new_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn totp(secret: &str, email: &str) -> Result<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|err| Error::Other(format!("Invalid TOTP secret: {err:?}")))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        SKEW as u8,
+        STEP,
+        bytes,
+        Some(ISSUER.to_string()),
+        email.to_string(),
+    )
+    .map_err(|err| Error::Other(format!("Invalid TOTP parameters: {err}")))
+}
+
+/// `otpauth://` URI for the secret, usually shown to the user as a QR code
+pub fn provisioning_uri(secret: &str, email: &str) -> Result<String> {
+    Ok(totp(secret, email)?.get_url())
+}
+
+/// Checks `code` against the secret. Returns the time step the code belongs
+/// to so the caller can refuse to accept it a second time.
+pub fn verify(secret: &str, code: &str) -> Result<Option<i64>> {
+    // The account name isn't part of the code, any valid one will do
+    let totp = totp(secret, "user")?;
+    let code = code.trim();
+    let current = chrono::Utc::now().timestamp() / STEP as i64;
+
+    for step in (current - SKEW)..=(current + SKEW) {
+        let expected = totp.generate(step as u64 * STEP);
+        if bool::from(expected.as_bytes().ct_eq(code.as_bytes())) {
+            return Ok(Some(step));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use axum::{http::HeaderValue, middleware, routing::get_service, Router};
+use metrics_exporter_prometheus::PrometheusHandle;
+use tower::ServiceBuilder;
+use tower_http::{
+    cors::{Any, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    services::ServeDir,
+    trace::TraceLayer,
+};
+
+use crate::{
+    config::Config,
+    db::Database,
+    helpers::{jwt::AccessTokens, oidc::OidcClient, throttle::Throttle},
+    mailer::Mailer,
+    middlewares::{auth::LAST_USED_INTERVAL, metrics::track_metrics, problem::problem_json},
+};
+
+pub mod config;
+pub mod ctx;
+pub mod db;
+pub mod dtos;
+pub mod error;
+pub mod export;
+pub mod generator;
+pub mod helpers;
+pub mod mailer;
+pub mod middlewares;
+pub mod models;
+pub mod pagination;
+pub mod queue;
+pub mod response;
+pub mod routes;
+pub mod scheduler;
+pub mod seeder;
+pub mod telemetry;
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub db: Arc<dyn Database>,
+    pub access_tokens: Arc<AccessTokens>,
+    pub mailer: Arc<dyn Mailer>,
+    pub config: Arc<Config>,
+    /// `None` when OIDC login isn't configured
+    pub oidc: Option<Arc<OidcClient>>,
+    pub metrics: PrometheusHandle,
+    /// Sessions and API keys whose `last_used_at` was written recently
+    pub last_used: Arc<Throttle>,
+}
+
+impl ApiState {
+    /// Builds everything else the handlers need from the validated config
+    pub fn new(config: Arc<Config>, db: Arc<dyn Database>, metrics: PrometheusHandle) -> Self {
+        Self {
+            db,
+            access_tokens: Arc::new(AccessTokens::new(&config.auth)),
+            mailer: mailer::from_config(&config.mail),
+            oidc: config.oidc.clone().map(|oidc| Arc::new(OidcClient::new(oidc))),
+            config,
+            metrics,
+            last_used: Arc::new(Throttle::new(LAST_USED_INTERVAL)),
+        }
+    }
+}
+
+/// Every route with its middlewares, ready to be served or driven directly
+/// with `tower::ServiceExt::oneshot`.
+pub fn build_app(state: ApiState) -> Router {
+    let config = state.config.clone();
+
+    Router::new()
+        .merge(routes::health::router(state.clone()))
+        .merge(routes::exercise::router(state.clone()))
+        .merge(routes::workout::router(state.clone()))
+        .merge(routes::set::router(state.clone()))
+        .merge(routes::auth::router(state.clone()))
+        .merge(routes::me::router(state.clone()))
+        .merge(routes::api_key::router(state.clone()))
+        .merge(routes::oidc::router(state.clone()))
+        .merge(routes::admin::router(state.clone()))
+        .merge(routes::target::router(state))
+        .nest_service("/", get_service(ServeDir::new(&config.server.static_dir)))
+        .layer(middleware::from_fn(problem_json))
+        .layer(middleware::from_fn(track_metrics))
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::new(telemetry::REQUEST_ID_HEADER, MakeRequestUuid))
+                .layer(TraceLayer::new_for_http().make_span_with(telemetry::request_span))
+                .layer(PropagateRequestIdLayer::new(telemetry::REQUEST_ID_HEADER)),
+        )
+        .layer(cors_layer(&config.server.cors_allowed_origins))
+}
+
+/// Any origin when `*` is configured, like before origins were configurable
+fn cors_layer(allowed_origins: &[String]) -> CorsLayer {
+    if allowed_origins.iter().any(|origin| origin == "*") {
+        return CorsLayer::permissive();
+    }
+
+    let origins: Vec<HeaderValue> = allowed_origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin.trim_end_matches('/')).ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers(Any)
+}
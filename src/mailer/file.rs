@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use tokio::io::AsyncWriteExt;
+
+use crate::error::{Error, Result};
+
+use super::{Mail, Mailer};
+
+/// Writes mails to a file (appending) or stdout instead of sending them.
+/// Meant for local development and tests.
+pub struct FileMailer {
+    path: Option<PathBuf>,
+}
+
+impl FileMailer {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path: Some(path) }
+    }
+
+    pub fn stdout() -> Self {
+        Self { path: None }
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        let rendered = format!(
+            "To: {}\nSubject: {}\n\n{}\n---\n",
+            mail.to, mail.subject, mail.body
+        );
+
+        let Some(path) = &self.path else {
+            print!("{rendered}");
+            return Ok(());
+        };
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|err| Error::Other(format!("Failed to open mail file: {err}")))?;
+
+        file.write_all(rendered.as_bytes())
+            .await
+            .map_err(|err| Error::Other(format!("Failed to write mail file: {err}")))
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{MailConfig, MailTransport},
+    error::Result,
+};
+
+pub mod file;
+pub mod smtp;
+
+pub use file::FileMailer;
+pub use smtp::SmtpMailer;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<()>;
+}
+
+/// Picks the mailer for the configured transport. The config is validated
+/// at startup, so the settings each transport needs are there.
+pub fn from_config(config: &MailConfig) -> Arc<dyn Mailer> {
+    match config.transport {
+        MailTransport::Smtp => {
+            let url = config.smtp_url.as_deref().expect("SMTP URL configured");
+            let from = config.from.as_deref().expect("Mail from address configured");
+
+            Arc::new(SmtpMailer::new(url, from).expect("Valid SMTP configuration"))
+        }
+        MailTransport::File => Arc::new(FileMailer::new(config.file.clone().expect("Mail file configured"))),
+        MailTransport::Stdout => Arc::new(FileMailer::stdout()),
+    }
+}
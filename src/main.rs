@@ -1,27 +1,20 @@
-use std::{io, time::Duration};
-
-use axum::{routing::get_service, Router};
-use futures::FutureExt;
-use sqlx::{mysql::MySqlPoolOptions, MySql, Pool};
-
-use tower_http::{cors::CorsLayer, services::ServeDir};
-
-use crate::models::token::Token;
-
-mod ctx;
-mod dtos;
-mod error;
-mod helpers;
-mod middlewares;
-mod models;
-mod response;
-mod routes;
-mod seeder;
-
-#[derive(Clone)]
-struct ApiState {
-    db: Pool<MySql>,
-}
+use std::{future::IntoFuture, io, net::SocketAddr, sync::Arc, time::Duration};
+
+use tokio::sync::watch;
+
+use workout_backend::{
+    build_app,
+    config::Config,
+    db::{self, Database},
+    models::{
+        email_change::EmailChange, email_verification::EmailVerification,
+        job::Job, login_throttle::LoginThrottle, oidc_login::OidcLogin,
+        password_reset::PasswordReset, token::Token,
+    },
+    queue::Workers,
+    scheduler::Scheduler,
+    telemetry, ApiState,
+};
 
 #[tokio::main]
 async fn main() {
@@ -32,45 +25,147 @@ async fn main() {
         Ok(_) => (),
     }
 
-    let database_url = &std::env::var("DATABASE_URL").expect("DATABASE_URL present");
-    println!("{database_url}");
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+
+    telemetry::init(&config.log);
+    let metrics = telemetry::init_metrics();
+
+    tracing::info!(
+        database_url = telemetry::redact_url(&config.database.url),
+        "Connecting to database"
+    );
 
-    let pool = MySqlPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+    let db = db::connect(&config.database)
         .await
         .expect("Failed to connect to DB");
+    tracing::info!(backend = ?db.backend(), "Connected to database");
 
-    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    db.migrate().await.unwrap();
 
-    let state = ApiState { db: pool.clone() };
-    let layer = Router::new();
+    let config = Arc::new(config);
+    let state = ApiState::new(config.clone(), db.clone(), metrics);
 
+    let mut scheduler = Scheduler::new();
+    register_cleanup_jobs(&mut scheduler, &db, &config);
+    let workers = Workers::start(state.clone());
+
+    let app = build_app(state);
+
+    let listner = tokio::net::TcpListener::bind(config.server.listen_addr).await.unwrap();
+    tracing::info!(listen_addr = %config.server.listen_addr, "Server started");
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
     tokio::spawn(async move {
-        println!("Starting deleting expired tokens task");
+        shutdown_signal().await;
+        tracing::info!("Shutting down, draining connections");
+        let _ = shutdown_tx.send(true);
+    });
 
-        let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+    let mut server_shutdown = shutdown_rx.clone();
+    let server = axum::serve(listner, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            let _ = server_shutdown.changed().await;
+        });
+
+    // Requests still running after the drain timeout are cut off, a stuck
+    // client shouldn't be able to hold up a deploy
+    let mut drain_shutdown = shutdown_rx;
+    let drain_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    tokio::select! {
+        result = server.into_future() => result.unwrap(),
+        _ = async move {
+            let _ = drain_shutdown.changed().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => tracing::warn!("Connections still open after the drain timeout, closing them"),
+    }
+
+    workers.shutdown().await;
+    scheduler.shutdown().await;
+    db.close().await;
 
-        loop {
-            interval.tick().await;
+    tracing::info!("Server stopped");
+}
 
-            match Token::delete_expired(&pool).await {
-                Err(err) => println!("Failed to delete expired tokens {:#?}", err),
-                Ok(_) => (),
-            }
-        }
+/// Resolves on Ctrl+C or SIGTERM, which is what orchestrators send
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("Ctrl+C handler installed");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("SIGTERM handler installed")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => (),
+        _ = terminate => (),
+    }
+}
+
+/// Deletes rows that can't be used anymore, like expired tokens and codes
+fn register_cleanup_jobs(scheduler: &mut Scheduler, database: &Arc<dyn Database>, config: &Config) {
+    let interval = Duration::from_secs(config.server.cleanup_interval_secs);
+    let timeout = Duration::from_secs(config.server.job_timeout_secs);
+
+    let db = database.clone();
+    let ttl = config.refresh_token_ttl();
+    scheduler.every("delete_expired_tokens", interval, timeout, move || {
+        let db = db.clone();
+        async move { Token::delete_expired(&db, ttl).await }
+    });
+
+    let db = database.clone();
+    scheduler.every("delete_expired_password_resets", interval, timeout, move || {
+        let db = db.clone();
+        async move { PasswordReset::delete_expired(&db).await }
+    });
+
+    let db = database.clone();
+    scheduler.every("delete_expired_email_verifications", interval, timeout, move || {
+        let db = db.clone();
+        async move { EmailVerification::delete_expired(&db).await }
+    });
+
+    let db = database.clone();
+    scheduler.every("delete_expired_email_changes", interval, timeout, move || {
+        let db = db.clone();
+        async move { EmailChange::delete_expired(&db).await }
+    });
+
+    let db = database.clone();
+    scheduler.every("delete_expired_oidc_logins", interval, timeout, move || {
+        let db = db.clone();
+        async move { OidcLogin::delete_expired(&db).await }
+    });
+
+    let db = database.clone();
+    scheduler.every("delete_stale_login_throttles", interval, timeout, move || {
+        let db = db.clone();
+        async move { LoginThrottle::delete_stale(&db).await }
+    });
+
+    let db = database.clone();
+    scheduler.every("delete_finished_jobs", interval, timeout, move || {
+        let db = db.clone();
+        async move { Job::delete_finished(&db).await }
     });
 
-    let app = layer
-        .merge(routes::exercise::router(state.clone()))
-        .merge(routes::workout::router(state.clone()))
-        .merge(routes::set::router(state.clone()))
-        .merge(routes::auth::router(state.clone()))
-        .merge(routes::target::router(state.clone()))
-        .nest_service("/", get_service(ServeDir::new("./static")))
-        .layer(CorsLayer::permissive());
-
-    let listner = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    println!("Server started");
-    axum::serve(listner, app).await.unwrap();
+    // A running job can't legitimately take longer than its timeout, so
+    // one still locked well after that lost its worker
+    let db = database.clone();
+    let stuck_after = timeout * 2;
+    scheduler.every("requeue_stuck_jobs", timeout, timeout, move || {
+        let db = db.clone();
+        async move { Job::requeue_stuck(&db, stuck_after).await }
+    });
 }
@@ -1,26 +1,116 @@
+use std::{convert::Infallible, net::SocketAddr};
+
 use axum::{
     body::Body,
-    extract::{FromRef, FromRequestParts, Request},
-    http::{request::Parts, HeaderMap},
+    extract::{ConnectInfo, FromRef, FromRequestParts, Request, State},
+    http::{header::USER_AGENT, request::Parts, HeaderMap},
     middleware::Next,
     response::Response,
     RequestPartsExt,
 };
 
 use crate::{
-    ctx::Ctx,
-    error::{AuthError::InvalidToken, Error, Result},
-    models::token::Token,
+    ctx::{ClientInfo, Ctx},
+    error::{
+        AuthError::{AccountDisabled, AdminOnly, EmailNotVerified, InvalidToken, MissingScope},
+        Error, Result,
+    },
+    models::{
+        api_key::{ApiKey, API_KEY_PREFIX},
+        token::Token,
+        user::{Role, User},
+    },
     ApiState,
 };
 
+/// How stale `last_used_at` of sessions and API keys may get. Writing it on
+/// every request would cost a query each.
+pub const LAST_USED_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// What users that haven't verified their email are allowed to do.
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnverifiedUsers {
+    /// Same as verified users
+    Allow,
+    /// May log in and use the auth endpoints, but nothing behind `require_verified`
+    Restrict,
+    /// May not log in at all
+    Deny,
+}
+
+impl std::str::FromStr for UnverifiedUsers {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "allow" => Ok(Self::Allow),
+            "restrict" => Ok(Self::Restrict),
+            "deny" => Ok(Self::Deny),
+            other => Err(format!("unknown value {other}, expected allow, restrict or deny")),
+        }
+    }
+}
+
+/// For account management. Only sessions get through, API keys can't
+/// touch the account they belong to.
 pub async fn require_auth(
     ctx: Result<Ctx>,
     req: Request<Body>,
     next: Next,
 ) -> Result<Response> {
+    if ctx?.is_api_key() {
+        return Err(Error::AuthError(MissingScope));
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// For the admin API. Like `require_auth`, API keys never get through. The
+/// role isn't in the access token, so the user is loaded here, which also
+/// locks out demoted and disabled admins right away.
+pub async fn require_admin(
+    State(state): State<ApiState>,
+    ctx: Result<Ctx>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    let ctx = ctx?;
+
+    if ctx.is_api_key() {
+        return Err(Error::AuthError(AdminOnly));
+    }
+
+    let user = User::find_by_id(&state.db, ctx.user_id().to_string())
+        .await?
+        .ok_or(Error::AuthError(InvalidToken))?;
+
+    if user.is_disabled() {
+        return Err(Error::AuthError(AccountDisabled));
+    }
+
+    if user.role != Role::Admin {
+        return Err(Error::AuthError(AdminOnly));
+    }
+
+    Ok(next.run(req).await)
+}
+
+pub async fn require_verified(
+    State(state): State<ApiState>,
+    ctx: Result<Ctx>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    let ctx = ctx?;
+
+    if !ctx.is_email_verified() && state.config.auth.unverified_users != UnverifiedUsers::Allow {
+        return Err(Error::AuthError(EmailNotVerified));
+    }
 
-    ctx?;
+    if !ctx.allows(req.method(), req.uri().path()) {
+        return Err(Error::AuthError(MissingScope));
+    }
 
     Ok(next.run(req).await)
 }
@@ -42,15 +132,85 @@ where
         };
 
         let token = token.to_str().map_err(|_| Error::AuthError(InvalidToken))?;
-        let token = Token::find_by_value(&state.db, token)
-            .await?
-            .ok_or(Error::AuthError(InvalidToken))?;
+        let token = token.strip_prefix("Bearer ").unwrap_or(token);
+
+        let ctx = if token.starts_with(API_KEY_PREFIX) {
+            api_key_ctx(&state, token).await?
+        } else {
+            session_ctx(&state, token).await?
+        };
+
+        tracing::Span::current().record("user_id", ctx.user_id());
+
+        Ok(ctx)
+    }
+}
+
+async fn session_ctx(state: &ApiState, token: &str) -> Result<Ctx> {
+    // One indexed lookup per request, so logging out, revoking a session
+    // and disabling the account all take effect right away instead of when
+    // the access token expires
+    let claims = state.access_tokens.verify(token)?;
+
+    if !Token::is_family_active(&state.db, claims.sid.clone(), state.config.refresh_token_ttl()).await? {
+        return Err(Error::AuthError(InvalidToken));
+    }
+
+    if state.last_used.ready(&claims.sid) {
+        Token::touch_family(&state.db, claims.sid.clone()).await?;
+    }
+
+    Ok(Ctx::new(claims.sub, claims.sid, claims.email_verified))
+}
+
+async fn api_key_ctx(state: &ApiState, value: &str) -> Result<Ctx> {
+    let mut key = ApiKey::find_valid_by_value(&state.db, value)
+        .await?
+        .ok_or(Error::AuthError(InvalidToken))?;
+
+    if state.last_used.ready(&key.id) {
+        key.touch(&state.db).await?;
+    }
+
+    let user = User::find_by_id(&state.db, key.user_id.clone())
+        .await?
+        .ok_or(Error::WTF("API key exists but user doesn't".to_string()))?;
+
+    if user.is_disabled() {
+        return Err(Error::AuthError(AccountDisabled));
+    }
+
+    Ok(Ctx::for_api_key(
+        user.id.clone(),
+        key.id.clone(),
+        user.is_email_verified(),
+        key.scopes(),
+    ))
+}
+
+#[async_trait::async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> core::result::Result<Self, Infallible> {
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(255).collect());
 
-        let user = token
-            .user(&state.db)
-            .await?
-            .ok_or(Error::WTF("Token exists but user doesn't".to_string()))?;
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
 
-        Ok(Ctx::new(user, token))
+        Ok(Self {
+            device_name: None,
+            user_agent,
+            ip,
+        })
     }
 }
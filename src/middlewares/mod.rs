@@ -1 +1,4 @@
 pub mod auth;
+pub mod metrics;
+pub mod problem;
+pub mod validation;
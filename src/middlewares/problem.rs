@@ -0,0 +1,49 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap, HeaderValue,
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::Problem;
+
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Sends errors as RFC 7807 problem details instead of the usual response
+/// envelope when the client lists `application/problem+json` in `Accept`.
+pub async fn problem_json(req: Request, next: Next) -> Response {
+    let wants_problem = accepts_problem_json(req.headers());
+    let response = next.run(req).await;
+
+    if !wants_problem {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Some(problem) = parts.extensions.remove::<Problem>() else {
+        return Response::from_parts(parts, body);
+    };
+
+    let Ok(json) = serde_json::to_vec(&problem) else {
+        return Response::from_parts(parts, body);
+    };
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+
+    Response::from_parts(parts, Body::from(json))
+}
+
+fn accepts_problem_json(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| range.split(';').next())
+        .any(|media_type| media_type.trim().eq_ignore_ascii_case(PROBLEM_JSON))
+}
@@ -0,0 +1,72 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Query, Request},
+    http::request::Parts,
+    Json,
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use crate::{
+    error::{Error, Result},
+    response::FieldErrors,
+};
+
+/// Like `Json`, but runs the payload's validation rules and reports bad
+/// bodies in the usual response envelope instead of axum's plain text.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self> {
+        let Json(payload) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(from_json_rejection)?;
+
+        payload.validate()?;
+
+        Ok(Self(payload))
+    }
+}
+
+/// Like `Query`, but runs the validation rules and reports unparsable query
+/// strings as validation errors on `query`.
+pub struct ValidQuery<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let Query(query) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| {
+                Error::Validation(FieldErrors::from([("query".to_string(), vec![rejection.body_text()])]))
+            })?;
+
+        query.validate()?;
+
+        Ok(Self(query))
+    }
+}
+
+fn from_json_rejection(rejection: JsonRejection) -> Error {
+    match rejection {
+        // Valid JSON with the wrong shape, e.g. a missing field
+        JsonRejection::JsonDataError(err) => Error::Validation(FieldErrors::from([(
+            "body".to_string(),
+            vec![err.body_text()],
+        )])),
+        other => Error::BadRequest(other.body_text()),
+    }
+}
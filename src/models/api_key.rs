@@ -0,0 +1,226 @@
+use axum::http::Method;
+use chrono::Utc;
+use futures::stream::BoxStream;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    helpers::security::{generate_token, hash_token},
+};
+
+/// Every API key starts with this, which is how the auth middleware tells
+/// them apart from access tokens.
+pub const API_KEY_PREFIX: &str = "wk_";
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    #[serde(rename = "workouts:read")]
+    WorkoutsRead,
+    #[serde(rename = "workouts:write")]
+    WorkoutsWrite,
+    #[serde(rename = "exercises:read")]
+    ExercisesRead,
+    #[serde(rename = "exercises:write")]
+    ExercisesWrite,
+    #[serde(rename = "sets:read")]
+    SetsRead,
+    #[serde(rename = "sets:write")]
+    SetsWrite,
+}
+
+impl Scope {
+    /// The scope a key needs for a request. `None` for routes API keys can't
+    /// use at all.
+    pub fn required_for(method: &Method, path: &str) -> Option<Self> {
+        let (read, write) = match path.split('/').nth(2)? {
+            "workouts" => (Self::WorkoutsRead, Self::WorkoutsWrite),
+            "exercises" => (Self::ExercisesRead, Self::ExercisesWrite),
+            "sets" => (Self::SetsRead, Self::SetsWrite),
+            _ => return None,
+        };
+
+        if *method == Method::GET || *method == Method::HEAD {
+            Some(read)
+        } else {
+            Some(write)
+        }
+    }
+
+    /// Write access to something implies read access to it
+    pub fn covers(self, required: Self) -> bool {
+        self == required
+            || matches!(
+                (self, required),
+                (Self::WorkoutsWrite, Self::WorkoutsRead)
+                    | (Self::ExercisesWrite, Self::ExercisesRead)
+                    | (Self::SetsWrite, Self::SetsRead)
+            )
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::WorkoutsRead => "workouts:read",
+            Self::WorkoutsWrite => "workouts:write",
+            Self::ExercisesRead => "exercises:read",
+            Self::ExercisesWrite => "exercises:write",
+            Self::SetsRead => "sets:read",
+            Self::SetsWrite => "sets:write",
+        };
+
+        write!(fmt, "{name}")
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, String> {
+        match value {
+            "workouts:read" => Ok(Self::WorkoutsRead),
+            "workouts:write" => Ok(Self::WorkoutsWrite),
+            "exercises:read" => Ok(Self::ExercisesRead),
+            "exercises:write" => Ok(Self::ExercisesWrite),
+            "sets:read" => Ok(Self::SetsRead),
+            "sets:write" => Ok(Self::SetsWrite),
+            other => Err(format!("unknown scope {other}")),
+        }
+    }
+}
+
+/// Through `FromStr`, so payloads with an unknown scope are rejected with
+/// its name
+impl<'de> serde::Deserialize<'de> for Scope {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A personal access token for scripts and integrations.
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct ApiKey {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub prefix: String,
+    /// sha256 of the key, the plaintext is only available when created
+    pub value: String,
+    /// Space separated, see `scopes()`
+    pub scopes: String,
+    pub last_used_at: Option<chrono::DateTime<Utc>>,
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// What gets stored for a new key
+pub struct NewApiKey<'a> {
+    pub id: &'a str,
+    pub user_id: &'a str,
+    pub name: &'a str,
+    pub prefix: &'a str,
+    pub hashed_value: &'a str,
+    pub scopes: &'a str,
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// Storage for API keys, implemented by every database backend
+#[async_trait::async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn insert(&self, key: NewApiKey<'_>) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<ApiKey>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<ApiKey>>;
+    /// Only keys that haven't expired
+    async fn find_valid_by_value(&self, hashed_value: &str) -> Result<Option<ApiKey>>;
+    async fn find_all_by_user_id(&self, user_id: &str) -> Result<Vec<ApiKey>>;
+    async fn touch(&self, id: &str) -> Result<()>;
+    async fn delete(&self, id: &str) -> Result<()>;
+}
+
+impl ApiKey {
+    /// Creates a key and returns it together with the plaintext value, which
+    /// is not stored.
+    #[tracing::instrument(name = "ApiKey::create", skip_all)]
+    pub async fn create(
+        db: &dyn Database,
+        user_id: String,
+        name: String,
+        scopes: &[Scope],
+        expires_at: Option<chrono::DateTime<Utc>>,
+    ) -> Result<(Self, String)> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let value = format!("{API_KEY_PREFIX}{}", generate_token());
+        let scopes = scopes
+            .iter()
+            .map(Scope::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        db.api_keys()
+            .insert(NewApiKey {
+                id: &id,
+                user_id: &user_id,
+                name: &name,
+                prefix: &value[..API_KEY_PREFIX.len() + 5],
+                hashed_value: &hash_token(&value),
+                scopes: &scopes,
+                expires_at,
+            })
+            .await?;
+
+        let key = Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))?;
+
+        Ok((key, value))
+    }
+
+    #[tracing::instrument(name = "ApiKey::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.api_keys().find_by_id(&id).await
+    }
+
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.api_keys().stream_by_user_id(user_id)
+    }
+
+    /// Looks up an unexpired key by its plaintext value
+    #[tracing::instrument(name = "ApiKey::find_valid_by_value", skip_all)]
+    pub async fn find_valid_by_value(db: &dyn Database, value: &str) -> Result<Option<Self>> {
+        db.api_keys().find_valid_by_value(&hash_token(value)).await
+    }
+
+    #[tracing::instrument(name = "ApiKey::find_all_by_user_id", skip_all)]
+    pub async fn find_all_by_user_id(db: &dyn Database, user_id: String) -> Result<Vec<Self>> {
+        db.api_keys().find_all_by_user_id(&user_id).await
+    }
+
+    /// Called by the auth middleware at most once per `LAST_USED_INTERVAL`
+    /// for each key
+    #[tracing::instrument(name = "ApiKey::touch", skip_all)]
+    pub async fn touch(&mut self, db: &dyn Database) -> Result<()> {
+        db.api_keys().touch(&self.id).await?;
+
+        self.last_used_at = Some(Utc::now());
+
+        Ok(())
+    }
+
+    /// Unknown scopes are skipped, so a scope can be retired without
+    /// breaking the keys that have it.
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .split_whitespace()
+            .filter_map(|scope| scope.parse().ok())
+            .collect()
+    }
+
+    #[tracing::instrument(name = "ApiKey::delete", skip_all)]
+    pub async fn delete(&mut self, db: &dyn Database) -> Result<()> {
+        db.api_keys().delete(&self.id).await
+    }
+}
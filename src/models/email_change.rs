@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    helpers::security::{generate_token, hash_token},
+};
+
+/// How long the link sent to the new address works
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A requested change of a user's email, waiting for the new address to be
+/// confirmed.
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct EmailChange {
+    pub id: String,
+    pub user_id: String,
+    pub new_email: String,
+    pub token: String,
+    pub expires_at: chrono::DateTime<Utc>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for email changes, implemented by every database backend
+#[async_trait::async_trait]
+pub trait EmailChangeRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, new_email: &str, hashed_token: &str, ttl: Duration) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<EmailChange>>;
+    /// Only changes that haven't expired
+    async fn find_valid_by_token(&self, hashed_token: &str) -> Result<Option<EmailChange>>;
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()>;
+    async fn delete_expired(&self) -> Result<()>;
+}
+
+impl EmailChange {
+    /// Creates a pending change and returns it together with the plaintext
+    /// token, which is not stored.
+    #[tracing::instrument(name = "EmailChange::create", skip_all)]
+    pub async fn create(db: &dyn Database, user_id: String, new_email: String) -> Result<(Self, String)> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let token = generate_token();
+
+        db.email_changes()
+            .insert(&id, &user_id, &new_email, &hash_token(&token), TTL)
+            .await?;
+
+        let change = Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))?;
+
+        Ok((change, token))
+    }
+
+    #[tracing::instrument(name = "EmailChange::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.email_changes().find_by_id(&id).await
+    }
+
+    #[tracing::instrument(name = "EmailChange::find_valid_by_token", skip_all)]
+    pub async fn find_valid_by_token(db: &dyn Database, token: &str) -> Result<Option<Self>> {
+        db.email_changes().find_valid_by_token(&hash_token(token)).await
+    }
+
+    #[tracing::instrument(name = "EmailChange::delete_by_user_id", skip_all)]
+    pub async fn delete_by_user_id(db: &dyn Database, user_id: String) -> Result<()> {
+        db.email_changes().delete_by_user_id(&user_id).await
+    }
+
+    #[tracing::instrument(name = "EmailChange::delete_expired", skip_all)]
+    pub async fn delete_expired(db: &dyn Database) -> Result<()> {
+        db.email_changes().delete_expired().await
+    }
+}
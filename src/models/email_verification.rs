@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    helpers::security::{generate_token, hash_token},
+};
+
+/// How long the link in the verification mail works
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct EmailVerification {
+    pub id: String,
+    pub user_id: String,
+    pub token: String,
+    pub expires_at: chrono::DateTime<Utc>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for email verifications, implemented by every database backend
+#[async_trait::async_trait]
+pub trait EmailVerificationRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, hashed_token: &str, ttl: Duration) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<EmailVerification>>;
+    /// Only verifications that haven't expired
+    async fn find_valid_by_token(&self, hashed_token: &str) -> Result<Option<EmailVerification>>;
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()>;
+    async fn delete_expired(&self) -> Result<()>;
+}
+
+impl EmailVerification {
+    /// Creates a verification for the user and returns it together with the
+    /// plaintext token, which is not stored.
+    #[tracing::instrument(name = "EmailVerification::create", skip_all)]
+    pub async fn create(db: &dyn Database, user_id: String) -> Result<(Self, String)> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let token = generate_token();
+
+        db.email_verifications()
+            .insert(&id, &user_id, &hash_token(&token), TTL)
+            .await?;
+
+        let verification = Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))?;
+
+        Ok((verification, token))
+    }
+
+    #[tracing::instrument(name = "EmailVerification::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.email_verifications().find_by_id(&id).await
+    }
+
+    #[tracing::instrument(name = "EmailVerification::find_valid_by_token", skip_all)]
+    pub async fn find_valid_by_token(db: &dyn Database, token: &str) -> Result<Option<Self>> {
+        db.email_verifications().find_valid_by_token(&hash_token(token)).await
+    }
+
+    #[tracing::instrument(name = "EmailVerification::delete_by_user_id", skip_all)]
+    pub async fn delete_by_user_id(db: &dyn Database, user_id: String) -> Result<()> {
+        db.email_verifications().delete_by_user_id(&user_id).await
+    }
+
+    #[tracing::instrument(name = "EmailVerification::delete_expired", skip_all)]
+    pub async fn delete_expired(db: &dyn Database) -> Result<()> {
+        db.email_verifications().delete_expired().await
+    }
+}
@@ -1,7 +1,11 @@
 use chrono::Utc;
-use sqlx::{MySql, Pool};
+use futures::stream::BoxStream;
 
-use crate::error::{self, Error, Result};
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    pagination::{ListParams, Order, Page, SortField, SortValue},
+};
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
 pub enum ExerciseType {
@@ -22,95 +26,135 @@ impl From<String> for ExerciseType {
     }
 }
 
-impl ToString for ExerciseType {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for ExerciseType {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.write_str(match self {
             Self::Static => "static",
             Self::DistanceOverTime => "distance_over_time",
             Self::WeightOverAmount => "weight_over_amount",
-        }
-        .to_string()
+        })
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct Exercise {
     pub id: String,
     pub user_id: String,
     pub name: String,
+    #[sqlx(try_from = "String")]
     pub exercise_type: ExerciseType,
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// What exercise lists can be sorted by
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExerciseSort {
+    #[default]
+    Name,
+    CreatedAt,
+}
+
+impl SortField for ExerciseSort {
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::CreatedAt => "created_at",
+        }
+    }
+
+    fn is_timestamp(&self) -> bool {
+        matches!(self, Self::CreatedAt)
+    }
+
+    /// Alphabetical, newest first by date
+    fn default_order(&self) -> Order {
+        match self {
+            Self::Name => Order::Asc,
+            Self::CreatedAt => Order::Desc,
+        }
+    }
+}
+
+/// Storage for exercises, implemented by every database backend
+#[async_trait::async_trait]
+pub trait ExerciseRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, name: &str, exercise_type: &ExerciseType) -> Result<()>;
+    /// Inserts the rows as they are, ids and timestamps included, in one
+    /// transaction
+    async fn insert_many(&self, exercises: &[Exercise]) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<Exercise>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Exercise>>;
+    /// One page more than `params.limit` when there is a next page, and
+    /// the total without the cursor
+    async fn list_by_user_id(&self, user_id: &str, params: &ListParams<ExerciseSort>) -> Result<(Vec<Exercise>, i64)>;
+    async fn update(&self, exercise: &Exercise) -> Result<()>;
+    async fn delete(&self, id: &str) -> Result<()>;
+}
+
 impl Exercise {
+    #[tracing::instrument(name = "Exercise::create", skip_all)]
     pub async fn create(
-        db: &Pool<MySql>,
+        db: &dyn Database,
         user_id: String,
         name: String,
         exercise_type: ExerciseType,
     ) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
 
-        sqlx::query!(
-            "INSERT INTO exercises(id, user_id, name, exercise_type) VALUE (?, ?, ?, ?)",
-            id,
-            user_id,
-            name,
-            exercise_type.to_string()
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        db.exercises().insert(&id, &user_id, &name, &exercise_type).await?;
 
         Self::find_by_id(db, id)
             .await?
             .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(Exercise, "SELECT * FROM exercises WHERE id = ? LIMIT 1", id)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    /// Inserts rows built elsewhere as they are, ids and timestamps
+    /// included. For bulk loads like the generator.
+    #[tracing::instrument(name = "Exercise::insert_many", skip_all)]
+    pub async fn insert_many(db: &dyn Database, exercises: &[Self]) -> Result<()> {
+        db.exercises().insert_many(exercises).await
     }
 
-    pub async fn find_all_by_user_id(db: &Pool<MySql>, user_id: String) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(Exercise, "SELECT * FROM exercises WHERE user_id = ? ORDER BY name", user_id)
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?
-        )
+    #[tracing::instrument(name = "Exercise::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.exercises().find_by_id(&id).await
     }
 
-    pub async fn save(&mut self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "UPDATE exercises SET name = ?, exercise_type = ? WHERE id = ?",
-            self.name,
-            self.exercise_type.to_string(),
-            self.id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.exercises().stream_by_user_id(user_id)
+    }
 
-        self.updated_at = Utc::now();
+    #[tracing::instrument(name = "Exercise::list_by_user_id", skip_all)]
+    pub async fn list_by_user_id(
+        db: &dyn Database,
+        user_id: String,
+        params: &ListParams<ExerciseSort>,
+    ) -> Result<Page<Self>> {
+        let (exercises, total) = db.exercises().list_by_user_id(&user_id, params).await?;
 
-        Ok(())
+        Ok(Page::new(exercises, total, params, Self::sort_value, |exercise| &exercise.id))
     }
 
-    pub async fn delete(&mut self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "DELETE FROM exercises WHERE id = ?",
-            self.id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+    fn sort_value(&self, sort: ExerciseSort) -> SortValue {
+        match sort {
+            ExerciseSort::Name => SortValue::Text(self.name.clone()),
+            ExerciseSort::CreatedAt => SortValue::Time(self.created_at),
+        }
+    }
+
+    #[tracing::instrument(name = "Exercise::save", skip_all)]
+    pub async fn save(&mut self, db: &dyn Database) -> Result<()> {
+        db.exercises().update(self).await?;
+
+        self.updated_at = Utc::now();
 
         Ok(())
     }
+
+    #[tracing::instrument(name = "Exercise::delete", skip_all)]
+    pub async fn delete(&mut self, db: &dyn Database) -> Result<()> {
+        db.exercises().delete(&self.id).await
+    }
 }
@@ -1,53 +1,61 @@
-use sqlx::{MySql, Pool};
+use futures::stream::BoxStream;
 
-use crate::error::{self, Error, Result};
+use crate::{
+    db::Database,
+    error::{Error, Result},
+};
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct ExerciseTarget {
     pub id: String,
     pub exercise_id: String,
     pub target_id: String,
 }
 
+/// Storage for the targets of exercises, implemented by every database
+/// backend
+#[async_trait::async_trait]
+pub trait ExerciseTargetRepository: Send + Sync {
+    async fn insert(&self, id: &str, exercise_id: &str, target_id: &str) -> Result<()>;
+    /// Inserts the rows as they are, ids and timestamps included, in one
+    /// transaction
+    async fn insert_many(&self, exercise_targets: &[ExerciseTarget]) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<ExerciseTarget>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<ExerciseTarget>>;
+    async fn delete_by_exercise_id(&self, exercise_id: &str) -> Result<()>;
+}
+
 impl ExerciseTarget {
-    pub async fn create(db: &Pool<MySql>, exercise_id: String, target_id: String) -> Result<Self> {
+    #[tracing::instrument(name = "ExerciseTarget::create", skip_all)]
+    pub async fn create(db: &dyn Database, exercise_id: String, target_id: String) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
 
-        sqlx::query!(
-            "INSERT INTO exercise_target (id, exercise_id, target_id) VALUES (?, ?, ?)",
-            id,
-            exercise_id,
-            target_id,
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        db.exercise_targets().insert(&id, &exercise_id, &target_id).await?;
 
         Self::find_by_id(db, id)
             .await?
             .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(sqlx::query_as!(
-            ExerciseTarget,
-            "SELECT * FROM exercise_target WHERE id = ?",
-            id
-        )
-        .fetch_optional(db)
-        .await
-        .map_err(error::from_sqlx_error)?)
+    /// Inserts rows built elsewhere as they are, ids and timestamps
+    /// included. For bulk loads like the generator.
+    #[tracing::instrument(name = "ExerciseTarget::insert_many", skip_all)]
+    pub async fn insert_many(db: &dyn Database, exercise_targets: &[Self]) -> Result<()> {
+        db.exercise_targets().insert_many(exercise_targets).await
     }
 
-    pub async fn delete_by_exercise_id(db: &Pool<MySql>, exercise_id: String) -> Result<()> {
-        sqlx::query!(
-            "DELETE FROM exercise_target WHERE exercise_id = ?",
-            exercise_id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+    #[tracing::instrument(name = "ExerciseTarget::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.exercise_targets().find_by_id(&id).await
+    }
+
+    /// The links of all the user's exercises, they have no `user_id` of their own
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.exercise_targets().stream_by_user_id(user_id)
+    }
 
-        Ok(())
+    #[tracing::instrument(name = "ExerciseTarget::delete_by_exercise_id", skip_all)]
+    pub async fn delete_by_exercise_id(db: &dyn Database, exercise_id: String) -> Result<()> {
+        db.exercise_targets().delete_by_exercise_id(&exercise_id).await
     }
 }
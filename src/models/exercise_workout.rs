@@ -1,14 +1,17 @@
 use chrono::Utc;
-use sqlx::{MySql, Pool};
+use futures::stream::BoxStream;
 
-use crate::error::{self, Error, Result};
+use crate::{
+    db::Database,
+    error::{Error, Result},
+};
 
 use super::{
     exercise::Exercise,
     set::{Set, SetType},
 };
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct ExerciseWorkout {
     pub id: String,
     pub user_id: String,
@@ -18,54 +21,66 @@ pub struct ExerciseWorkout {
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// Storage for exercises done in a workout, implemented by every database
+/// backend
+#[async_trait::async_trait]
+pub trait ExerciseWorkoutRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, exercise_id: &str, workout_id: &str) -> Result<()>;
+    /// Inserts the rows as they are, ids and timestamps included, in one
+    /// transaction
+    async fn insert_many(&self, exercise_workouts: &[ExerciseWorkout]) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<ExerciseWorkout>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<ExerciseWorkout>>;
+    async fn find_all_by_exercise_and_workout_id(&self, exercise_id: &str, workout_id: &str) -> Result<Vec<ExerciseWorkout>>;
+    /// Newest first
+    async fn find_all_by_workout_id(&self, workout_id: &str) -> Result<Vec<ExerciseWorkout>>;
+    async fn delete(&self, id: &str) -> Result<()>;
+}
+
 impl ExerciseWorkout {
+    #[tracing::instrument(name = "ExerciseWorkout::create", skip_all)]
     pub async fn create(
-        db: &Pool<MySql>,
+        db: &dyn Database,
         user_id: String,
         exercise_id: String,
         workout_id: String,
     ) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
 
-        sqlx::query!(
-            "INSERT INTO exercise_workout(id, user_id, exercise_id, workout_id) VALUE (?, ?, ?, ?)",
-            id,
-            user_id,
-            exercise_id,
-            workout_id,
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        db.exercise_workouts().insert(&id, &user_id, &exercise_id, &workout_id).await?;
 
         Self::find_by_id(db, id)
             .await?
             .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(sqlx::query_as!(
-            ExerciseWorkout,
-            "SELECT * FROM exercise_workout WHERE id = ? LIMIT 1",
-            id
-        )
-        .fetch_optional(db)
-        .await
-        .map_err(error::from_sqlx_error)?)
+    /// Inserts rows built elsewhere as they are, ids and timestamps
+    /// included. For bulk loads like the generator.
+    #[tracing::instrument(name = "ExerciseWorkout::insert_many", skip_all)]
+    pub async fn insert_many(db: &dyn Database, exercise_workouts: &[Self]) -> Result<()> {
+        db.exercise_workouts().insert_many(exercise_workouts).await
     }
 
-    pub async fn find_all_by_exercise_and_workout_id(db: &Pool<MySql>, exercise_id: String, workout_id: String) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(ExerciseWorkout, "SELECT * FROM exercise_workout WHERE exercise_id = ? AND workout_id = ?", exercise_id, workout_id)
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?
-        )
+    #[tracing::instrument(name = "ExerciseWorkout::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.exercise_workouts().find_by_id(&id).await
     }
 
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.exercise_workouts().stream_by_user_id(user_id)
+    }
+
+    #[tracing::instrument(name = "ExerciseWorkout::find_all_by_exercise_and_workout_id", skip_all)]
+    pub async fn find_all_by_exercise_and_workout_id(db: &dyn Database, exercise_id: String, workout_id: String) -> Result<Vec<Self>> {
+        db.exercise_workouts()
+            .find_all_by_exercise_and_workout_id(&exercise_id, &workout_id)
+            .await
+    }
+
+    #[tracing::instrument(name = "ExerciseWorkout::add_set", skip_all)]
     pub async fn add_set(
         &self,
-        db: &Pool<MySql>,
+        db: &dyn Database,
         quality: f32,
         quantity: f32,
         set_type: SetType,
@@ -81,34 +96,22 @@ impl ExerciseWorkout {
         .await
     }
 
-    pub async fn exercise(&self, db: &Pool<MySql>) -> Result<Exercise> {
-        Ok(Exercise::find_by_id(db, self.exercise_id.clone())
+    #[tracing::instrument(name = "ExerciseWorkout::exercise", skip_all)]
+    pub async fn exercise(&self, db: &dyn Database) -> Result<Exercise> {
+        Exercise::find_by_id(db, self.exercise_id.clone())
             .await?
             .ok_or(Error::WTF(
                 "ExerciseWorkout exists but referenced exercise doesn't".into(),
-            ))?)
+            ))
     }
 
-    pub async fn sets(&self, db: &Pool<MySql>) -> Result<Vec<Set>> {
-        Ok(sqlx::query_as!(
-            Set,
-            "SELECT * FROM sets WHERE exercise_workout_id = ? ORDER BY set_type ASC, created_at ASC",
-            self.id
-        )
-        .fetch_all(db)
-        .await
-        .map_err(error::from_sqlx_error)?)
+    #[tracing::instrument(name = "ExerciseWorkout::sets", skip_all)]
+    pub async fn sets(&self, db: &dyn Database) -> Result<Vec<Set>> {
+        db.sets().find_all_by_exercise_workout_id(&self.id).await
     }
 
-    pub async fn delete(&mut self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "DELETE FROM exercise_workout WHERE id = ?",
-            self.id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
-
-        Ok(())
+    #[tracing::instrument(name = "ExerciseWorkout::delete", skip_all)]
+    pub async fn delete(&mut self, db: &dyn Database) -> Result<()> {
+        db.exercise_workouts().delete(&self.id).await
     }
 }
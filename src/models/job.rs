@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+};
+
+/// First retry delay, doubled for every further attempt
+const BASE_RETRY_SECONDS: u64 = 30;
+/// Upper bound for the exponential backoff
+const MAX_RETRY_SECONDS: u64 = 60 * 60;
+/// How long finished jobs are kept around for the admin view
+const KEEP_DONE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// What a job does, stored as JSON in `payload`. Payloads only reference
+/// rows, codes and links are created when the mail is sent so they never
+/// sit in the table in plain text.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobPayload {
+    /// A new verification link for the user's email
+    SendVerification { user_id: String },
+    /// A new password reset code
+    SendPasswordReset { user_id: String },
+    /// A link confirming the change, sent to the new address
+    SendEmailChange { user_id: String, new_email: String },
+    /// A notice to the address the user just changed away from
+    SendEmailChanged { user_id: String, old_email: String },
+}
+
+impl JobPayload {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::SendVerification { .. } => "send_verification",
+            Self::SendPasswordReset { .. } => "send_password_reset",
+            Self::SendEmailChange { .. } => "send_email_change",
+            Self::SendEmailChanged { .. } => "send_email_changed",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    /// Failed `max_attempts` times, only retried by an admin
+    Dead,
+}
+
+impl TryFrom<String> for JobStatus {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        match value.as_str() {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "done" => Ok(Self::Done),
+            "dead" => Ok(Self::Dead),
+            _ => Err(Error::Other(format!("Unknown JobStatus: {value}"))),
+        }
+    }
+}
+
+/// Stored as text, decoded like `Role` so a bad row fails its query rather
+/// than the worker that claimed it
+impl<DB: sqlx::Database> sqlx::Type<DB> for JobStatus
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for JobStatus
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(value: <DB as sqlx::database::HasValueRef<'r>>::ValueRef) -> std::result::Result<Self, sqlx::error::BoxDynError> {
+        Ok(Self::try_from(String::decode(value)?)?)
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.write_str(match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Dead => "dead",
+        })
+    }
+}
+
+/// Work done outside of the request that asked for it. Any number of
+/// server instances can process the same table, claiming uses row locks so
+/// every job runs on one worker at a time.
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    pub payload: String,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: chrono::DateTime<Utc>,
+    pub locked_by: Option<String>,
+    pub locked_at: Option<chrono::DateTime<Utc>>,
+    pub last_error: Option<String>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for the job queue, implemented by every database backend
+#[async_trait::async_trait]
+pub trait JobRepository: Send + Sync {
+    async fn insert(&self, id: &str, kind: &str, payload: &str, max_attempts: i32) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<Job>>;
+    async fn search(&self, status: Option<String>, limit: i64, offset: i64) -> Result<Vec<Job>>;
+    /// Marks the oldest due job as running for `worker` and returns it as
+    /// updated. A job must never be handed to two workers.
+    async fn claim(&self, worker: &str) -> Result<Option<Job>>;
+    /// `complete` and `fail` only touch the job while `worker` holds it and
+    /// return `false` otherwise
+    async fn complete(&self, id: &str, worker: &str) -> Result<bool>;
+    async fn fail(&self, id: &str, worker: &str, status: JobStatus, delay: Duration, reason: &str) -> Result<bool>;
+    async fn retry(&self, id: &str) -> Result<()>;
+    async fn requeue_stuck(&self, older_than: Duration) -> Result<()>;
+    async fn delete_finished(&self, older_than: Duration) -> Result<()>;
+}
+
+impl Job {
+    #[tracing::instrument(name = "Job::enqueue", skip_all)]
+    pub async fn enqueue(db: &dyn Database, payload: &JobPayload, max_attempts: i32) -> Result<Self> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let json = serde_json::to_string(payload)
+            .map_err(|err| Error::Other(format!("Failed to serialize job payload: {err}")))?;
+
+        db.jobs().insert(&id, payload.kind(), &json, max_attempts).await?;
+
+        Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
+    }
+
+    #[tracing::instrument(name = "Job::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.jobs().find_by_id(&id).await
+    }
+
+    /// Newest first, optionally only the ones with `status`
+    #[tracing::instrument(name = "Job::search", skip_all)]
+    pub async fn search(db: &dyn Database, status: Option<JobStatus>, limit: i64, offset: i64) -> Result<Vec<Self>> {
+        db.jobs()
+            .search(status.map(|status| status.to_string()), limit, offset)
+            .await
+    }
+
+    /// Takes the oldest due job for `worker`. Rows locked by other workers
+    /// are skipped rather than waited for, so instances never block each
+    /// other or run a job twice.
+    #[tracing::instrument(name = "Job::claim", skip_all)]
+    pub async fn claim(db: &dyn Database, worker: &str) -> Result<Option<Self>> {
+        db.jobs().claim(worker).await
+    }
+
+    pub fn payload(&self) -> Result<JobPayload> {
+        serde_json::from_str(&self.payload)
+            .map_err(|err| Error::Other(format!("Invalid payload for job {}: {err}", self.id)))
+    }
+
+    /// Returns `false` if the worker lost the job in the meantime, e.g.
+    /// because it ran so long that `requeue_stuck` handed it to another one.
+    /// The job is left alone then.
+    #[tracing::instrument(name = "Job::complete", skip_all)]
+    pub async fn complete(&mut self, db: &dyn Database) -> Result<bool> {
+        let Some(worker) = &self.locked_by else {
+            return Ok(false);
+        };
+
+        if !db.jobs().complete(&self.id, worker).await? {
+            return Ok(false);
+        }
+
+        self.status = JobStatus::Done;
+        self.locked_by = None;
+        self.locked_at = None;
+
+        Ok(true)
+    }
+
+    /// Schedules another attempt with exponential backoff, or gives up once
+    /// the job has used all its attempts. Returns `false` like `complete`.
+    #[tracing::instrument(name = "Job::fail", skip_all)]
+    pub async fn fail(&mut self, db: &dyn Database, reason: &str) -> Result<bool> {
+        let Some(worker) = &self.locked_by else {
+            return Ok(false);
+        };
+
+        let status = if self.attempts >= self.max_attempts {
+            JobStatus::Dead
+        } else {
+            JobStatus::Pending
+        };
+        let delay = retry_delay(self.attempts);
+
+        if !db.jobs().fail(&self.id, worker, status, delay, reason).await? {
+            return Ok(false);
+        }
+
+        self.status = status;
+        self.run_at = Utc::now() + delay;
+        self.last_error = Some(reason.to_string());
+        self.locked_by = None;
+        self.locked_at = None;
+
+        Ok(true)
+    }
+
+    /// Gives a dead job a fresh set of attempts
+    #[tracing::instrument(name = "Job::retry", skip_all)]
+    pub async fn retry(&mut self, db: &dyn Database) -> Result<()> {
+        db.jobs().retry(&self.id).await?;
+
+        self.status = JobStatus::Pending;
+        self.attempts = 0;
+        self.run_at = Utc::now();
+
+        Ok(())
+    }
+
+    /// Puts jobs back whose worker died mid-run, e.g. because the instance
+    /// was killed. The lost run counts as an attempt.
+    #[tracing::instrument(name = "Job::requeue_stuck", skip_all)]
+    pub async fn requeue_stuck(db: &dyn Database, older_than: Duration) -> Result<()> {
+        db.jobs().requeue_stuck(older_than).await
+    }
+
+    #[tracing::instrument(name = "Job::delete_finished", skip_all)]
+    pub async fn delete_finished(db: &dyn Database) -> Result<()> {
+        db.jobs().delete_finished(KEEP_DONE).await
+    }
+}
+
+/// 30s, 1m, 2m, 4m, ... up to an hour
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+
+    Duration::from_secs((BASE_RETRY_SECONDS << exponent).min(MAX_RETRY_SECONDS))
+}
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{db::Database, error::Result};
+
+/// Failures are forgotten once there hasn't been one for this long
+const FAILURE_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// Upper bound for the exponential lockout
+const MAX_LOCKOUT_SECONDS: u64 = 15 * 60;
+/// Throttles without a failure for this long are deleted
+const KEEP_STALE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Failed login attempts for an account or an IP.
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct LoginThrottle {
+    pub id: String,
+    pub throttle_key: String,
+    pub failures: i32,
+    pub locked_until: Option<chrono::DateTime<Utc>>,
+    pub last_failure_at: chrono::DateTime<Utc>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for login throttles, implemented by every database backend
+#[async_trait::async_trait]
+pub trait LoginThrottleRepository: Send + Sync {
+    async fn find_by_key(&self, key: &str) -> Result<Option<LoginThrottle>>;
+    /// Creates the throttle with one failure, or adds one to the existing
+    /// one. The count starts over when the last failure is older than
+    /// `window`.
+    async fn add_failure(&self, id: &str, key: &str, window: Duration) -> Result<()>;
+    async fn lock(&self, id: &str, lockout: Duration) -> Result<()>;
+    async fn delete_by_key(&self, key: &str) -> Result<()>;
+    async fn delete_stale(&self, older_than: Duration) -> Result<()>;
+}
+
+impl LoginThrottle {
+    pub fn email_key(email: &str) -> String {
+        format!("email:{}", email.to_lowercase())
+    }
+
+    pub fn ip_key(ip: &str) -> String {
+        format!("ip:{ip}")
+    }
+
+    /// Separate from `email_key`, asking for mails mustn't lock the account
+    pub fn mail_key(email: &str) -> String {
+        format!("mail:{}", email.to_lowercase())
+    }
+
+    pub fn mail_ip_key(ip: &str) -> String {
+        format!("mail-ip:{ip}")
+    }
+
+    #[tracing::instrument(name = "LoginThrottle::find_by_key", skip_all)]
+    pub async fn find_by_key(db: &dyn Database, key: &str) -> Result<Option<Self>> {
+        db.login_throttles().find_by_key(key).await
+    }
+
+    /// Seconds until the key may try again, rounded up so the last partial
+    /// second still counts. `None` if it isn't locked.
+    #[tracing::instrument(name = "LoginThrottle::retry_after", skip_all)]
+    pub async fn retry_after(db: &dyn Database, key: &str) -> Result<Option<u64>> {
+        let Some(throttle) = Self::find_by_key(db, key).await? else {
+            return Ok(None);
+        };
+
+        Ok(throttle
+            .locked_until
+            .map(|until| (until - Utc::now()).num_milliseconds())
+            .filter(|millis| *millis > 0)
+            .map(|millis| (millis as u64).div_ceil(1000)))
+    }
+
+    /// Counts a failed attempt. Once more than `free_attempts` failures
+    /// happened within the window the key is locked, doubling the lockout
+    /// for every further failure.
+    #[tracing::instrument(name = "LoginThrottle::record_failure", skip_all)]
+    pub async fn record_failure(db: &dyn Database, key: &str, free_attempts: i32) -> Result<()> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        db.login_throttles().add_failure(&id, key, FAILURE_WINDOW).await?;
+
+        let Some(throttle) = Self::find_by_key(db, key).await? else {
+            return Ok(());
+        };
+
+        if throttle.failures <= free_attempts {
+            return Ok(());
+        }
+
+        let exponent = (throttle.failures - free_attempts - 1).min(16) as u32;
+        let lockout = 2u64.pow(exponent).min(MAX_LOCKOUT_SECONDS);
+
+        db.login_throttles()
+            .lock(&throttle.id, Duration::from_secs(lockout))
+            .await
+    }
+
+    #[tracing::instrument(name = "LoginThrottle::clear", skip_all)]
+    pub async fn clear(db: &dyn Database, key: &str) -> Result<()> {
+        db.login_throttles().delete_by_key(key).await
+    }
+
+    #[tracing::instrument(name = "LoginThrottle::delete_stale", skip_all)]
+    pub async fn delete_stale(db: &dyn Database) -> Result<()> {
+        db.login_throttles().delete_stale(KEEP_STALE).await
+    }
+}
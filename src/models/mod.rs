@@ -1,8 +1,17 @@
+pub mod api_key;
+pub mod email_change;
+pub mod email_verification;
 pub mod exercise;
 pub mod exercise_target;
 pub mod exercise_workout;
+pub mod job;
+pub mod login_throttle;
+pub mod oidc_login;
+pub mod password_reset;
+pub mod recovery_code;
 pub mod set;
 pub mod target;
 pub mod token;
 pub mod user;
+pub mod user_identity;
 pub mod workout;
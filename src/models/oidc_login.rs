@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    helpers::security::{generate_token, hash_token},
+};
+
+/// How long the user has to finish logging in at the provider
+const TTL: Duration = Duration::from_secs(10 * 60);
+
+/// An OIDC login that was sent to the provider and hasn't come back yet.
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct OidcLogin {
+    pub id: String,
+    /// sha256 of the state parameter
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub device_name: Option<String>,
+    pub expires_at: chrono::DateTime<Utc>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for OIDC logins, implemented by every database backend
+#[async_trait::async_trait]
+pub trait OidcLoginRepository: Send + Sync {
+    async fn insert(
+        &self,
+        id: &str,
+        hashed_state: &str,
+        nonce: &str,
+        code_verifier: &str,
+        device_name: Option<&str>,
+        ttl: Duration,
+    ) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<OidcLogin>>;
+    /// Only logins that haven't expired
+    async fn find_valid_by_state(&self, hashed_state: &str) -> Result<Option<OidcLogin>>;
+    /// Returns `false` if there was nothing to delete
+    async fn delete(&self, id: &str) -> Result<bool>;
+    async fn delete_expired(&self) -> Result<()>;
+}
+
+impl OidcLogin {
+    /// Starts a login and returns it together with the plaintext state,
+    /// which is not stored.
+    #[tracing::instrument(name = "OidcLogin::create", skip_all)]
+    pub async fn create(db: &dyn Database, device_name: Option<String>) -> Result<(Self, String)> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let state = generate_token();
+
+        db.oidc_logins()
+            .insert(
+                &id,
+                &hash_token(&state),
+                &generate_token(),
+                &generate_token(),
+                device_name.as_deref(),
+                TTL,
+            )
+            .await?;
+
+        let login = Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))?;
+
+        Ok((login, state))
+    }
+
+    #[tracing::instrument(name = "OidcLogin::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.oidc_logins().find_by_id(&id).await
+    }
+
+    #[tracing::instrument(name = "OidcLogin::find_valid_by_state", skip_all)]
+    pub async fn find_valid_by_state(db: &dyn Database, state: &str) -> Result<Option<Self>> {
+        db.oidc_logins().find_valid_by_state(&hash_token(state)).await
+    }
+
+    /// Removes the login so the callback can't be replayed. Returns `false`
+    /// if someone else got there first.
+    #[tracing::instrument(name = "OidcLogin::consume", skip_all)]
+    pub async fn consume(&self, db: &dyn Database) -> Result<bool> {
+        db.oidc_logins().delete(&self.id).await
+    }
+
+    #[tracing::instrument(name = "OidcLogin::delete_expired", skip_all)]
+    pub async fn delete_expired(db: &dyn Database) -> Result<()> {
+        db.oidc_logins().delete_expired().await
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    helpers::security::{generate_token, hash_token},
+};
+
+/// How long a reset code works
+const TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct PasswordReset {
+    pub id: String,
+    pub user_id: String,
+    pub code: String,
+    pub expires_at: chrono::DateTime<Utc>,
+    pub used_at: Option<chrono::DateTime<Utc>>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for password resets, implemented by every database backend
+#[async_trait::async_trait]
+pub trait PasswordResetRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, hashed_code: &str, ttl: Duration) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<PasswordReset>>;
+    /// Only unused codes that haven't expired
+    async fn find_valid_by_code(&self, hashed_code: &str) -> Result<Option<PasswordReset>>;
+    /// Returns `false` if the code had already been used
+    async fn mark_used(&self, id: &str) -> Result<bool>;
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()>;
+    /// Deletes expired and used codes
+    async fn delete_expired(&self) -> Result<()>;
+}
+
+impl PasswordReset {
+    /// Creates a new reset code for the user and returns it together with
+    /// the plaintext code. Only the hash is stored, so this is the only
+    /// time the plaintext is available.
+    #[tracing::instrument(name = "PasswordReset::create", skip_all)]
+    pub async fn create(db: &dyn Database, user_id: String) -> Result<(Self, String)> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let code = generate_token();
+
+        db.password_resets()
+            .insert(&id, &user_id, &hash_token(&code), TTL)
+            .await?;
+
+        let reset = Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))?;
+
+        Ok((reset, code))
+    }
+
+    #[tracing::instrument(name = "PasswordReset::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.password_resets().find_by_id(&id).await
+    }
+
+    #[tracing::instrument(name = "PasswordReset::find_valid_by_code", skip_all)]
+    pub async fn find_valid_by_code(db: &dyn Database, code: &str) -> Result<Option<Self>> {
+        db.password_resets().find_valid_by_code(&hash_token(code)).await
+    }
+
+    /// Marks the code as used. Returns `false` if someone else got there
+    /// first, in which case the code must not be honoured.
+    #[tracing::instrument(name = "PasswordReset::consume", skip_all)]
+    pub async fn consume(&mut self, db: &dyn Database) -> Result<bool> {
+        let consumed = db.password_resets().mark_used(&self.id).await?;
+
+        self.used_at = Some(Utc::now());
+        self.updated_at = Utc::now();
+
+        Ok(consumed)
+    }
+
+    #[tracing::instrument(name = "PasswordReset::delete_by_user_id", skip_all)]
+    pub async fn delete_by_user_id(db: &dyn Database, user_id: String) -> Result<()> {
+        db.password_resets().delete_by_user_id(&user_id).await
+    }
+
+    #[tracing::instrument(name = "PasswordReset::delete_expired", skip_all)]
+    pub async fn delete_expired(db: &dyn Database) -> Result<()> {
+        db.password_resets().delete_expired().await
+    }
+}
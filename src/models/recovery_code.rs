@@ -0,0 +1,96 @@
+use chrono::Utc;
+use rand::{rngs::OsRng, Rng};
+
+use crate::{
+    db::Database,
+    error::Result,
+    helpers::security::hash_token,
+};
+
+/// How many codes a user gets when enabling 2FA
+const CODE_COUNT: usize = 10;
+
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct RecoveryCode {
+    pub id: String,
+    pub user_id: String,
+    pub code: String,
+    pub used_at: Option<chrono::DateTime<Utc>>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for recovery codes, implemented by every database backend
+#[async_trait::async_trait]
+pub trait RecoveryCodeRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, hashed_code: &str) -> Result<()>;
+    async fn find_unused_by_user_id(&self, user_id: &str) -> Result<Vec<RecoveryCode>>;
+    /// Returns `false` if the code doesn't exist or was already used
+    async fn mark_used(&self, user_id: &str, hashed_code: &str) -> Result<bool>;
+    async fn delete_by_user_id(&self, user_id: &str) -> Result<()>;
+}
+
+impl RecoveryCode {
+    /// Replaces the user's recovery codes with a fresh batch and returns the
+    /// plaintext codes. Only hashes are stored, so this is the only time
+    /// they can be shown.
+    #[tracing::instrument(name = "RecoveryCode::create_batch", skip_all)]
+    pub async fn create_batch(db: &dyn Database, user_id: String) -> Result<Vec<String>> {
+        Self::delete_by_user_id(db, user_id.clone()).await?;
+
+        let mut codes = Vec::with_capacity(CODE_COUNT);
+
+        for _ in 0..CODE_COUNT {
+            let code = generate_code();
+
+            db.recovery_codes()
+                .insert(&uuid::Uuid::new_v4().to_string(), &user_id, &hash_token(&normalize(&code)))
+                .await?;
+
+            codes.push(code);
+        }
+
+        Ok(codes)
+    }
+
+    #[tracing::instrument(name = "RecoveryCode::find_unused_by_user_id", skip_all)]
+    pub async fn find_unused_by_user_id(db: &dyn Database, user_id: String) -> Result<Vec<Self>> {
+        db.recovery_codes().find_unused_by_user_id(&user_id).await
+    }
+
+    /// Uses up one of the user's recovery codes. Returns `false` if the code
+    /// doesn't exist or has already been used.
+    #[tracing::instrument(name = "RecoveryCode::consume", skip_all)]
+    pub async fn consume(db: &dyn Database, user_id: String, code: &str) -> Result<bool> {
+        db.recovery_codes()
+            .mark_used(&user_id, &hash_token(&normalize(code)))
+            .await
+    }
+
+    #[tracing::instrument(name = "RecoveryCode::delete_by_user_id", skip_all)]
+    pub async fn delete_by_user_id(db: &dyn Database, user_id: String) -> Result<()> {
+        db.recovery_codes().delete_by_user_id(&user_id).await
+    }
+}
+
+/// Ten lowercase letters and digits, split in two for readability, e.g.
+/// `k3x9a-m2pq7`
+fn generate_code() -> String {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+    let mut code: String = (0..10)
+        .map(|_| CHARS[OsRng.gen_range(0..CHARS.len())] as char)
+        .collect();
+    code.insert(5, '-');
+
+    code
+}
+
+/// Users type these by hand, so ignore case, spaces and dashes
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
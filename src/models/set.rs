@@ -1,7 +1,10 @@
-use chrono::{DateTime, Utc};
-use sqlx::{MySql, Pool};
+use chrono::Utc;
+use futures::stream::BoxStream;
 
-use crate::error::{self, Error, Result};
+use crate::{
+    db::Database,
+    error::{Error, Result},
+};
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub enum SetType {
@@ -19,17 +22,16 @@ impl From<String> for SetType {
     }
 }
 
-impl ToString for SetType {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for SetType {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.write_str(match self {
             Self::Warmup => "warmup",
             Self::Normal => "normal",
-        }
-        .to_string()
+        })
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct Set {
     pub id: String,
     pub user_id: String,
@@ -37,14 +39,40 @@ pub struct Set {
     pub quality: f32,
     pub quantity: f32,
     pub note: Option<String>,
+    #[sqlx(try_from = "String")]
     pub set_type: SetType,
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// Storage for sets, implemented by every database backend. Lists of sets
+/// put warmups first, then go by creation time.
+#[async_trait::async_trait]
+pub trait SetRepository: Send + Sync {
+    async fn insert(
+        &self,
+        id: &str,
+        user_id: &str,
+        exercise_workout_id: &str,
+        quality: f32,
+        quantity: f32,
+        set_type: &SetType,
+    ) -> Result<()>;
+    /// Inserts the rows as they are, ids and timestamps included, in one
+    /// transaction
+    async fn insert_many(&self, sets: &[Set]) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<Set>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Set>>;
+    async fn find_all_by_exercise_workout_id(&self, exercise_workout_id: &str) -> Result<Vec<Set>>;
+    async fn find_all_by_user_id(&self, user_id: &str) -> Result<Vec<Set>>;
+    async fn update(&self, set: &Set) -> Result<()>;
+    async fn delete(&self, id: &str) -> Result<()>;
+}
+
 impl Set {
+    #[tracing::instrument(name = "Set::create", skip_all)]
     pub async fn create(
-        db: &Pool<MySql>,
+        db: &dyn Database,
         user_id: String,
         exercise_workout_id: String,
         quality: f32,
@@ -53,79 +81,53 @@ impl Set {
     ) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
 
-        sqlx::query!(
-            "INSERT INTO sets(id, user_id, exercise_workout_id, quality, quantity, set_type) VALUE (?, ?, ?, ?, ?, ?)",
-            id,
-            user_id,
-            exercise_workout_id,
-            quality,
-            quantity,
-            set_type.to_string()
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        db.sets()
+            .insert(&id, &user_id, &exercise_workout_id, quality, quantity, &set_type)
+            .await?;
 
         Self::find_by_id(db, id)
             .await?
             .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(Set, "SELECT * FROM sets WHERE id = ? LIMIT 1", id)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?
-        )
+    /// Inserts rows built elsewhere as they are, ids and timestamps
+    /// included. For bulk loads like the generator.
+    #[tracing::instrument(name = "Set::insert_many", skip_all)]
+    pub async fn insert_many(db: &dyn Database, sets: &[Self]) -> Result<()> {
+        db.sets().insert_many(sets).await
+    }
 
+    #[tracing::instrument(name = "Set::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.sets().find_by_id(&id).await
     }
 
-    pub async fn find_all_by_exercise_workout_id(db: &Pool<MySql>, exercise_workout_id: String) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(Set, "SELECT * FROM sets WHERE exercise_workout_id = ? ORDER BY set_type ASC, created_at ASC", exercise_workout_id)
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?
-        )
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.sets().stream_by_user_id(user_id)
+    }
 
+    #[tracing::instrument(name = "Set::find_all_by_exercise_workout_id", skip_all)]
+    pub async fn find_all_by_exercise_workout_id(db: &dyn Database, exercise_workout_id: String) -> Result<Vec<Self>> {
+        db.sets().find_all_by_exercise_workout_id(&exercise_workout_id).await
     }
 
-    pub async fn find_all_by_user_id(db: &Pool<MySql>, user_id: String) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(Set, "SELECT * FROM sets WHERE user_id = ? ORDER BY set_type ASC, created_at ASC", user_id)
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?
-        )
+    #[allow(dead_code)]
+    #[tracing::instrument(name = "Set::find_all_by_user_id", skip_all)]
+    pub async fn find_all_by_user_id(db: &dyn Database, user_id: String) -> Result<Vec<Self>> {
+        db.sets().find_all_by_user_id(&user_id).await
     }
 
-    pub async fn save(&mut self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "UPDATE sets SET quality = ?, quantity = ?, set_type = ? WHERE id = ?",
-            self.quality,
-            self.quantity,
-            self.set_type.to_string(),
-            self.id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+    #[tracing::instrument(name = "Set::save", skip_all)]
+    pub async fn save(&mut self, db: &dyn Database) -> Result<()> {
+        db.sets().update(self).await?;
 
         self.updated_at = Utc::now();
 
         Ok(())
     }
 
-    pub async fn delete(&mut self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "DELETE FROM sets WHERE id = ?",
-            self.id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
-
-        Ok(())
+    #[tracing::instrument(name = "Set::delete", skip_all)]
+    pub async fn delete(&mut self, db: &dyn Database) -> Result<()> {
+        db.sets().delete(&self.id).await
     }
 }
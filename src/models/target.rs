@@ -1,39 +1,34 @@
-use sqlx::{MySql, Pool};
+use crate::{db::Database, error::Result};
 
-use crate::error::{self, Result};
-
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct Target {
     pub id: String,
     pub name: String,
     pub sort: i32,
 }
 
+/// Read access to the fixed list of muscle groups, implemented by every
+/// database backend. Lists are ordered by `sort`.
+#[async_trait::async_trait]
+pub trait TargetRepository: Send + Sync {
+    async fn all(&self) -> Result<Vec<Target>>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<Target>>;
+    async fn all_by_exercise_id(&self, exercise_id: &str) -> Result<Vec<Target>>;
+}
+
 impl Target {
-    pub async fn all(db: &Pool<MySql>) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(Target, "SELECT * FROM targets ORDER BY sort ASC")
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    #[tracing::instrument(name = "Target::all", skip_all)]
+    pub async fn all(db: &dyn Database) -> Result<Vec<Self>> {
+        db.targets().all().await
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(Target, "SELECT * FROM targets WHERE id = ?", id)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    #[tracing::instrument(name = "Target::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.targets().find_by_id(&id).await
     }
 
-    pub async fn all_by_exercise_id(db: &Pool<MySql>, exercise_id: String) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(Target, "SELECT * FROM targets WHERE id IN (SELECT target_id FROM exercise_target WHERE exercise_id = ?) ORDER BY sort ASC", exercise_id)
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    #[tracing::instrument(name = "Target::all_by_exercise_id", skip_all)]
+    pub async fn all_by_exercise_id(db: &dyn Database, exercise_id: String) -> Result<Vec<Self>> {
+        db.targets().all_by_exercise_id(&exercise_id).await
     }
 }
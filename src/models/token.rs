@@ -1,93 +1,183 @@
+use std::time::Duration;
+
 use chrono::Utc;
-use sqlx::{MySql, Pool};
+use futures::stream::BoxStream;
 
 use crate::{
-    error::{self, Error, Result},
-    helpers::security::generate_token,
+    ctx::ClientInfo,
+    db::Database,
+    error::{Error, Result},
+    helpers::security::{generate_token, hash_token, verify_token_hash},
 };
 
 use super::user::User;
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+/// A refresh token. Each login starts a new family (a session), and every
+/// refresh replaces the token with a new one in the same family. Tokens
+/// older than the configured refresh token TTL are treated as gone.
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct Token {
     pub id: String,
     pub user_id: String,
+    pub family_id: String,
+    /// sha256 of the token, the plaintext is only available when created
     pub value: String,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub last_used_at: Option<chrono::DateTime<Utc>>,
+    pub rotated_at: Option<chrono::DateTime<Utc>>,
 
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// Storage for refresh tokens, implemented by every database backend.
+/// Tokens older than `ttl` are never returned.
+#[async_trait::async_trait]
+pub trait TokenRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, family_id: &str, hashed_value: &str, client: &ClientInfo) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<Token>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Token>>;
+    async fn find_by_value(&self, hashed_value: &str, ttl: Duration) -> Result<Option<Token>>;
+    async fn find_active_by_family_id(&self, family_id: &str, ttl: Duration) -> Result<Option<Token>>;
+    async fn find_all_active_by_user_id(&self, user_id: &str, ttl: Duration) -> Result<Vec<Token>>;
+    /// Returns `false` if the token had already been rotated
+    async fn mark_rotated(&self, id: &str) -> Result<bool>;
+    /// Whether the family still has a current token and its user isn't
+    /// disabled, i.e. whether access tokens of the session may still be used
+    async fn is_family_active(&self, family_id: &str, ttl: Duration) -> Result<bool>;
+    /// Sets `last_used_at` of the current token of the family to now
+    async fn touch_family(&self, family_id: &str) -> Result<()>;
+    async fn delete_family(&self, family_id: &str) -> Result<()>;
+    async fn delete_all_by_user_id(&self, user_id: &str) -> Result<()>;
+    async fn delete_all_by_user_id_except_family(&self, user_id: &str, family_id: &str) -> Result<()>;
+    async fn delete_expired(&self, ttl: Duration) -> Result<()>;
+}
+
 impl Token {
-    pub async fn create(db: &Pool<MySql>, user_id: String) -> Result<Self> {
+    /// Creates the first token of a new family. Returns the token together
+    /// with its plaintext value, which is not stored.
+    #[tracing::instrument(name = "Token::create", skip_all)]
+    pub async fn create(db: &dyn Database, user_id: String, client: ClientInfo) -> Result<(Self, String)> {
         let id = uuid::Uuid::new_v4().to_string();
 
-        sqlx::query!(
-            "INSERT INTO tokens(id, user_id, value) VALUE (?, ?, ?)",
-            id,
-            user_id,
-            generate_token()
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        Self::create_in_family(db, id.clone(), user_id, id, client).await
+    }
+
+    #[tracing::instrument(name = "Token::create_in_family", skip_all)]
+    async fn create_in_family(
+        db: &dyn Database,
+        id: String,
+        user_id: String,
+        family_id: String,
+        client: ClientInfo,
+    ) -> Result<(Self, String)> {
+        let value = generate_token();
+
+        db.tokens()
+            .insert(&id, &user_id, &family_id, &hash_token(&value), &client)
+            .await?;
 
-        Self::find_by_id(db, id)
+        let token = Self::find_by_id(db, id)
             .await?
-            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))?;
+
+        Ok((token, value))
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(Token, "SELECT * FROM tokens WHERE id = ? AND created_at > (NOW() - INTERVAL 1 WEEK) LIMIT 1", id)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    #[tracing::instrument(name = "Token::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.tokens().find_by_id(&id).await
     }
 
-    pub async fn find_by_value(db: &Pool<MySql>, value: &str) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(Token, "SELECT * FROM tokens WHERE value = ? AND created_at > (NOW() - INTERVAL 1 WEEK) LIMIT 1", value)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    /// Rotated tokens included, so the whole history of every session
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.tokens().stream_by_user_id(user_id)
     }
 
-    pub async fn find_all_by_user_id(db: &Pool<MySql>, user_id: String) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(Token, "SELECT * FROM tokens WHERE user_id = ? AND created_at > (NOW() - INTERVAL 1 WEEK)", user_id)
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    #[tracing::instrument(name = "Token::find_by_value", skip_all)]
+    pub async fn find_by_value(db: &dyn Database, value: &str, ttl: Duration) -> Result<Option<Self>> {
+        let token = db.tokens().find_by_value(&hash_token(value), ttl).await?;
+
+        Ok(token.filter(|token| verify_token_hash(value, &token.value)))
     }
 
-    pub async fn delete(&self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "DELETE FROM tokens WHERE id = ?",
-            self.id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+    /// The current (not yet rotated) token of the family.
+    #[tracing::instrument(name = "Token::find_active_by_family_id", skip_all)]
+    pub async fn find_active_by_family_id(db: &dyn Database, family_id: String, ttl: Duration) -> Result<Option<Self>> {
+        db.tokens().find_active_by_family_id(&family_id, ttl).await
+    }
 
-        Ok(())
+    /// The current token of every session the user has.
+    #[tracing::instrument(name = "Token::find_all_active_by_user_id", skip_all)]
+    pub async fn find_all_active_by_user_id(db: &dyn Database, user_id: String, ttl: Duration) -> Result<Vec<Self>> {
+        db.tokens().find_all_active_by_user_id(&user_id, ttl).await
     }
 
-    pub async fn delete_expired(db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "DELETE FROM tokens WHERE created_at < (NOW() - INTERVAL 1 WEEK)",
+    /// Replaces this token with a new one in the same family. Returns `None`
+    /// if the token had already been rotated, which means it has been used
+    /// twice and the caller should revoke the family. Run it in a
+    /// transaction, or a failed insert leaves the session without a token.
+    #[tracing::instrument(name = "Token::rotate", skip_all)]
+    pub async fn rotate(&mut self, db: &dyn Database, client: ClientInfo) -> Result<Option<(Self, String)>> {
+        if !db.tokens().mark_rotated(&self.id).await? {
+            return Ok(None);
+        }
+
+        self.rotated_at = Some(Utc::now());
+
+        let next = Self::create_in_family(
+            db,
+            uuid::Uuid::new_v4().to_string(),
+            self.user_id.clone(),
+            self.family_id.clone(),
+            client,
         )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        .await?;
+
+        Ok(Some(next))
+    }
+
+    #[tracing::instrument(name = "Token::is_family_active", skip_all)]
+    pub async fn is_family_active(db: &dyn Database, family_id: String, ttl: Duration) -> Result<bool> {
+        db.tokens().is_family_active(&family_id, ttl).await
+    }
+
+    #[tracing::instrument(name = "Token::touch_family", skip_all)]
+    pub async fn touch_family(db: &dyn Database, family_id: String) -> Result<()> {
+        db.tokens().touch_family(&family_id).await
+    }
+
+    #[tracing::instrument(name = "Token::delete_family", skip_all)]
+    pub async fn delete_family(db: &dyn Database, family_id: String) -> Result<()> {
+        db.tokens().delete_family(&family_id).await
+    }
+
+    #[tracing::instrument(name = "Token::delete_all_by_user_id", skip_all)]
+    pub async fn delete_all_by_user_id(db: &dyn Database, user_id: String) -> Result<()> {
+        db.tokens().delete_all_by_user_id(&user_id).await
+    }
+
+    /// Signs the user out of every session but `family_id`
+    #[tracing::instrument(name = "Token::delete_all_by_user_id_except_family", skip_all)]
+    pub async fn delete_all_by_user_id_except_family(
+        db: &dyn Database,
+        user_id: String,
+        family_id: String,
+    ) -> Result<()> {
+        db.tokens()
+            .delete_all_by_user_id_except_family(&user_id, &family_id)
+            .await
+    }
 
-        Ok(())
+    #[tracing::instrument(name = "Token::delete_expired", skip_all)]
+    pub async fn delete_expired(db: &dyn Database, ttl: Duration) -> Result<()> {
+        db.tokens().delete_expired(ttl).await
     }
 
-    pub async fn user(&self, db: &Pool<MySql>) -> Result<Option<User>> {
+    #[tracing::instrument(name = "Token::user", skip_all)]
+    pub async fn user(&self, db: &dyn Database) -> Result<Option<User>> {
         User::find_by_id(db, self.user_id.clone()).await
     }
 }
@@ -1,73 +1,333 @@
+use std::collections::HashMap;
+
 use chrono::Utc;
-use sqlx::{MySql, Pool};
 
-use crate::error::{self, Error, Result};
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    helpers::{security::verify_password, totp},
+    models::recovery_code::RecoveryCode,
+};
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl TryFrom<String> for Role {
+    type Error = Error;
 
-use super::{exercise::Exercise, token::Token, workout::Workout};
+    fn try_from(value: String) -> Result<Self> {
+        match value.as_str() {
+            "user" => Ok(Self::User),
+            "admin" => Ok(Self::Admin),
+            _ => Err(Error::Other(format!("Unknown Role: {value}"))),
+        }
+    }
+}
+
+/// Stored as text. Decoding goes through `TryFrom` so an unknown value fails
+/// the query instead of the whole process, also for the `query_as!` macros,
+/// which only convert columns with `Into`.
+impl<DB: sqlx::Database> sqlx::Type<DB> for Role
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for Role
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(value: <DB as sqlx::database::HasValueRef<'r>>::ValueRef) -> std::result::Result<Self, sqlx::error::BoxDynError> {
+        Ok(Self::try_from(String::decode(value)?)?)
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.write_str(match self {
+            Self::User => "user",
+            Self::Admin => "admin",
+        })
+    }
+}
+
+/// How much a user has logged, for the admin API
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+pub struct UserUsage {
+    pub workouts: i64,
+    pub sets: i64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct User {
     pub id: String,
     pub email: String,
-    pub password: String,
+    /// Argon2 hash, `None` for accounts that only sign in through OIDC
+    pub password: Option<String>,
+    pub role: Role,
+    /// Disabled users can't log in or refresh their tokens
+    pub disabled_at: Option<chrono::DateTime<Utc>>,
+    pub email_verified_at: Option<chrono::DateTime<Utc>>,
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<chrono::DateTime<Utc>>,
+    pub totp_last_step: Option<i64>,
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// Storage for users, implemented by every database backend
+#[async_trait::async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn insert(&self, id: &str, email: &str, hashed_password: Option<&str>) -> Result<()>;
+    /// Inserts the rows as they are, ids and timestamps included, in one
+    /// transaction
+    async fn insert_many(&self, users: &[User]) -> Result<()>;
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>>;
+    /// `pattern` is a `LIKE` pattern escaped with backslashes
+    async fn search(&self, pattern: &str, limit: i64, offset: i64) -> Result<Vec<User>>;
+    async fn update(&self, user: &User) -> Result<()>;
+    async fn delete(&self, id: &str) -> Result<()>;
+    async fn mark_email_verified(&self, id: &str) -> Result<()>;
+    async fn set_role(&self, id: &str, role: Role) -> Result<()>;
+    async fn set_disabled(&self, id: &str, disabled: bool) -> Result<()>;
+    /// Counts for all `ids` in one query. Users without any workouts or sets
+    /// are left out.
+    async fn usage(&self, ids: &[String]) -> Result<HashMap<String, UserUsage>>;
+    async fn set_totp_secret(&self, id: &str, secret: &str) -> Result<()>;
+    async fn enable_totp(&self, id: &str) -> Result<()>;
+    async fn disable_totp(&self, id: &str) -> Result<()>;
+    /// Returns `false` when `step` isn't newer than the last recorded one
+    async fn record_totp_step(&self, id: &str, step: i64) -> Result<bool>;
+}
+
 impl User {
-    pub async fn create(db: &Pool<MySql>, email: String, hashed_password: String) -> Result<Self> {
+    #[tracing::instrument(name = "User::create", skip_all)]
+    pub async fn create(db: &dyn Database, email: String, hashed_password: String) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
 
-        sqlx::query!(
-            "INSERT INTO users(id, email, password) VALUE (?, ?, ?)",
-            id,
-            email,
-            hashed_password
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        db.users().insert(&id, &email, Some(&hashed_password)).await?;
 
         Self::find_by_id(db, id)
             .await?
             .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
     }
 
-    pub async fn find_by_email(db: &Pool<MySql>, email: &str) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(User, "SELECT * FROM users WHERE email = ? LIMIT 1", email)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    /// Inserts rows built elsewhere as they are, ids and timestamps
+    /// included. For bulk loads like the generator.
+    #[tracing::instrument(name = "User::insert_many", skip_all)]
+    pub async fn insert_many(db: &dyn Database, users: &[Self]) -> Result<()> {
+        db.users().insert_many(users).await
+    }
+
+    /// For accounts created through an identity provider, which have no
+    /// password until the user sets one with a password reset.
+    #[tracing::instrument(name = "User::create_without_password", skip_all)]
+    pub async fn create_without_password(db: &dyn Database, email: String) -> Result<Self> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        db.users().insert(&id, &email, None).await?;
+
+        Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
+    }
+
+    #[tracing::instrument(name = "User::find_by_email", skip_all)]
+    pub async fn find_by_email(db: &dyn Database, email: &str) -> Result<Option<Self>> {
+        db.users().find_by_email(email).await
+    }
+
+    #[tracing::instrument(name = "User::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.users().find_by_id(&id).await
+    }
+
+    /// Users whose email contains `search`, newest first
+    #[tracing::instrument(name = "User::search", skip_all)]
+    pub async fn search(db: &dyn Database, search: &str, limit: i64, offset: i64) -> Result<Vec<Self>> {
+        let pattern = format!(
+            "%{}%",
+            search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+
+        db.users().search(&pattern, limit, offset).await
+    }
+
+    #[tracing::instrument(name = "User::save", skip_all)]
+    pub async fn save(&mut self, db: &dyn Database) -> Result<()> {
+        db.users().update(self).await?;
+
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    /// Deletes the account. Everything the user owns goes with it through
+    /// the foreign keys.
+    #[tracing::instrument(name = "User::delete", skip_all)]
+    pub async fn delete(&mut self, db: &dyn Database) -> Result<()> {
+        db.users().delete(&self.id).await
+    }
+
+    #[tracing::instrument(name = "User::mark_email_verified", skip_all)]
+    pub async fn mark_email_verified(&mut self, db: &dyn Database) -> Result<()> {
+        db.users().mark_email_verified(&self.id).await?;
+
+        self.email_verified_at = Some(Utc::now());
+        self.updated_at = Utc::now();
+
+        Ok(())
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(User, "SELECT * FROM users WHERE id = ? LIMIT 1", id)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    #[tracing::instrument(name = "User::set_role", skip_all)]
+    pub async fn set_role(&mut self, db: &dyn Database, role: Role) -> Result<()> {
+        db.users().set_role(&self.id, role).await?;
+
+        self.role = role;
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "User::disable", skip_all)]
+    pub async fn disable(&mut self, db: &dyn Database) -> Result<()> {
+        db.users().set_disabled(&self.id, true).await?;
+
+        self.disabled_at = Some(Utc::now());
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "User::enable", skip_all)]
+    pub async fn enable(&mut self, db: &dyn Database) -> Result<()> {
+        db.users().set_disabled(&self.id, false).await?;
+
+        self.disabled_at = None;
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "User::usage", skip_all)]
+    pub async fn usage(&self, db: &dyn Database) -> Result<UserUsage> {
+        let mut usage = db.users().usage(std::slice::from_ref(&self.id)).await?;
+
+        Ok(usage.remove(&self.id).unwrap_or_default())
+    }
+
+    /// Usage of many users at once, for lists
+    #[tracing::instrument(name = "User::usage_by_id", skip_all)]
+    pub async fn usage_by_id(db: &dyn Database, users: &[User]) -> Result<HashMap<String, UserUsage>> {
+        if users.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ids: Vec<String> = users.iter().map(|user| user.id.clone()).collect();
+        db.users().usage(&ids).await
     }
 
-    pub async fn create_token(&self, db: &Pool<MySql>) -> Result<Token> {
-        Token::create(db, self.id.clone()).await
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
     }
 
-    pub async fn tokens(&self, db: &Pool<MySql>) -> Result<Vec<Token>> {
-        Token::find_all_by_user_id(db, self.id.clone()).await
+    /// Always `false` for accounts without a password
+    pub fn check_password(&self, password: &str) -> Result<bool> {
+        match &self.password {
+            Some(hash) => verify_password(password, hash),
+            None => Ok(false),
+        }
     }
 
-    pub async fn exercises(&self, db: &Pool<MySql>) -> Result<Vec<Exercise>> {
-        Exercise::find_all_by_user_id(db, self.id.clone()).await
+    /// For confirming changes to the account. Accounts that only sign in
+    /// through OIDC have no password to confirm with, so they always pass.
+    pub fn confirm_password(&self, password: &str) -> Result<bool> {
+        match &self.password {
+            Some(hash) => verify_password(password, hash),
+            None => Ok(true),
+        }
     }
 
-    pub async fn workouts(&self, db: &Pool<MySql>) -> Result<Vec<Workout>> {
-        Workout::find_all_done_by_user_id(db, self.id.clone()).await
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+
+    /// Stores a new, not yet confirmed, TOTP secret. Replaces any earlier
+    /// unconfirmed one.
+    #[tracing::instrument(name = "User::set_totp_secret", skip_all)]
+    pub async fn set_totp_secret(&mut self, db: &dyn Database, secret: String) -> Result<()> {
+        db.users().set_totp_secret(&self.id, &secret).await?;
+
+        self.totp_secret = Some(secret);
+        self.totp_enabled_at = None;
+        self.totp_last_step = None;
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "User::enable_totp", skip_all)]
+    pub async fn enable_totp(&mut self, db: &dyn Database) -> Result<()> {
+        db.users().enable_totp(&self.id).await?;
+
+        self.totp_enabled_at = Some(Utc::now());
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "User::disable_totp", skip_all)]
+    pub async fn disable_totp(&mut self, db: &dyn Database) -> Result<()> {
+        db.users().disable_totp(&self.id).await?;
+
+        self.totp_secret = None;
+        self.totp_enabled_at = None;
+        self.totp_last_step = None;
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    /// Remembers that a code for `step` was used. Returns `false` if a code
+    /// for this or a later step was already accepted, in which case the code
+    /// must not be honoured.
+    #[tracing::instrument(name = "User::record_totp_step", skip_all)]
+    pub async fn record_totp_step(&mut self, db: &dyn Database, step: i64) -> Result<bool> {
+        let recorded = db.users().record_totp_step(&self.id, step).await?;
+
+        self.totp_last_step = Some(step);
+
+        Ok(recorded)
+    }
+
+    /// Accepts either a code from the authenticator app or an unused
+    /// recovery code. Both can only be used once.
+    #[tracing::instrument(name = "User::verify_second_factor", skip_all)]
+    pub async fn verify_second_factor(&mut self, db: &dyn Database, code: &str) -> Result<bool> {
+        let Some(secret) = self.totp_secret.clone() else {
+            return Ok(false);
+        };
+
+        if let Some(step) = totp::verify(&secret, code)? {
+            return self.record_totp_step(db, step).await;
+        }
+
+        RecoveryCode::consume(db, self.id.clone(), code).await
     }
 
-    pub async fn current_workout(&self, db: &Pool<MySql>) -> Result<Option<Workout>> {
-        Workout::find_current_by_user_id(db, self.id.clone()).await
+    pub fn is_totp_enabled(&self) -> bool {
+        self.totp_enabled_at.is_some()
     }
 }
@@ -0,0 +1,82 @@
+use chrono::Utc;
+use futures::stream::BoxStream;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+};
+
+use super::user::User;
+
+/// Links an account at an external identity provider to a user.
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
+pub struct UserIdentity {
+    pub id: String,
+    pub user_id: String,
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub last_login_at: Option<chrono::DateTime<Utc>>,
+
+    pub created_at: chrono::DateTime<Utc>,
+    pub updated_at: chrono::DateTime<Utc>,
+}
+
+/// Storage for linked identities, implemented by every database backend
+#[async_trait::async_trait]
+pub trait UserIdentityRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str, issuer: &str, subject: &str, email: Option<&str>) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<UserIdentity>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<UserIdentity>>;
+    async fn find_by_subject(&self, issuer: &str, subject: &str) -> Result<Option<UserIdentity>>;
+    async fn touch(&self, id: &str) -> Result<()>;
+}
+
+impl UserIdentity {
+    #[tracing::instrument(name = "UserIdentity::create", skip_all)]
+    pub async fn create(
+        db: &dyn Database,
+        user_id: String,
+        issuer: String,
+        subject: String,
+        email: Option<String>,
+    ) -> Result<Self> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        db.user_identities()
+            .insert(&id, &user_id, &issuer, &subject, email.as_deref())
+            .await?;
+
+        Self::find_by_id(db, id)
+            .await?
+            .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
+    }
+
+    #[tracing::instrument(name = "UserIdentity::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.user_identities().find_by_id(&id).await
+    }
+
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.user_identities().stream_by_user_id(user_id)
+    }
+
+    #[tracing::instrument(name = "UserIdentity::find_by_subject", skip_all)]
+    pub async fn find_by_subject(db: &dyn Database, issuer: &str, subject: &str) -> Result<Option<Self>> {
+        db.user_identities().find_by_subject(issuer, subject).await
+    }
+
+    #[tracing::instrument(name = "UserIdentity::touch", skip_all)]
+    pub async fn touch(&mut self, db: &dyn Database) -> Result<()> {
+        db.user_identities().touch(&self.id).await?;
+
+        self.last_login_at = Some(Utc::now());
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "UserIdentity::user", skip_all)]
+    pub async fn user(&self, db: &dyn Database) -> Result<Option<User>> {
+        User::find_by_id(db, self.user_id.clone()).await
+    }
+}
@@ -1,7 +1,11 @@
 use chrono::Utc;
-use sqlx::{MySql, Pool};
+use futures::stream::BoxStream;
 
-use crate::error::{self, Error, Result};
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    pagination::{ListParams, Order, Page, SortField, SortValue},
+};
 
 use super::exercise_workout::ExerciseWorkout;
 
@@ -21,81 +25,122 @@ impl From<String> for WorkoutStatus {
     }
 }
 
-impl ToString for WorkoutStatus {
-    fn to_string(&self) -> String {
-        match self {
+impl std::fmt::Display for WorkoutStatus {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.write_str(match self {
             Self::Ongoing => "ongoing",
             Self::Done => "done",
-        }
-        .to_string()
+        })
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct Workout {
     pub id: String,
     pub user_id: String,
+    #[sqlx(try_from = "String")]
     pub status: WorkoutStatus,
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// What workout lists can be sorted by
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkoutSort {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl SortField for WorkoutSort {
+    fn column(&self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::UpdatedAt => "updated_at",
+        }
+    }
+
+    fn is_timestamp(&self) -> bool {
+        true
+    }
+
+    /// Newest first
+    fn default_order(&self) -> Order {
+        Order::Desc
+    }
+}
+
+/// Storage for workouts, implemented by every database backend
+#[async_trait::async_trait]
+pub trait WorkoutRepository: Send + Sync {
+    async fn insert(&self, id: &str, user_id: &str) -> Result<()>;
+    /// Inserts the rows as they are, ids and timestamps included, in one
+    /// transaction
+    async fn insert_many(&self, workouts: &[Workout]) -> Result<()>;
+    async fn find_by_id(&self, id: &str) -> Result<Option<Workout>>;
+    fn stream_by_user_id(&self, user_id: String) -> BoxStream<'_, Result<Workout>>;
+    /// One page more than `params.limit` when there is a next page, and
+    /// the total without the cursor
+    async fn list_done_by_user_id(&self, user_id: &str, params: &ListParams<WorkoutSort>) -> Result<(Vec<Workout>, i64)>;
+    async fn find_current_by_user_id(&self, user_id: &str) -> Result<Option<Workout>>;
+    async fn finish(&self, id: &str) -> Result<()>;
+    /// Finished workouts containing the exercise, paged like
+    /// `list_done_by_user_id`
+    async fn list_where_exercise_is_used(&self, exercise_id: &str, params: &ListParams<WorkoutSort>) -> Result<(Vec<Workout>, i64)>;
+    async fn delete(&self, id: &str) -> Result<()>;
+}
+
 impl Workout {
+    #[tracing::instrument(name = "Workout::create", skip_all)]
     pub async fn create(
-        db: &Pool<MySql>,
+        db: &dyn Database,
         user_id: String,
     ) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
 
-        sqlx::query!(
-            "INSERT INTO workout(id, user_id) VALUE (?, ?)",
-            id,
-            user_id,
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+        db.workouts().insert(&id, &user_id).await?;
 
         Self::find_by_id(db, id)
             .await?
             .ok_or(Error::WTF("Inserted ID doesn't exist".into()))
     }
 
-    pub async fn find_by_id(db: &Pool<MySql>, id: String) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(Workout, "SELECT * FROM workout WHERE id = ? LIMIT 1", id)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    /// Inserts rows built elsewhere as they are, ids and timestamps
+    /// included. For bulk loads like the generator.
+    #[tracing::instrument(name = "Workout::insert_many", skip_all)]
+    pub async fn insert_many(db: &dyn Database, workouts: &[Self]) -> Result<()> {
+        db.workouts().insert_many(workouts).await
+    }
+
+    #[tracing::instrument(name = "Workout::find_by_id", skip_all)]
+    pub async fn find_by_id(db: &dyn Database, id: String) -> Result<Option<Self>> {
+        db.workouts().find_by_id(&id).await
+    }
+
+    pub fn stream_by_user_id(db: &dyn Database, user_id: String) -> BoxStream<'_, Result<Self>> {
+        db.workouts().stream_by_user_id(user_id)
     }
 
-    pub async fn find_all_done_by_user_id(db: &Pool<MySql>, user_id: String) -> Result<Vec<Self>> {
-        Ok(
-            sqlx::query_as!(Workout, "SELECT * FROM workout WHERE user_id = ? AND status = 'done'", user_id)
-                .fetch_all(db)
-                .await
-                .map_err(error::from_sqlx_error)?
-        )
+    #[tracing::instrument(name = "Workout::list_done_by_user_id", skip_all)]
+    pub async fn list_done_by_user_id(
+        db: &dyn Database,
+        user_id: String,
+        params: &ListParams<WorkoutSort>,
+    ) -> Result<Page<Self>> {
+        let (workouts, total) = db.workouts().list_done_by_user_id(&user_id, params).await?;
+
+        Ok(Page::new(workouts, total, params, Self::sort_value, |workout| &workout.id))
     }
 
-    pub async fn find_current_by_user_id(db: &Pool<MySql>, user_id: String) -> Result<Option<Self>> {
-        Ok(
-            sqlx::query_as!(Workout, "SELECT * FROM workout WHERE user_id = ? and status = 'ongoing'", user_id)
-                .fetch_optional(db)
-                .await
-                .map_err(error::from_sqlx_error)?,
-        )
+    #[tracing::instrument(name = "Workout::find_current_by_user_id", skip_all)]
+    pub async fn find_current_by_user_id(db: &dyn Database, user_id: String) -> Result<Option<Self>> {
+        db.workouts().find_current_by_user_id(&user_id).await
     }
 
-    pub async fn finish(&mut self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "UPDATE workout SET status = 'done' WHERE id = ?",
-            self.id,
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+    #[tracing::instrument(name = "Workout::finish", skip_all)]
+    pub async fn finish(&mut self, db: &dyn Database) -> Result<()> {
+        db.workouts().finish(&self.id).await?;
 
         self.status = WorkoutStatus::Done;
         self.updated_at = Utc::now();
@@ -103,31 +148,31 @@ impl Workout {
         Ok(())
     }
 
-    pub async fn exercise_workouts(&self, db: &Pool<MySql>) -> Result<Vec<ExerciseWorkout>> {
-        Ok(sqlx::query_as!(ExerciseWorkout, "SELECT * FROM exercise_workout WHERE workout_id = ? ORDER BY created_at DESC", self.id)
-            .fetch_all(db)
-            .await
-            .map_err(error::from_sqlx_error)?)
+    #[tracing::instrument(name = "Workout::exercise_workouts", skip_all)]
+    pub async fn exercise_workouts(&self, db: &dyn Database) -> Result<Vec<ExerciseWorkout>> {
+        db.exercise_workouts().find_all_by_workout_id(&self.id).await
     }
 
-    pub async fn find_all_where_exercised_is_used(db: &Pool<MySql>, exercise_id: String) -> Result<Vec<Workout>> {
-        Ok(
-            sqlx::query_as!(Workout, "SELECT * FROM workout WHERE id IN (SELECT workout_id FROM exercise_workout WHERE exercise_id = ?) AND status <> 'ongoing' ORDER BY created_at DESC", exercise_id)
-            .fetch_all(db)
-            .await
-            .map_err(error::from_sqlx_error)?
-        )
+    #[tracing::instrument(name = "Workout::list_where_exercise_is_used", skip_all)]
+    pub async fn list_where_exercise_is_used(
+        db: &dyn Database,
+        exercise_id: String,
+        params: &ListParams<WorkoutSort>,
+    ) -> Result<Page<Self>> {
+        let (workouts, total) = db.workouts().list_where_exercise_is_used(&exercise_id, params).await?;
+
+        Ok(Page::new(workouts, total, params, Self::sort_value, |workout| &workout.id))
     }
 
-    pub async fn delete(&mut self, db: &Pool<MySql>) -> Result<()> {
-        sqlx::query!(
-            "DELETE FROM workout WHERE id = ?",
-            self.id
-        )
-        .execute(db)
-        .await
-        .map_err(error::from_sqlx_error)?;
+    fn sort_value(&self, sort: WorkoutSort) -> SortValue {
+        SortValue::Time(match sort {
+            WorkoutSort::CreatedAt => self.created_at,
+            WorkoutSort::UpdatedAt => self.updated_at,
+        })
+    }
 
-        Ok(())
+    #[tracing::instrument(name = "Workout::delete", skip_all)]
+    pub async fn delete(&mut self, db: &dyn Database) -> Result<()> {
+        db.workouts().delete(&self.id).await
     }
 }
@@ -0,0 +1,125 @@
+//! Cursor pagination for list endpoints. A cursor holds the sort value and
+//! id of the last item of a page, so the next page starts right after it
+//! even when rows were added in between, which offsets can't do.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+
+use crate::response::Meta;
+
+pub const DEFAULT_LIMIT: i64 = 50;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// A field a list can be sorted by
+pub trait SortField: Copy + Send + Sync {
+    /// Column of the listed table, also the name used in cursors
+    fn column(&self) -> &'static str;
+    fn is_timestamp(&self) -> bool;
+    fn default_order(&self) -> Order;
+}
+
+/// Value of the sort column, bound differently per backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortValue {
+    Time(DateTime<Utc>),
+    Text(String),
+}
+
+/// A list request after parsing and validation
+#[derive(Debug, Clone)]
+pub struct ListParams<S> {
+    pub limit: i64,
+    /// Sort value and id of the last item of the previous page
+    pub after: Option<(SortValue, String)>,
+    /// Only items created at or after this
+    pub from: Option<DateTime<Utc>>,
+    /// Only items created at or before this
+    pub to: Option<DateTime<Utc>>,
+    pub sort: S,
+    pub order: Order,
+}
+
+impl<S: SortField + Default> Default for ListParams<S> {
+    fn default() -> Self {
+        let sort = S::default();
+
+        Self {
+            limit: DEFAULT_LIMIT,
+            after: None,
+            from: None,
+            to: None,
+            sort,
+            order: sort.default_order(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    /// Items matching the filters over all pages
+    pub total: i64,
+}
+
+impl<T> Page<T> {
+    /// `items` is what the repository returned for `params`, which is one
+    /// more than the limit when there is a next page
+    pub fn new<S: SortField>(
+        mut items: Vec<T>,
+        total: i64,
+        params: &ListParams<S>,
+        sort_value: impl Fn(&T, S) -> SortValue,
+        id: impl Fn(&T) -> &str,
+    ) -> Self {
+        let mut next_cursor = None;
+        if items.len() as i64 > params.limit {
+            items.truncate(params.limit as usize);
+            next_cursor = items
+                .last()
+                .map(|last| encode_cursor(params.sort, &sort_value(last, params.sort), id(last)));
+        }
+
+        Self { items, next_cursor, total }
+    }
+
+    pub fn meta(&self) -> Meta {
+        Meta {
+            next_cursor: self.next_cursor.clone(),
+            total: self.total,
+        }
+    }
+}
+
+fn encode_cursor<S: SortField>(sort: S, value: &SortValue, id: &str) -> String {
+    let value = match value {
+        SortValue::Time(time) => time.to_rfc3339(),
+        SortValue::Text(text) => text.clone(),
+    };
+
+    URL_SAFE_NO_PAD.encode(serde_json::json!([sort.column(), value, id]).to_string())
+}
+
+/// `None` for cursors that weren't made for `sort`
+pub fn decode_cursor<S: SortField>(sort: S, cursor: &str) -> Option<(SortValue, String)> {
+    let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let (column, value, id): (String, String, String) = serde_json::from_slice(&json).ok()?;
+
+    if column != sort.column() {
+        return None;
+    }
+
+    let value = if sort.is_timestamp() {
+        SortValue::Time(DateTime::parse_from_rfc3339(&value).ok()?.with_timezone(&Utc))
+    } else {
+        SortValue::Text(value)
+    };
+
+    Some((value, id))
+}
@@ -0,0 +1,230 @@
+use std::{panic::AssertUnwindSafe, time::Duration};
+
+use futures::FutureExt;
+use tokio::{sync::watch, task::JoinSet};
+use tracing::Instrument;
+
+use crate::{
+    db::Database,
+    error::{Error, Result},
+    mailer::Mail,
+    models::{
+        email_change::EmailChange,
+        email_verification::EmailVerification,
+        job::{Job, JobPayload},
+        password_reset::PasswordReset,
+        user::User,
+    },
+    ApiState,
+};
+
+/// Queues `payload` to run on one of the workers, possibly on another
+/// instance. Queued through a transaction, the job only runs once that
+/// commits.
+pub async fn enqueue(state: &ApiState, db: &dyn Database, payload: JobPayload) -> Result<Job> {
+    Job::enqueue(db, &payload, state.config.queue.max_attempts).await
+}
+
+/// Workers processing the job queue inside this server.
+pub struct Workers {
+    shutdown: watch::Sender<bool>,
+    tasks: JoinSet<()>,
+}
+
+impl Workers {
+    pub fn start(state: ApiState) -> Self {
+        let shutdown = watch::channel(false).0;
+        let mut tasks = JoinSet::new();
+        // Tells apart the workers of different instances in `locked_by`
+        let instance = uuid::Uuid::new_v4().simple().to_string();
+
+        for index in 0..state.config.queue.workers {
+            let worker = format!("{}-{index}", &instance[..12]);
+            tasks.spawn(work(state.clone(), worker, shutdown.subscribe()));
+        }
+
+        tracing::info!(workers = state.config.queue.workers, "Started job workers");
+
+        Self { shutdown, tasks }
+    }
+
+    /// Stops claiming new jobs and waits for the running ones to finish
+    pub async fn shutdown(mut self) {
+        let _ = self.shutdown.send(true);
+
+        while self.tasks.join_next().await.is_some() {}
+
+        tracing::info!("Job workers stopped");
+    }
+}
+
+/// Runs the due jobs one after another on the calling task, for tools and
+/// tests that don't start workers. Returns how many ran.
+pub async fn run_pending(state: &ApiState) -> Result<usize> {
+    let timeout = Duration::from_secs(state.config.server.job_timeout_secs);
+    let mut ran = 0;
+
+    while let Some(job) = Job::claim(&state.db, "inline").await? {
+        process(state, job, timeout).await;
+        ran += 1;
+    }
+
+    Ok(ran)
+}
+
+async fn work(state: ApiState, worker: String, mut shutdown: watch::Receiver<bool>) {
+    let poll_interval = Duration::from_millis(state.config.queue.poll_interval_ms);
+    let timeout = Duration::from_secs(state.config.server.job_timeout_secs);
+
+    while !*shutdown.borrow() {
+        let job = match Job::claim(&state.db, &worker).await {
+            Ok(job) => job,
+            Err(err) => {
+                tracing::error!(error = %err, worker, "Failed to claim job");
+                None
+            }
+        };
+
+        let Some(job) = job else {
+            // Nothing to do, wait a bit unless we're shutting down
+            tokio::select! {
+                _ = tokio::time::sleep(poll_interval) => (),
+                _ = shutdown.changed() => (),
+            }
+            continue;
+        };
+
+        let span = tracing::info_span!("job", id = job.id, kind = job.kind, attempt = job.attempts, worker);
+        process(&state, job, timeout).instrument(span).await;
+    }
+}
+
+async fn process(state: &ApiState, mut job: Job, timeout: Duration) {
+    let result = match job.payload() {
+        Ok(payload) => {
+            match tokio::time::timeout(timeout, AssertUnwindSafe(perform(state, payload)).catch_unwind()).await {
+                Ok(Ok(result)) => result,
+                Ok(Err(_)) => Err(Error::Other("Job panicked".to_string())),
+                Err(_) => Err(Error::Other(format!("Job timed out after {}s", timeout.as_secs()))),
+            }
+        }
+        Err(err) => Err(err),
+    };
+
+    let (mut outcome, saved) = match result {
+        Ok(()) => ("done", job.complete(&state.db).await),
+        Err(err) => {
+            let saved = job.fail(&state.db, &err.to_string()).await;
+            tracing::warn!(error = %err, status = %job.status, "Job failed");
+            ("failed", saved)
+        }
+    };
+
+    match saved {
+        Ok(true) => {}
+        // Another worker has it by now and its result is the one that counts
+        Ok(false) => {
+            tracing::warn!("Lost the job to another worker, dropping the result");
+            outcome = "lost";
+        }
+        Err(err) => tracing::error!(error = %err, "Failed to save job result"),
+    }
+
+    metrics::counter!("jobs_processed_total", "kind" => job.kind.clone(), "outcome" => outcome).increment(1);
+}
+
+async fn perform(state: &ApiState, payload: JobPayload) -> Result<()> {
+    let mail = match payload {
+        JobPayload::SendVerification { user_id } => verification_mail(state, user_id).await?,
+        JobPayload::SendPasswordReset { user_id } => password_reset_mail(state, user_id).await?,
+        JobPayload::SendEmailChange { user_id, new_email } => email_change_mail(state, user_id, new_email).await?,
+        JobPayload::SendEmailChanged { user_id, old_email } => email_changed_mail(state, user_id, old_email).await?,
+    };
+
+    match mail {
+        Some(mail) => state.mailer.send(mail).await,
+        // The user is gone or doesn't need the mail anymore
+        None => Ok(()),
+    }
+}
+
+/// Every attempt replaces the earlier links and codes of the user, only the
+/// one in the mail that went out works.
+async fn verification_mail(state: &ApiState, user_id: String) -> Result<Option<Mail>> {
+    let Some(user) = User::find_by_id(&state.db, user_id).await? else {
+        return Ok(None);
+    };
+    if user.is_email_verified() {
+        return Ok(None);
+    }
+
+    EmailVerification::delete_by_user_id(&state.db, user.id.clone()).await?;
+    let (_, token) = EmailVerification::create(&state.db, user.id.clone()).await?;
+
+    Ok(Some(Mail {
+        to: user.email,
+        subject: "Verify your email".to_string(),
+        body: format!(
+            "Welcome! Please verify your email by opening the link below.\n\n\
+             {}/api/auth/verify/{token}\n\n\
+             The link is valid for 24 hours.",
+            state.config.server.public_url
+        ),
+    }))
+}
+
+async fn password_reset_mail(state: &ApiState, user_id: String) -> Result<Option<Mail>> {
+    let Some(user) = User::find_by_id(&state.db, user_id).await? else {
+        return Ok(None);
+    };
+
+    PasswordReset::delete_by_user_id(&state.db, user.id.clone()).await?;
+    let (_, code) = PasswordReset::create(&state.db, user.id.clone()).await?;
+
+    Ok(Some(Mail {
+        to: user.email,
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Someone asked to reset the password for your account.\n\n\
+             Your reset code is: {code}\n\n\
+             The code is valid for one hour. If you didn't ask for this you can ignore this email."
+        ),
+    }))
+}
+
+async fn email_change_mail(state: &ApiState, user_id: String, new_email: String) -> Result<Option<Mail>> {
+    let Some(user) = User::find_by_id(&state.db, user_id).await? else {
+        return Ok(None);
+    };
+
+    EmailChange::delete_by_user_id(&state.db, user.id.clone()).await?;
+    let (_, token) = EmailChange::create(&state.db, user.id.clone(), new_email.clone()).await?;
+
+    Ok(Some(Mail {
+        to: new_email,
+        subject: "Confirm your new email".to_string(),
+        body: format!(
+            "Someone asked to use this address for their account.\n\n\
+             Open the link below to confirm the change.\n\n\
+             {}/api/auth/email/confirm/{token}\n\n\
+             The link is valid for 24 hours. If you didn't ask for this you can ignore this email.",
+            state.config.server.public_url
+        ),
+    }))
+}
+
+async fn email_changed_mail(state: &ApiState, user_id: String, old_email: String) -> Result<Option<Mail>> {
+    let Some(user) = User::find_by_id(&state.db, user_id).await? else {
+        return Ok(None);
+    };
+
+    Ok(Some(Mail {
+        to: old_email,
+        subject: "Your email was changed".to_string(),
+        body: format!(
+            "The email for your account was changed to {}.\n\n\
+             If you didn't do this, reset your password and contact support.",
+            user.email
+        ),
+    }))
+}
@@ -1,15 +1,37 @@
+use std::collections::BTreeMap;
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub enum ResponseStatus {
     Success,
     Failure,
 }
 
+/// Problems with a request, keyed by field name
+pub type FieldErrors = BTreeMap<String, Vec<String>>;
+
+/// Paging details of a list response
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+pub struct Meta {
+    /// Pass as `cursor` to get the next page, `null` on the last one
+    pub next_cursor: Option<String>,
+    pub total: i64,
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct Response<T> {
     status: ResponseStatus,
     message: String,
+    /// Stable error code, see `Error::code`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<FieldErrors>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
 }
 
 impl<T> Response<T> {
@@ -17,7 +39,11 @@ impl<T> Response<T> {
         Self {
             status: ResponseStatus::Success,
             message: "Success".into(),
+            code: None,
+            detail: None,
             data: Some(data),
+            errors: None,
+            meta: None,
         }
     }
 
@@ -25,7 +51,27 @@ impl<T> Response<T> {
         Self {
             status: ResponseStatus::Failure,
             message: message.into(),
+            code: None,
+            detail: None,
             data: None,
+            errors: None,
+            meta: None,
         }
     }
+
+    pub fn with_code(self, code: String) -> Self {
+        Self { code: Some(code), ..self }
+    }
+
+    pub fn with_detail(self, detail: String) -> Self {
+        Self { detail: Some(detail), ..self }
+    }
+
+    pub fn with_errors(self, errors: FieldErrors) -> Self {
+        Self { errors: Some(errors), ..self }
+    }
+
+    pub fn with_meta(self, meta: Meta) -> Self {
+        Self { meta: Some(meta), ..self }
+    }
 }
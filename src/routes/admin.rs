@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    routing::{delete, get, put},
+    Json, Router,
+};
+
+use crate::{
+    ctx::Ctx,
+    dtos::admin::{AdminUserResponse, JobResponse, SearchJobsQuery, SearchUsersQuery, SetRolePayload},
+    error::{AuthError, Error, Resource, Result},
+    middlewares::{
+        auth::require_admin,
+        validation::{ValidJson, ValidQuery},
+    },
+    models::{
+        job::{Job, JobStatus},
+        token::Token,
+        user::User,
+    },
+    response::Response,
+    ApiState,
+};
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/api/admin/users", get(get_users))
+        .route("/api/admin/users/:id", get(get_user))
+        .route("/api/admin/users/:id/disable", put(disable_user))
+        .route("/api/admin/users/:id/enable", put(enable_user))
+        .route("/api/admin/users/:id/role", put(set_role))
+        .route("/api/admin/users/:id/tokens", delete(logout_user))
+        .route("/api/admin/jobs", get(get_jobs))
+        .route("/api/admin/jobs/:id", get(get_job))
+        .route("/api/admin/jobs/:id/retry", put(retry_job))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_admin))
+        .with_state(state)
+}
+
+async fn find_user(state: &ApiState, id: String) -> Result<User> {
+    User::find_by_id(&state.db, id.clone())
+        .await?
+        .ok_or(Error::NotFound(Resource::User, format!("User with id {}", id)))
+}
+
+async fn admin_response(state: &ApiState, user: User) -> Result<AdminUserResponse> {
+    let usage = user.usage(&state.db).await?;
+
+    Ok(AdminUserResponse::new(user, usage))
+}
+
+async fn get_users(
+    State(state): State<ApiState>,
+    ValidQuery(query): ValidQuery<SearchUsersQuery>,
+) -> Result<(StatusCode, Json<Response<Vec<AdminUserResponse>>>)> {
+    let users = User::search(
+        &state.db,
+        query.search.as_deref().unwrap_or_default(),
+        query.limit.unwrap_or(50),
+        query.offset.unwrap_or(0),
+    )
+    .await?;
+
+    let mut usage = User::usage_by_id(&state.db, &users).await?;
+    let response = users
+        .into_iter()
+        .map(|user| {
+            let usage = usage.remove(&user.id).unwrap_or_default();
+            AdminUserResponse::new(user, usage)
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(Response::success(response))))
+}
+
+async fn get_user(
+    State(state): State<ApiState>,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<AdminUserResponse>>)> {
+    let user = find_user(&state, id).await?;
+
+    Ok((StatusCode::OK, Json(Response::success(admin_response(&state, user).await?))))
+}
+
+/// Disables the account and signs it out everywhere
+async fn disable_user(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<AdminUserResponse>>)> {
+    if id == ctx.user_id() {
+        return Err(Error::AuthError(AuthError::CannotModifySelf));
+    }
+
+    let mut user = find_user(&state, id).await?;
+
+    let tx = state.db.begin().await?;
+    user.disable(&*tx).await?;
+    Token::delete_all_by_user_id(&*tx, user.id.clone()).await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(Response::success(admin_response(&state, user).await?))))
+}
+
+async fn enable_user(
+    State(state): State<ApiState>,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<AdminUserResponse>>)> {
+    let mut user = find_user(&state, id).await?;
+
+    user.enable(&state.db).await?;
+
+    Ok((StatusCode::OK, Json(Response::success(admin_response(&state, user).await?))))
+}
+
+async fn set_role(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    Path((id,)): Path<(String,)>,
+    ValidJson(payload): ValidJson<SetRolePayload>,
+) -> Result<(StatusCode, Json<Response<AdminUserResponse>>)> {
+    // Keeps the last admin from locking everyone out by accident
+    if id == ctx.user_id() {
+        return Err(Error::AuthError(AuthError::CannotModifySelf));
+    }
+
+    let mut user = find_user(&state, id).await?;
+
+    user.set_role(&state.db, payload.role).await?;
+
+    Ok((StatusCode::OK, Json(Response::success(admin_response(&state, user).await?))))
+}
+
+/// Signs the user out of every session. API keys keep working.
+async fn logout_user(
+    State(state): State<ApiState>,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<AdminUserResponse>>)> {
+    let user = find_user(&state, id).await?;
+
+    Token::delete_all_by_user_id(&state.db, user.id.clone()).await?;
+
+    Ok((StatusCode::OK, Json(Response::success(admin_response(&state, user).await?))))
+}
+
+async fn find_job(state: &ApiState, id: String) -> Result<Job> {
+    Job::find_by_id(&state.db, id.clone())
+        .await?
+        .ok_or(Error::NotFound(Resource::Job, format!("Job with id {}", id)))
+}
+
+async fn get_jobs(
+    State(state): State<ApiState>,
+    ValidQuery(query): ValidQuery<SearchJobsQuery>,
+) -> Result<(StatusCode, Json<Response<Vec<JobResponse>>>)> {
+    let jobs = Job::search(
+        &state.db,
+        query.status,
+        query.limit.unwrap_or(50),
+        query.offset.unwrap_or(0),
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(Response::success(jobs.into_iter().map(JobResponse::from).collect())),
+    ))
+}
+
+async fn get_job(
+    State(state): State<ApiState>,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<JobResponse>>)> {
+    let job = find_job(&state, id).await?;
+
+    Ok((StatusCode::OK, Json(Response::success(job.into()))))
+}
+
+/// Runs a dead job again with a fresh set of attempts
+async fn retry_job(
+    State(state): State<ApiState>,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<JobResponse>>)> {
+    let mut job = find_job(&state, id).await?;
+
+    if job.status != JobStatus::Dead {
+        return Err(Error::InvalidState(Resource::Job, "Only dead jobs can be retried".to_string()));
+    }
+
+    job.retry(&state.db).await?;
+
+    Ok((StatusCode::OK, Json(Response::success(job.into()))))
+}
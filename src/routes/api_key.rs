@@ -0,0 +1,80 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    routing::{delete, get, post},
+    Json, Router,
+};
+
+use crate::{
+    ctx::Ctx,
+    dtos::api_key::{ApiKeyResponse, CreateApiKeyPayload, CreatedApiKeyResponse},
+    error::{AuthError, Error, Resource, Result},
+    middlewares::{auth::require_auth, validation::ValidJson},
+    models::api_key::ApiKey,
+    response::Response,
+    ApiState,
+};
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/api/auth/api-keys", post(create_api_key))
+        .route("/api/auth/api-keys", get(get_api_keys))
+        .route("/api/auth/api-keys/:id", delete(delete_api_key))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .with_state(state)
+}
+
+async fn create_api_key(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    ValidJson(payload): ValidJson<CreateApiKeyPayload>,
+) -> Result<(StatusCode, Json<Response<CreatedApiKeyResponse>>)> {
+    let (api_key, key) = ApiKey::create(
+        &state.db,
+        ctx.user_id().to_string(),
+        payload.name,
+        &payload.scopes,
+        payload.expires_at,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Response::success(CreatedApiKeyResponse {
+            key,
+            api_key: api_key.into(),
+        })),
+    ))
+}
+
+async fn get_api_keys(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+) -> Result<(StatusCode, Json<Response<Vec<ApiKeyResponse>>>)> {
+    let api_keys = ApiKey::find_all_by_user_id(&state.db, ctx.user_id().to_string())
+        .await?
+        .into_iter()
+        .map(ApiKeyResponse::from)
+        .collect();
+
+    Ok((StatusCode::OK, Json(Response::success(api_keys))))
+}
+
+async fn delete_api_key(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<ApiKeyResponse>>)> {
+    let Some(mut api_key) = ApiKey::find_by_id(&state.db, id.clone()).await? else {
+        return Err(Error::NotFound(Resource::ApiKey, format!("API key with id {}", id)));
+    };
+
+    if api_key.user_id != ctx.user_id() {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::ApiKey)));
+    }
+
+    api_key.delete(&state.db).await?;
+
+    Ok((StatusCode::OK, Json(Response::success(api_key.into()))))
+}
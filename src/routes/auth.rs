@@ -1,32 +1,47 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use serde_json::{json, Value};
 
 use crate::{
-    ctx::Ctx, dtos::auth::{CreateUserPayload, LoginPayload, LoginResponse, UserResponse}, error::{AuthError, Error, Result}, helpers::security::{hash_password, verify_password}, models::user::User, response::Response, ApiState
+    ctx::{ClientInfo, Ctx}, db::Database, dtos::auth::{ChangeEmailPayload, ChangePasswordPayload, CreateUserPayload, DisableTwoFactorPayload, ForgotPasswordPayload, LoginChallengeResponse, LoginPayload, LoginResponse, LoginResult, LoginTwoFactorPayload, RecoveryCodesResponse, RefreshTokenPayload, ResendVerificationPayload, ResetPasswordPayload, SessionResponse, TwoFactorCodePayload, TwoFactorSetupResponse, TwoFactorStatusResponse, UserResponse}, error::{AuthError, Error, Resource, Result}, helpers::{security::{hash_password, verify_dummy_password}, totp}, middlewares::{auth::UnverifiedUsers, validation::ValidJson}, models::{email_change::EmailChange, email_verification::EmailVerification, job::JobPayload, login_throttle::LoginThrottle, password_reset::PasswordReset, recovery_code::RecoveryCode, token::Token, user::User}, queue, response::Response, ApiState
 };
 use crate::middlewares::auth::require_auth;
 
 pub fn router(state: ApiState) -> Router {
     Router::new()
-        .route("/api/auth/refresh", get(refresh_token))
         .route("/api/auth/logout", delete(logout))
+        .route("/api/auth/logout/all", delete(logout_everywhere))
+        .route("/api/auth/sessions", get(get_sessions))
+        .route("/api/auth/sessions/:id", delete(delete_session))
+        .route("/api/auth/password", put(change_password))
+        .route("/api/auth/email", post(change_email))
+        .route("/api/auth/2fa", get(get_two_factor))
+        .route("/api/auth/2fa/setup", post(setup_two_factor))
+        .route("/api/auth/2fa/confirm", post(confirm_two_factor))
+        .route("/api/auth/2fa/disable", post(disable_two_factor))
         .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
         .route("/api/auth/login", post(login))
+        .route("/api/auth/login/2fa", post(login_two_factor))
+        .route("/api/auth/refresh", post(refresh_token))
         .route("/api/auth/register", post(register))
+        .route("/api/auth/verify/resend", post(resend_verification))
+        .route("/api/auth/verify/:token", get(verify_email))
+        .route("/api/auth/email/confirm/:token", get(confirm_email_change))
+        .route("/api/auth/password/forgot", post(forgot_password))
+        .route("/api/auth/password/reset", post(reset_password))
         .with_state(state)
 }
 
 async fn register(
     State(state): State<ApiState>,
-    Json(payload): Json<CreateUserPayload>,
+    ValidJson(payload): ValidJson<CreateUserPayload>,
 ) -> Result<(StatusCode, Json<Response<UserResponse>>)> {
-    if let Some(_) = User::find_by_email(&state.db, &payload.email).await? {
+    if User::find_by_email(&state.db, &payload.email).await?.is_some() {
         return Err(Error::AuthError(AuthError::EmailAlreadyInUse(
             payload.email,
         )));
@@ -34,55 +49,569 @@ async fn register(
 
     let hashed_password = hash_password(&payload.password)?;
 
-    let user = User::create(&state.db, payload.email, hashed_password).await?;
+    let tx = state.db.begin().await?;
+    let user = User::create(&*tx, payload.email, hashed_password).await?;
+    queue::enqueue(&state, &*tx, JobPayload::SendVerification { user_id: user.id.clone() }).await?;
+    tx.commit().await?;
+
+    metrics::counter!("users_registered_total").increment(1);
 
     Ok((StatusCode::CREATED, Json(Response::success(user.into()))))
 }
 
+/// Failed logins an account gets before it starts getting locked out
+const ACCOUNT_FREE_ATTEMPTS: i32 = 5;
+/// Same for IPs, higher since many users can share one
+const IP_FREE_ATTEMPTS: i32 = 20;
+/// Reset and verification mails an address gets before it's throttled
+const MAIL_FREE_REQUESTS: i32 = 3;
+
 async fn login(
     State(state): State<ApiState>,
-    Json(payload): Json<LoginPayload>,
+    client: ClientInfo,
+    ValidJson(payload): ValidJson<LoginPayload>,
+) -> Result<(StatusCode, Json<Response<LoginResult>>)> {
+    let email_key = LoginThrottle::email_key(&payload.email);
+    let ip_key = client.ip.as_deref().map(LoginThrottle::ip_key);
+
+    for key in std::iter::once(&email_key).chain(ip_key.as_ref()) {
+        if let Some(seconds) = LoginThrottle::retry_after(&state.db, key).await? {
+            return Err(Error::AuthError(AuthError::TooManyLoginAttempts(seconds)));
+        }
+    }
+
+    let user = User::find_by_email(&state.db, &payload.email).await?;
+
+    // Unknown emails and wrong passwords must look the same from outside
+    let user = match user {
+        Some(user) if user.check_password(&payload.password)? => Some(user),
+        Some(user) if user.password.is_some() => None,
+        // Unknown email or an account without a password
+        _ => {
+            verify_dummy_password(&payload.password)?;
+            None
+        }
+    };
+
+    let Some(user) = user else {
+        LoginThrottle::record_failure(&state.db, &email_key, ACCOUNT_FREE_ATTEMPTS).await?;
+        if let Some(ip_key) = &ip_key {
+            LoginThrottle::record_failure(&state.db, ip_key, IP_FREE_ATTEMPTS).await?;
+        }
+
+        return Err(Error::AuthError(AuthError::LoginFailed));
+    };
+
+    if user.is_disabled() {
+        return Err(Error::AuthError(AuthError::AccountDisabled));
+    }
+
+    if !user.is_email_verified() && state.config.auth.unverified_users == UnverifiedUsers::Deny {
+        return Err(Error::AuthError(AuthError::EmailNotVerified));
+    }
+
+    if user.is_totp_enabled() {
+        // The account throttle is only cleared once the second factor is in,
+        // otherwise a stolen password could be used to guess codes forever
+        let (challenge, challenge_expires_at) =
+            state.access_tokens.issue_challenge(user.id.clone(), payload.device_name)?;
+
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(Response::success(LoginResult::Challenge(LoginChallengeResponse {
+                challenge,
+                challenge_expires_at,
+                two_factor_required: true,
+            }))),
+        ));
+    }
+
+    LoginThrottle::clear(&state.db, &email_key).await?;
+
+    let (refresh_token, refresh_value) = Token::create(
+        &state.db,
+        user.id.clone(),
+        ClientInfo {
+            device_name: payload.device_name,
+            ..client
+        },
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Response::success(LoginResult::Tokens(login_response(
+            &state,
+            user,
+            refresh_token,
+            refresh_value,
+        )?))),
+    ))
+}
+
+async fn login_two_factor(
+    State(state): State<ApiState>,
+    client: ClientInfo,
+    ValidJson(payload): ValidJson<LoginTwoFactorPayload>,
 ) -> Result<(StatusCode, Json<Response<LoginResponse>>)> {
-    let user = User::find_by_email(&state.db, &payload.email)
+    let claims = state.access_tokens.verify_challenge(&payload.challenge)?;
+
+    let mut user = User::find_by_id(&state.db, claims.sub)
         .await?
-        .ok_or(Error::NotFound("User".to_string()))?;
+        .filter(User::is_totp_enabled)
+        .ok_or(Error::AuthError(AuthError::InvalidChallenge))?;
 
-    if verify_password(&payload.password, &user.password)? {
-        Ok((
-            StatusCode::CREATED,
-            Json(Response::success(LoginResponse {
-                token: user.create_token(&state.db).await?.value,
-                user: user.into(),
-            })),
-        ))
-    } else {
-        Err(Error::AuthError(AuthError::LoginFailed))
+    if user.is_disabled() {
+        return Err(Error::AuthError(AuthError::AccountDisabled));
     }
+
+    let email_key = LoginThrottle::email_key(&user.email);
+
+    if let Some(seconds) = LoginThrottle::retry_after(&state.db, &email_key).await? {
+        return Err(Error::AuthError(AuthError::TooManyLoginAttempts(seconds)));
+    }
+
+    // The code is used up in the same transaction that creates the session,
+    // so a failed insert doesn't cost the user a recovery code
+    let tx = state.db.begin().await?;
+
+    if !user.verify_second_factor(&*tx, &payload.code).await? {
+        drop(tx);
+        LoginThrottle::record_failure(&state.db, &email_key, ACCOUNT_FREE_ATTEMPTS).await?;
+        return Err(Error::AuthError(AuthError::TwoFactorFailed));
+    }
+
+    let (refresh_token, refresh_value) = Token::create(
+        &*tx,
+        user.id.clone(),
+        ClientInfo {
+            device_name: claims.device_name,
+            ..client
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    LoginThrottle::clear(&state.db, &email_key).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Response::success(login_response(&state, user, refresh_token, refresh_value)?)),
+    ))
+}
+
+pub fn login_response(
+    state: &ApiState,
+    user: User,
+    refresh_token: Token,
+    refresh_value: String,
+) -> Result<LoginResponse> {
+    let (token, token_expires_at) = state.access_tokens.issue(
+        user.id.clone(),
+        refresh_token.family_id.clone(),
+        user.is_email_verified(),
+    )?;
+
+    Ok(LoginResponse {
+        token,
+        token_expires_at,
+        refresh_token: refresh_value,
+        user: user.into(),
+    })
 }
 
 async fn refresh_token(
     State(state): State<ApiState>,
-    ctx: Ctx,
+    client: ClientInfo,
+    ValidJson(payload): ValidJson<RefreshTokenPayload>,
 ) -> Result<(StatusCode, Json<Response<LoginResponse>>)> {
-    let user = ctx.user().clone();
-    let token = user.create_token(&state.db).await?.value;
-    ctx.token().delete(&state.db).await?;
+    let mut token = Token::find_by_value(&state.db, &payload.refresh_token, state.config.refresh_token_ttl())
+        .await?
+        .ok_or(Error::AuthError(AuthError::InvalidToken))?;
+
+    let client = ClientInfo {
+        device_name: token.device_name.clone(),
+        ..client
+    };
+
+    let tx = state.db.begin().await?;
+    let Some((next, next_value)) = token.rotate(&*tx, client).await? else {
+        // The token was already exchanged once, so either the client or an
+        // attacker is holding a stale copy. Kill the whole session.
+        Token::delete_family(&*tx, token.family_id.clone()).await?;
+        tx.commit().await?;
+        return Err(Error::AuthError(AuthError::RefreshTokenReused));
+    };
+    tx.commit().await?;
+
+    let user = token
+        .user(&state.db)
+        .await?
+        .ok_or(Error::WTF("Token exists but user doesn't".to_string()))?;
+
+    if user.is_disabled() {
+        return Err(Error::AuthError(AuthError::AccountDisabled));
+    }
 
     Ok((
         StatusCode::CREATED,
-        Json(Response::success(LoginResponse {
-            token,
-            user: user.into(),
-        })),
+        Json(Response::success(login_response(&state, user, next, next_value)?)),
     ))
 }
 
+/// Revokes the current session, its access tokens stop working right away
+/// along with the refresh token.
 async fn logout(State(state): State<ApiState>, ctx: Ctx) -> Result<Json<Value>> {
-    let user = ctx.user().clone();
+    Token::delete_family(&state.db, ctx.session_id().to_string()).await?;
 
-    for t in user.tokens(&state.db).await? {
-        t.delete(&state.db).await?;
+    Ok(Json(json!({ "status": "Success", "message": "logged out" })))
+}
+
+async fn logout_everywhere(State(state): State<ApiState>, ctx: Ctx) -> Result<Json<Value>> {
+    Token::delete_all_by_user_id(&state.db, ctx.user_id().to_string()).await?;
+
+    Ok(Json(json!({ "status": "Success", "message": "logged out everywhere" })))
+}
+
+async fn get_sessions(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+) -> Result<(StatusCode, Json<Response<Vec<SessionResponse>>>)> {
+    let ttl = state.config.refresh_token_ttl();
+    let sessions = Token::find_all_active_by_user_id(&state.db, ctx.user_id().to_string(), ttl)
+        .await?
+        .into_iter()
+        .map(|token| SessionResponse::from_token(token, ctx.session_id()))
+        .collect();
+
+    Ok((StatusCode::OK, Json(Response::success(sessions))))
+}
+
+async fn delete_session(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    Path((id,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<SessionResponse>>)> {
+    let token = Token::find_active_by_family_id(&state.db, id.clone(), state.config.refresh_token_ttl()).await?;
+
+    let Some(token) = token else {
+        return Err(Error::NotFound(Resource::Session, format!("Session with id {}", id)));
+    };
+
+    if token.user_id != ctx.user_id() {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::Session)));
     }
 
-    Ok(Json(json!({ "status": "Success", "message": "logged out" })))
+    Token::delete_family(&state.db, token.family_id.clone()).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(Response::success(SessionResponse::from_token(token, ctx.session_id()))),
+    ))
+}
+
+async fn verify_email(
+    State(state): State<ApiState>,
+    Path((token,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<UserResponse>>)> {
+    let verification = EmailVerification::find_valid_by_token(&state.db, &token)
+        .await?
+        .ok_or(Error::AuthError(AuthError::InvalidVerificationToken))?;
+
+    let mut user = User::find_by_id(&state.db, verification.user_id.clone())
+        .await?
+        .ok_or(Error::WTF("Email verification exists but user doesn't".to_string()))?;
+
+    let tx = state.db.begin().await?;
+    user.mark_email_verified(&*tx).await?;
+    EmailVerification::delete_by_user_id(&*tx, user.id.clone()).await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(Response::success(user.into()))))
+}
+
+/// Every request counts, whether or not a mail goes out, so these can't be
+/// used to flood an inbox or the queue. Throttled like logins, but with
+/// keys of their own.
+async fn throttle_mail_request(state: &ApiState, client: &ClientInfo, email: &str) -> Result<()> {
+    let email_key = LoginThrottle::mail_key(email);
+    let ip_key = client.ip.as_deref().map(LoginThrottle::mail_ip_key);
+
+    for key in std::iter::once(&email_key).chain(ip_key.as_ref()) {
+        if let Some(seconds) = LoginThrottle::retry_after(&state.db, key).await? {
+            return Err(Error::AuthError(AuthError::TooManyMailRequests(seconds)));
+        }
+    }
+
+    LoginThrottle::record_failure(&state.db, &email_key, MAIL_FREE_REQUESTS).await?;
+    if let Some(ip_key) = &ip_key {
+        LoginThrottle::record_failure(&state.db, ip_key, IP_FREE_ATTEMPTS).await?;
+    }
+
+    Ok(())
+}
+
+async fn resend_verification(
+    State(state): State<ApiState>,
+    client: ClientInfo,
+    ValidJson(payload): ValidJson<ResendVerificationPayload>,
+) -> Result<Json<Value>> {
+    throttle_mail_request(&state, &client, &payload.email).await?;
+
+    // Same response for unknown and already verified emails, see `forgot_password`
+    if let Some(user) = User::find_by_email(&state.db, &payload.email).await? {
+        if !user.is_email_verified() {
+            queue::enqueue(&state, &state.db, JobPayload::SendVerification { user_id: user.id }).await?;
+        }
+    }
+
+    Ok(Json(json!({ "status": "Success", "message": "verification sent if the email needs it" })))
+}
+
+async fn forgot_password(
+    State(state): State<ApiState>,
+    client: ClientInfo,
+    ValidJson(payload): ValidJson<ForgotPasswordPayload>,
+) -> Result<Json<Value>> {
+    throttle_mail_request(&state, &client, &payload.email).await?;
+
+    // Respond the same way whether or not the email is registered so this
+    // can't be used to find out which emails have accounts.
+    if let Some(user) = User::find_by_email(&state.db, &payload.email).await? {
+        queue::enqueue(&state, &state.db, JobPayload::SendPasswordReset { user_id: user.id }).await?;
+    }
+
+    Ok(Json(json!({ "status": "Success", "message": "reset code sent if the email is registered" })))
+}
+
+async fn reset_password(
+    State(state): State<ApiState>,
+    ValidJson(payload): ValidJson<ResetPasswordPayload>,
+) -> Result<Json<Value>> {
+    let mut reset = PasswordReset::find_valid_by_code(&state.db, &payload.code)
+        .await?
+        .ok_or(Error::AuthError(AuthError::InvalidResetCode))?;
+
+    let mut user = User::find_by_id(&state.db, reset.user_id.clone())
+        .await?
+        .ok_or(Error::WTF("Password reset exists but user doesn't".to_string()))?;
+    let password = hash_password(&payload.password)?;
+
+    let tx = state.db.begin().await?;
+    if !reset.consume(&*tx).await? {
+        return Err(Error::AuthError(AuthError::InvalidResetCode));
+    }
+
+    user.password = Some(password);
+    user.save(&*tx).await?;
+
+    Token::delete_all_by_user_id(&*tx, user.id.clone()).await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "status": "Success", "message": "password reset" })))
+}
+
+/// Changes the password and signs out every other session. The one making
+/// the change stays logged in.
+async fn change_password(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    ValidJson(payload): ValidJson<ChangePasswordPayload>,
+) -> Result<Json<Value>> {
+    let mut user = ctx.user(&state.db).await?;
+
+    if !user.confirm_password(&payload.current_password)? {
+        return Err(Error::AuthError(AuthError::WrongPassword));
+    }
+
+    user.password = Some(hash_password(&payload.new_password)?);
+
+    let tx = state.db.begin().await?;
+    user.save(&*tx).await?;
+
+    Token::delete_all_by_user_id_except_family(
+        &*tx,
+        user.id.clone(),
+        ctx.session_id().to_string(),
+    )
+    .await?;
+    PasswordReset::delete_by_user_id(&*tx, user.id.clone()).await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "status": "Success", "message": "password changed" })))
+}
+
+/// Starts an email change. The address isn't switched until the link sent
+/// to the new one is opened, see `confirm_email_change`.
+async fn change_email(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    ValidJson(payload): ValidJson<ChangeEmailPayload>,
+) -> Result<Json<Value>> {
+    let user = ctx.user(&state.db).await?;
+
+    if !user.confirm_password(&payload.password)? {
+        return Err(Error::AuthError(AuthError::WrongPassword));
+    }
+
+    if User::find_by_email(&state.db, &payload.email).await?.is_some() {
+        return Err(Error::AuthError(AuthError::EmailAlreadyInUse(
+            payload.email,
+        )));
+    }
+
+    queue::enqueue(
+        &state,
+        &state.db,
+        JobPayload::SendEmailChange { user_id: user.id, new_email: payload.email },
+    )
+    .await?;
+
+    Ok(Json(json!({ "status": "Success", "message": "confirmation sent to the new email" })))
+}
+
+async fn confirm_email_change(
+    State(state): State<ApiState>,
+    Path((token,)): Path<(String,)>,
+) -> Result<(StatusCode, Json<Response<UserResponse>>)> {
+    let change = EmailChange::find_valid_by_token(&state.db, &token)
+        .await?
+        .ok_or(Error::AuthError(AuthError::InvalidVerificationToken))?;
+
+    // Someone may have registered the address since the change was requested
+    if User::find_by_email(&state.db, &change.new_email).await?.is_some() {
+        EmailChange::delete_by_user_id(&state.db, change.user_id.clone()).await?;
+        return Err(Error::AuthError(AuthError::EmailAlreadyInUse(
+            change.new_email,
+        )));
+    }
+
+    let mut user = User::find_by_id(&state.db, change.user_id.clone())
+        .await?
+        .ok_or(Error::WTF("Email change exists but user doesn't".to_string()))?;
+
+    let old_email = std::mem::replace(&mut user.email, change.new_email);
+
+    let tx = state.db.begin().await?;
+    user.save(&*tx).await?;
+
+    // Opening the link proves the new address works
+    if !user.is_email_verified() {
+        user.mark_email_verified(&*tx).await?;
+    }
+
+    EmailChange::delete_by_user_id(&*tx, user.id.clone()).await?;
+    EmailVerification::delete_by_user_id(&*tx, user.id.clone()).await?;
+
+    queue::enqueue(
+        &state,
+        &*tx,
+        JobPayload::SendEmailChanged { user_id: user.id.clone(), old_email },
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, Json(Response::success(user.into()))))
+}
+
+async fn get_two_factor(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+) -> Result<(StatusCode, Json<Response<TwoFactorStatusResponse>>)> {
+    let user = ctx.user(&state.db).await?;
+    let recovery_codes = RecoveryCode::find_unused_by_user_id(&state.db, user.id.clone()).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(Response::success(TwoFactorStatusResponse {
+            enabled: user.is_totp_enabled(),
+            enabled_at: user.totp_enabled_at,
+            recovery_codes_left: recovery_codes.len(),
+        })),
+    ))
+}
+
+/// Generates a new secret. 2FA isn't enforced until the user proves their
+/// app works by confirming a code.
+async fn setup_two_factor(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+) -> Result<(StatusCode, Json<Response<TwoFactorSetupResponse>>)> {
+    let mut user = ctx.user(&state.db).await?;
+
+    if user.is_totp_enabled() {
+        return Err(Error::AuthError(AuthError::TwoFactorAlreadyEnabled));
+    }
+
+    let secret = totp::new_secret();
+    user.set_totp_secret(&state.db, secret.clone()).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Response::success(TwoFactorSetupResponse {
+            provisioning_uri: totp::provisioning_uri(&secret, &user.email)?,
+            secret,
+        })),
+    ))
+}
+
+async fn confirm_two_factor(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    ValidJson(payload): ValidJson<TwoFactorCodePayload>,
+) -> Result<(StatusCode, Json<Response<RecoveryCodesResponse>>)> {
+    let mut user = ctx.user(&state.db).await?;
+
+    if user.is_totp_enabled() {
+        return Err(Error::AuthError(AuthError::TwoFactorAlreadyEnabled));
+    }
+
+    let Some(secret) = user.totp_secret.clone() else {
+        return Err(Error::AuthError(AuthError::TwoFactorNotEnabled));
+    };
+
+    let Some(step) = totp::verify(&secret, &payload.code)? else {
+        return Err(Error::AuthError(AuthError::TwoFactorFailed));
+    };
+
+    let tx = state.db.begin().await?;
+    if !user.record_totp_step(&*tx, step).await? {
+        return Err(Error::AuthError(AuthError::TwoFactorFailed));
+    }
+
+    user.enable_totp(&*tx).await?;
+    let recovery_codes = RecoveryCode::create_batch(&*tx, user.id.clone()).await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(Response::success(RecoveryCodesResponse { recovery_codes })),
+    ))
+}
+
+async fn disable_two_factor(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    ValidJson(payload): ValidJson<DisableTwoFactorPayload>,
+) -> Result<Json<Value>> {
+    let mut user = ctx.user(&state.db).await?;
+
+    if !user.is_totp_enabled() {
+        return Err(Error::AuthError(AuthError::TwoFactorNotEnabled));
+    }
+
+    if !user.confirm_password(&payload.password)? {
+        return Err(Error::AuthError(AuthError::WrongPassword));
+    }
+
+    if !user.verify_second_factor(&state.db, &payload.code).await? {
+        return Err(Error::AuthError(AuthError::TwoFactorFailed));
+    }
+
+    let tx = state.db.begin().await?;
+    user.disable_totp(&*tx).await?;
+    RecoveryCode::delete_by_user_id(&*tx, user.id.clone()).await?;
+    tx.commit().await?;
+
+    Ok(Json(json!({ "status": "Success", "message": "two-factor authentication disabled" })))
 }
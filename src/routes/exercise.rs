@@ -5,14 +5,18 @@ use axum::{extract::State, http::StatusCode, middleware, routing::post, Json, Ro
 use crate::dtos::exercise::{
     ExerciseGroupHistoryPayload, ExerciseHistoryPayload, ExerciseResponse,
 };
-use crate::error::{AuthError, Error};
-use crate::middlewares::auth::require_auth;
+use crate::dtos::pagination::ListQuery;
+use crate::error::{AuthError, Error, Resource};
+use crate::middlewares::auth::require_verified;
+use crate::middlewares::validation::{ValidJson, ValidQuery};
 use crate::models::exercise_target::ExerciseTarget;
 use crate::models::exercise_workout::ExerciseWorkout;
 use crate::models::set::Set;
 use crate::models::target::Target;
-use crate::models::workout::Workout;
-use crate::response::Response;
+use crate::models::exercise::ExerciseSort;
+use crate::models::workout::{Workout, WorkoutSort};
+use crate::db::Database;
+use crate::response::{FieldErrors, Response};
 use crate::{
     ctx::Ctx, dtos::exercise::CreateExercisePayload, error::Result, models::exercise::Exercise,
     ApiState,
@@ -25,33 +29,30 @@ pub fn router(state: ApiState) -> Router {
         .route("/api/exercises/:id", put(update_exercise))
         .route("/api/exercises/:id/history", get(get_exercise_history))
         .route("/api/exercises/:id", delete(delete_exercise))
-        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_verified))
         .with_state(state)
 }
 
 async fn create_exercise(
     State(state): State<ApiState>,
     ctx: Ctx,
-    Json(payload): Json<CreateExercisePayload>,
+    ValidJson(payload): ValidJson<CreateExercisePayload>,
 ) -> Result<(StatusCode, Json<Response<ExerciseResponse>>)> {
-    let user = ctx.user().clone();
-
-    let exercise =
-        Exercise::create(&state.db, user.id, payload.name, payload.exercise_type).await?;
-
-    let mut targets = vec![];
-
-    for target_id in payload.targets {
-        // TODO: Use transactions
-        let exercise_target =
-            ExerciseTarget::create(&state.db, exercise.id.clone(), target_id).await?;
-
-        let target = Target::find_by_id(&state.db, exercise_target.target_id)
-            .await?
-            .ok_or(Error::WTF("Previous insert should've failed".to_string()))?;
-
-        targets.push(target);
+    let targets = find_targets(&state.db, &payload.targets).await?;
+
+    let tx = state.db.begin().await?;
+    let exercise = Exercise::create(
+        &*tx,
+        ctx.user_id().to_string(),
+        payload.name,
+        payload.exercise_type,
+    )
+    .await?;
+
+    for target in &targets {
+        ExerciseTarget::create(&*tx, exercise.id.clone(), target.id.clone()).await?;
     }
+    tx.commit().await?;
 
     Ok((
         StatusCode::CREATED,
@@ -61,13 +62,37 @@ async fn create_exercise(
     ))
 }
 
+/// The targets of a payload. Unknown ids are reported as a validation error
+/// instead of failing a foreign key halfway through the inserts.
+async fn find_targets(db: &dyn Database, ids: &[String]) -> Result<Vec<Target>> {
+    let mut targets = vec![];
+    let mut unknown = vec![];
+
+    for id in ids {
+        match Target::find_by_id(db, id.clone()).await? {
+            Some(target) => targets.push(target),
+            None => unknown.push(format!("unknown target {id}")),
+        }
+    }
+
+    if !unknown.is_empty() {
+        return Err(Error::Validation(FieldErrors::from([("targets".to_string(), unknown)])));
+    }
+
+    Ok(targets)
+}
+
 async fn get_exercises(
     State(state): State<ApiState>,
     ctx: Ctx,
+    ValidQuery(query): ValidQuery<ListQuery<ExerciseSort>>,
 ) -> Result<(StatusCode, Json<Response<Vec<ExerciseResponse>>>)> {
+    let params = query.into_params()?;
+    let page = Exercise::list_by_user_id(&state.db, ctx.user_id().to_string(), &params).await?;
+    let meta = page.meta();
     let mut exercises = vec![];
 
-    for exercise in ctx.user().exercises(&state.db).await? {
+    for exercise in page.items {
         let targets = Target::all_by_exercise_id(&state.db, exercise.id.clone()).await?;
 
         exercises.push(ExerciseResponse::from_exercise_and_targets(
@@ -75,37 +100,40 @@ async fn get_exercises(
         ));
     }
 
-    Ok((StatusCode::CREATED, Json(Response::success(exercises))))
+    Ok((StatusCode::CREATED, Json(Response::success(exercises).with_meta(meta))))
 }
 
 async fn update_exercise(
     State(state): State<ApiState>,
     ctx: Ctx,
     Path((id,)): Path<(String,)>,
-    Json(payload): Json<CreateExercisePayload>,
+    ValidJson(payload): ValidJson<CreateExercisePayload>,
 ) -> Result<(StatusCode, Json<Response<ExerciseResponse>>)> {
-    let user = ctx.user();
+    let user_id = ctx.user_id();
     let exercise = Exercise::find_by_id(&state.db, id.clone()).await?;
 
     let Some(mut exercise) = exercise else {
-        return Err(Error::NotFound(format!("Exercise with id {}", id)));
+        return Err(Error::NotFound(Resource::Exercise, format!("Exercise with id {}", id)));
     };
 
-    if exercise.user_id != user.id {
-        return Err(Error::AuthError(AuthError::NotYourItem));
+    if exercise.user_id != user_id {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::Exercise)));
     }
 
+    let targets = find_targets(&state.db, &payload.targets).await?;
+
     exercise.name = payload.name;
     exercise.exercise_type = payload.exercise_type;
 
-    exercise.save(&state.db).await?;
+    let tx = state.db.begin().await?;
+    exercise.save(&*tx).await?;
 
-    // TODO: Use transactions
-    ExerciseTarget::delete_by_exercise_id(&state.db, exercise.id.clone()).await?;
+    ExerciseTarget::delete_by_exercise_id(&*tx, exercise.id.clone()).await?;
 
-    for target_id in payload.targets {
-        ExerciseTarget::create(&state.db, exercise.id.clone(), target_id).await?;
+    for target in &targets {
+        ExerciseTarget::create(&*tx, exercise.id.clone(), target.id.clone()).await?;
     }
+    tx.commit().await?;
 
     let targets = Target::all_by_exercise_id(&state.db, exercise.id.clone()).await?;
 
@@ -121,8 +149,10 @@ async fn get_exercise_history(
     State(state): State<ApiState>,
     ctx: Ctx,
     Path((id,)): Path<(String,)>,
+    ValidQuery(query): ValidQuery<ListQuery<WorkoutSort>>,
 ) -> Result<(StatusCode, Json<Response<Vec<ExerciseHistoryPayload>>>)> {
-    let user = ctx.user();
+    let params = query.into_params()?;
+    let user_id = ctx.user_id();
     // NOTE: This is pretty pointless but i like verifying the user before
     //       fetching all exercise_workouts because if the the list
     //       is empty, it would give back an empty list instead of
@@ -130,19 +160,20 @@ async fn get_exercise_history(
     let exercise = Exercise::find_by_id(&state.db, id.clone()).await?;
 
     let Some(exercise) = exercise else {
-        return Err(Error::NotFound(format!("Exercise with id {}", id)));
+        return Err(Error::NotFound(Resource::Exercise, format!("Exercise with id {}", id)));
     };
 
-    if exercise.user_id != user.id {
-        return Err(Error::AuthError(AuthError::NotYourItem));
+    if exercise.user_id != user_id {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::Exercise)));
     }
 
-    let workouts =
-        Workout::find_all_where_exercised_is_used(&state.db, exercise.id.clone()).await?;
+    let page =
+        Workout::list_where_exercise_is_used(&state.db, exercise.id.clone(), &params).await?;
+    let meta = page.meta();
 
     let mut all = vec![];
 
-    for w in workouts {
+    for w in page.items {
         let exercise_workouts = ExerciseWorkout::find_all_by_exercise_and_workout_id(
             &state.db,
             exercise.id.clone(),
@@ -167,7 +198,7 @@ async fn get_exercise_history(
         })
     }
 
-    Ok((StatusCode::OK, Json(Response::success(all))))
+    Ok((StatusCode::OK, Json(Response::success(all).with_meta(meta))))
 }
 
 async fn delete_exercise(
@@ -175,15 +206,15 @@ async fn delete_exercise(
     ctx: Ctx,
     Path((id,)): Path<(String,)>,
 ) -> Result<(StatusCode, Json<Response<Exercise>>)> {
-    let user = ctx.user();
+    let user_id = ctx.user_id();
     let exercise = Exercise::find_by_id(&state.db, id.clone()).await?;
 
     let Some(mut exercise) = exercise else {
-        return Err(Error::NotFound(format!("Exercise with id {}", id)));
+        return Err(Error::NotFound(Resource::Exercise, format!("Exercise with id {}", id)));
     };
 
-    if exercise.user_id != user.id {
-        return Err(Error::AuthError(AuthError::NotYourItem));
+    if exercise.user_id != user_id {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::Exercise)));
     }
 
     exercise.delete(&state.db).await?;
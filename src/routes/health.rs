@@ -0,0 +1,83 @@
+use axum::{
+    extract::State,
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+use crate::ApiState;
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(render_metrics))
+        .with_state(state)
+}
+
+/// The process is up, says nothing about its dependencies
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Ready to take traffic: the database answers and every migration this
+/// build knows about has been applied.
+async fn readyz(State(state): State<ApiState>) -> (StatusCode, Json<Value>) {
+    match check_database(&state).await {
+        Ok(()) => (StatusCode::OK, Json(json!({ "status": "ok" }))),
+        Err(reason) => {
+            tracing::warn!(reason, "Readiness check failed");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "status": "unavailable", "reason": reason })),
+            )
+        }
+    }
+}
+
+async fn check_database(state: &ApiState) -> std::result::Result<(), String> {
+    state
+        .db
+        .ping()
+        .await
+        .map_err(|err| format!("Database ping failed: {err}"))?;
+
+    let pending = state
+        .db
+        .pending_migrations()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if pending > 0 {
+        return Err(format!("{pending} migrations not applied"));
+    }
+
+    Ok(())
+}
+
+/// Only for scrapers holding `server.metrics_token`, it's never public
+async fn render_metrics(State(state): State<ApiState>, headers: HeaderMap) -> Response {
+    let Some(expected) = &state.config.server.metrics_token else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !token.is_some_and(|token| bool::from(token.as_bytes().ct_eq(expected.as_bytes()))) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    // Sampled on scrape, the pool has no hook for changes
+    let pool = state.db.pool_stats();
+    metrics::gauge!("db_pool_connections").set(pool.size as f64);
+    metrics::gauge!("db_pool_idle_connections").set(pool.idle as f64);
+    metrics::gauge!("db_pool_max_connections").set(pool.max as f64);
+
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], state.metrics.render()).into_response()
+}
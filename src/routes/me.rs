@@ -0,0 +1,66 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get},
+    Json, Router,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    ctx::Ctx,
+    dtos::me::DeleteAccountPayload,
+    error::{AuthError, Error, Result},
+    export,
+    middlewares::{auth::require_auth, validation::ValidJson},
+    ApiState,
+};
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/api/me", delete(delete_account))
+        .route("/api/me/export", get(export))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .with_state(state)
+}
+
+/// Everything stored about the user as one JSON document
+async fn export(State(state): State<ApiState>, ctx: Ctx) -> Result<impl IntoResponse> {
+    let user = ctx.user(&state.db).await?;
+
+    Ok((
+        [
+            (CONTENT_TYPE, "application/json"),
+            (CONTENT_DISPOSITION, "attachment; filename=\"workout-export.json\""),
+        ],
+        Body::from_stream(export::stream(state.db.clone(), user)),
+    ))
+}
+
+/// Deletes the account and everything in it. Needs the password, and a 2FA
+/// code when that is enabled, so a stolen access token isn't enough.
+/// Accounts that only sign in through OIDC have no password to give.
+async fn delete_account(
+    State(state): State<ApiState>,
+    ctx: Ctx,
+    ValidJson(payload): ValidJson<DeleteAccountPayload>,
+) -> Result<Json<Value>> {
+    let mut user = ctx.user(&state.db).await?;
+
+    if !user.confirm_password(&payload.password)? {
+        return Err(Error::AuthError(AuthError::WrongPassword));
+    }
+
+    if user.is_totp_enabled() {
+        let code = payload.code.unwrap_or_default();
+        if !user.verify_second_factor(&state.db, &code).await? {
+            return Err(Error::AuthError(AuthError::TwoFactorFailed));
+        }
+    }
+
+    user.delete(&state.db).await?;
+
+    Ok(Json(json!({ "status": "Success", "message": "account deleted" })))
+}
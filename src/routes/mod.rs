@@ -1,5 +1,10 @@
+pub mod admin;
+pub mod api_key;
 pub mod auth;
 pub mod exercise;
+pub mod health;
+pub mod me;
+pub mod oidc;
 pub mod set;
 pub mod target;
 pub mod workout;
@@ -0,0 +1,163 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Redirect,
+    routing::get,
+    Json, Router,
+};
+
+use crate::{
+    ctx::ClientInfo,
+    dtos::auth::{LoginChallengeResponse, LoginResult, OidcAuthorizeQuery, OidcCallbackQuery},
+    error::{AuthError, Error, Resource, Result},
+    helpers::oidc::{IdTokenClaims, OidcClient},
+    middlewares::{auth::UnverifiedUsers, validation::ValidQuery},
+    models::{oidc_login::OidcLogin, token::Token, user::User, user_identity::UserIdentity},
+    response::Response,
+    routes::auth::login_response,
+    ApiState,
+};
+
+pub fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/api/auth/oidc/authorize", get(authorize))
+        .route("/api/auth/oidc/callback", get(callback))
+        .with_state(state)
+}
+
+fn oidc_client(state: &ApiState) -> Result<&OidcClient> {
+    state
+        .oidc
+        .as_deref()
+        .ok_or(Error::NotFound(Resource::OidcProvider, "OIDC login".to_string()))
+}
+
+/// Sends the user to the identity provider
+async fn authorize(
+    State(state): State<ApiState>,
+    ValidQuery(query): ValidQuery<OidcAuthorizeQuery>,
+) -> Result<Redirect> {
+    let oidc = oidc_client(&state)?;
+
+    let (login, login_state) = OidcLogin::create(&state.db, query.device_name).await?;
+
+    let url = oidc
+        .authorization_url(&login_state, &login.nonce, &login.code_verifier)
+        .await?;
+
+    Ok(Redirect::to(&url))
+}
+
+/// Where the provider sends the user back. Ends like a password login,
+/// including the 2FA challenge for users that have it enabled.
+async fn callback(
+    State(state): State<ApiState>,
+    client: ClientInfo,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<(StatusCode, Json<Response<LoginResult>>)> {
+    let oidc = oidc_client(&state)?;
+
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        // The provider sends `error` instead of a code when the user cancels
+        tracing::info!(error = ?query.error, "OIDC login failed");
+        return Err(Error::AuthError(AuthError::OidcFailed));
+    };
+
+    let login = OidcLogin::find_valid_by_state(&state.db, &login_state)
+        .await?
+        .ok_or(Error::AuthError(AuthError::OidcFailed))?;
+
+    if !login.consume(&state.db).await? {
+        return Err(Error::AuthError(AuthError::OidcFailed));
+    }
+
+    let claims = oidc
+        .exchange_code(&code, &login.code_verifier, &login.nonce)
+        .await?;
+
+    let user = match UserIdentity::find_by_subject(&state.db, &claims.iss, &claims.sub).await? {
+        Some(mut identity) => {
+            identity.touch(&state.db).await?;
+            identity
+                .user(&state.db)
+                .await?
+                .ok_or(Error::WTF("Identity exists but user doesn't".to_string()))?
+        }
+        None => link_identity(&state, oidc, &claims).await?,
+    };
+
+    if user.is_disabled() {
+        return Err(Error::AuthError(AuthError::AccountDisabled));
+    }
+
+    if !user.is_email_verified() && state.config.auth.unverified_users == UnverifiedUsers::Deny {
+        return Err(Error::AuthError(AuthError::EmailNotVerified));
+    }
+
+    if user.is_totp_enabled() {
+        let (challenge, challenge_expires_at) =
+            state.access_tokens.issue_challenge(user.id.clone(), login.device_name)?;
+
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(Response::success(LoginResult::Challenge(LoginChallengeResponse {
+                challenge,
+                challenge_expires_at,
+                two_factor_required: true,
+            }))),
+        ));
+    }
+
+    let (refresh_token, refresh_value) = Token::create(
+        &state.db,
+        user.id.clone(),
+        ClientInfo {
+            device_name: login.device_name,
+            ..client
+        },
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Response::success(LoginResult::Tokens(login_response(
+            &state,
+            user,
+            refresh_token,
+            refresh_value,
+        )?))),
+    ))
+}
+
+/// First login of an identity. Links it to the account with the same
+/// email, or creates one if that's allowed. Only done for emails the
+/// provider says it has verified, anything else could be used to take over
+/// an account.
+async fn link_identity(state: &ApiState, oidc: &OidcClient, claims: &IdTokenClaims) -> Result<User> {
+    let Some(email) = claims.email.clone().filter(|_| claims.email_verified) else {
+        return Err(Error::AuthError(AuthError::OidcFailed));
+    };
+
+    let tx = state.db.begin().await?;
+    let mut user = match User::find_by_email(&*tx, &email).await? {
+        Some(user) => user,
+        None if oidc.auto_register() => User::create_without_password(&*tx, email.clone()).await?,
+        None => return Err(Error::AuthError(AuthError::RegistrationClosed)),
+    };
+
+    if !user.is_email_verified() {
+        user.mark_email_verified(&*tx).await?;
+    }
+
+    UserIdentity::create(
+        &*tx,
+        user.id.clone(),
+        claims.iss.clone(),
+        claims.sub.clone(),
+        Some(email),
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(user)
+}
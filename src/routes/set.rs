@@ -3,8 +3,9 @@ use axum::routing::{delete, put};
 use axum::{extract::State, http::StatusCode, middleware, routing::post, Json, Router};
 
 use crate::dtos::set::{CreateSetPayload, UpdateSetPayload};
-use crate::error::{AuthError, Error};
-use crate::middlewares::auth::require_auth;
+use crate::error::{AuthError, Error, Resource};
+use crate::middlewares::auth::require_verified;
+use crate::middlewares::validation::ValidJson;
 use crate::models::set::Set;
 use crate::response::Response;
 use crate::{ctx::Ctx, error::Result, ApiState};
@@ -14,18 +15,19 @@ pub fn router(state: ApiState) -> Router {
         .route("/api/sets", post(create_set))
         .route("/api/sets/:id", put(update_set))
         .route("/api/sets/:id", delete(delete_set))
-        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_verified))
         .with_state(state)
 }
 
 async fn create_set(
     State(state): State<ApiState>,
     ctx: Ctx,
-    Json(payload): Json<CreateSetPayload>,
+    ValidJson(payload): ValidJson<CreateSetPayload>,
 ) -> Result<(StatusCode, Json<Response<Set>>)> {
-    let user = ctx.user();
+    let user_id = ctx.user_id();
 
-    let set = Set::create(&state.db, user.id.clone(), payload.exercise_workout_id, payload.quality, payload.quantity, payload.set_type).await?;
+    let set = Set::create(&state.db, user_id.to_string(), payload.exercise_workout_id, payload.quality, payload.quantity, payload.set_type).await?;
+    metrics::counter!("sets_logged_total").increment(1);
 
     Ok((
         StatusCode::CREATED,
@@ -37,21 +39,21 @@ async fn update_set(
     State(state): State<ApiState>,
     ctx: Ctx,
     Path((id,)): Path<(String,)>,
-    Json(payload): Json<UpdateSetPayload>,
+    ValidJson(payload): ValidJson<UpdateSetPayload>,
 ) -> Result<(StatusCode, Json<Response<Set>>)> {
-    let user = ctx.user();
+    let user_id = ctx.user_id();
 
     let set = Set::find_by_id(&state.db, id.clone()).await?;
 
     let Some(mut set) = set else {
-        return Err(Error::NotFound(format!(
+        return Err(Error::NotFound(Resource::Set, format!(
             "Set with id {}",
             id
         )));
     };
 
-    if set.user_id != user.id {
-        return Err(Error::AuthError(AuthError::NotYourItem));
+    if set.user_id != user_id {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::Set)));
     }
 
     set.quality = payload.quality;
@@ -71,19 +73,19 @@ async fn delete_set(
     ctx: Ctx,
     Path((id,)): Path<(String,)>,
 ) -> Result<(StatusCode, Json<Response<Set>>)> {
-    let user = ctx.user();
+    let user_id = ctx.user_id();
 
     let set = Set::find_by_id(&state.db, id.clone()).await?;
 
     let Some(mut set) = set else {
-        return Err(Error::NotFound(format!(
+        return Err(Error::NotFound(Resource::Set, format!(
             "Set with id {}",
             id
         )));
     };
 
-    if set.user_id != user.id {
-        return Err(Error::AuthError(AuthError::NotYourItem));
+    if set.user_id != user_id {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::Set)));
     }
 
     set.delete(&state.db).await?;
@@ -3,10 +3,13 @@ use axum::routing::{delete, get, put};
 use axum::{extract::State, http::StatusCode, middleware, routing::post, Json, Router};
 
 use crate::dtos::exercise_workout::CreateExerciseWorkoutPayload;
+use crate::dtos::pagination::ListQuery;
 use crate::dtos::workout::{DetailedExercise, DetailedWorkout};
-use crate::error::{AuthError, Error};
-use crate::middlewares::auth::require_auth;
+use crate::error::{AuthError, Error, Resource};
+use crate::middlewares::auth::require_verified;
+use crate::middlewares::validation::{ValidJson, ValidQuery};
 use crate::models::exercise_workout::ExerciseWorkout;
+use crate::models::workout::WorkoutSort;
 use crate::response::Response;
 use crate::{ctx::Ctx, error::Result, models::workout::Workout, ApiState};
 
@@ -26,7 +29,7 @@ pub fn router(state: ApiState) -> Router {
             delete(delete_exercise_to_current_workout),
         )
         .route("/api/workouts/:id", put(delete_workout))
-        .route_layer(middleware::from_fn_with_state(state.clone(), require_auth))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_verified))
         .with_state(state)
 }
 
@@ -34,9 +37,8 @@ async fn create_workout(
     State(state): State<ApiState>,
     ctx: Ctx,
 ) -> Result<(StatusCode, Json<Response<Workout>>)> {
-    let user = ctx.user().clone();
-
-    let workout = Workout::create(&state.db, user.id).await?;
+    let workout = Workout::create(&state.db, ctx.user_id().to_string()).await?;
+    metrics::counter!("workouts_started_total").increment(1);
 
     Ok((StatusCode::CREATED, Json(Response::success(workout))))
 }
@@ -44,22 +46,25 @@ async fn create_workout(
 async fn get_done_workouts(
     State(state): State<ApiState>,
     ctx: Ctx,
+    ValidQuery(query): ValidQuery<ListQuery<WorkoutSort>>,
 ) -> Result<(StatusCode, Json<Response<Vec<Workout>>>)> {
-    let workouts = ctx.user().workouts(&state.db).await?;
+    let params = query.into_params()?;
+    let page = Workout::list_done_by_user_id(&state.db, ctx.user_id().to_string(), &params).await?;
+    let meta = page.meta();
 
-    Ok((StatusCode::OK, Json(Response::success(workouts))))
+    Ok((StatusCode::OK, Json(Response::success(page.items).with_meta(meta))))
 }
 
 async fn get_current_workout(
     State(state): State<ApiState>,
     ctx: Ctx,
 ) -> Result<(StatusCode, Json<Response<DetailedWorkout>>)> {
-    let workout = ctx.user().current_workout(&state.db).await?;
+    let workout = Workout::find_current_by_user_id(&state.db, ctx.user_id().to_string()).await?;
 
     let Some(workout) = workout else {
-        return Err(Error::NotFound(format!(
+        return Err(Error::NotFound(Resource::Workout, format!(
             "Current workout for user {}",
-            ctx.user().id
+            ctx.user_id()
         )))
     };
 
@@ -98,15 +103,16 @@ async fn finish_current_workout(
     State(state): State<ApiState>,
     ctx: Ctx,
 ) -> Result<(StatusCode, Json<Response<Workout>>)> {
-    let workout = ctx.user().current_workout(&state.db).await?;
+    let workout = Workout::find_current_by_user_id(&state.db, ctx.user_id().to_string()).await?;
 
     if let Some(mut workout) = workout {
         workout.finish(&state.db).await?;
+        metrics::counter!("workouts_finished_total").increment(1);
         Ok((StatusCode::OK, Json(Response::success(workout))))
     } else {
-        Err(Error::NotFound(format!(
+        Err(Error::NotFound(Resource::Workout, format!(
             "Current workout for user {}",
-            ctx.user().id
+            ctx.user_id()
         )))
     }
 }
@@ -114,20 +120,20 @@ async fn finish_current_workout(
 async fn add_exercise_to_current_workout(
     State(state): State<ApiState>,
     ctx: Ctx,
-    Json(payload): Json<CreateExerciseWorkoutPayload>,
+    ValidJson(payload): ValidJson<CreateExerciseWorkoutPayload>,
 ) -> Result<(StatusCode, Json<Response<ExerciseWorkout>>)> {
-    let user = ctx.user();
-    let workout = user.current_workout(&state.db).await?;
+    let user_id = ctx.user_id();
+    let workout = Workout::find_current_by_user_id(&state.db, user_id.to_string()).await?;
 
     if let Some(workout) = workout {
         let exercise_workout =
-            ExerciseWorkout::create(&state.db, user.id.clone(), payload.exercise_id, workout.id)
+            ExerciseWorkout::create(&state.db, user_id.to_string(), payload.exercise_id, workout.id)
                 .await?;
         Ok((StatusCode::CREATED, Json(Response::success(exercise_workout))))
     } else {
-        Err(Error::NotFound(format!(
+        Err(Error::NotFound(Resource::Workout, format!(
             "Current workout for user {}",
-            ctx.user().id
+            ctx.user_id()
         )))
     }
 }
@@ -137,18 +143,18 @@ async fn delete_workout(
     ctx: Ctx,
     Path((id,)): Path<(String,)>,
 ) -> Result<(StatusCode, Json<Response<Workout>>)> {
-    let user = ctx.user();
+    let user_id = ctx.user_id();
     let workout = Workout::find_by_id(&state.db, id.clone()).await?;
 
     let Some(mut workout) = workout else {
-        return Err(Error::NotFound(format!(
+        return Err(Error::NotFound(Resource::Workout, format!(
             "Workout with id {}",
             id
         )));
     };
 
-    if workout.user_id != user.id {
-        return Err(Error::AuthError(AuthError::NotYourItem));
+    if workout.user_id != user_id {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::Workout)));
     }
 
     workout.delete(&state.db).await?;
@@ -164,18 +170,18 @@ async fn delete_exercise_to_current_workout(
     ctx: Ctx,
     Path((exercise_workout_id,)): Path<(String,)>,
 ) -> Result<(StatusCode, Json<Response<ExerciseWorkout>>)> {
-    let user = ctx.user();
+    let user_id = ctx.user_id();
     let exercise_workout = ExerciseWorkout::find_by_id(&state.db, exercise_workout_id.clone()).await?;
 
     let Some(mut exercise_workout) = exercise_workout else {
-        return Err(Error::NotFound(format!(
+        return Err(Error::NotFound(Resource::ExerciseWorkout, format!(
             "ExerciseWorkout with id {}",
             exercise_workout_id.clone()
         )));
     };
 
-    if exercise_workout.user_id != user.id {
-        return Err(Error::AuthError(AuthError::NotYourItem));
+    if exercise_workout.user_id != user_id {
+        return Err(Error::AuthError(AuthError::NotYourItem(Resource::ExerciseWorkout)));
     }
 
     exercise_workout.delete(&state.db).await?;
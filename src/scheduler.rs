@@ -0,0 +1,94 @@
+use std::{future::Future, panic::AssertUnwindSafe, time::Duration};
+
+use futures::FutureExt;
+use tokio::{sync::watch, task::JoinSet, time::MissedTickBehavior};
+use tracing::Instrument;
+
+use crate::error::Result;
+
+/// Runs periodic background jobs. Each job gets its own task and span, a
+/// failing, panicking or hanging run is logged and the job carries on with
+/// its next tick.
+pub struct Scheduler {
+    shutdown: watch::Sender<bool>,
+    tasks: JoinSet<()>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            shutdown: watch::channel(false).0,
+            tasks: JoinSet::new(),
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `job` right away and then every `interval`. A run taking longer
+    /// than `timeout` is cancelled.
+    pub fn every<F, Fut>(&mut self, name: &'static str, interval: Duration, timeout: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut shutdown = self.shutdown.subscribe();
+
+        self.tasks.spawn(async move {
+            tracing::info!(job = name, interval_secs = interval.as_secs(), "Scheduled job");
+
+            let mut ticker = tokio::time::interval(interval);
+            // A slow run pushes the next one back instead of causing a burst
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => (),
+                    _ = shutdown.changed() => break,
+                }
+
+                run_once(name, timeout, job())
+                    .instrument(tracing::info_span!("job", name))
+                    .await;
+            }
+        });
+    }
+
+    /// Stops scheduling new runs and waits for the ones in progress, which
+    /// are bounded by their timeout.
+    pub async fn shutdown(mut self) {
+        let _ = self.shutdown.send(true);
+
+        while self.tasks.join_next().await.is_some() {}
+
+        tracing::info!("Scheduler stopped");
+    }
+}
+
+async fn run_once(name: &'static str, timeout: Duration, run: impl Future<Output = Result<()>>) {
+    let started_at = tokio::time::Instant::now();
+
+    let outcome = match tokio::time::timeout(timeout, AssertUnwindSafe(run).catch_unwind()).await {
+        Ok(Ok(Ok(()))) => {
+            tracing::debug!(elapsed_ms = started_at.elapsed().as_millis() as u64, "Job finished");
+            "ok"
+        }
+        Ok(Ok(Err(err))) => {
+            tracing::error!(error = %err, "Job failed");
+            "failed"
+        }
+        Ok(Err(_)) => {
+            tracing::error!("Job panicked");
+            "panicked"
+        }
+        Err(_) => {
+            tracing::error!(timeout_secs = timeout.as_secs(), "Job timed out");
+            "timed_out"
+        }
+    };
+
+    metrics::counter!("jobs_run_total", "job" => name, "outcome" => outcome).increment(1);
+}
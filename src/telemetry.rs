@@ -0,0 +1,93 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderName,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tracing::Span;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::config::{LogConfig, LogFormat};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Sets up the global subscriber. The JSON format writes one object per
+/// line for log collectors.
+pub fn init(config: &LogConfig) {
+    let registry = tracing_subscriber::registry().with(EnvFilter::new(&config.filter));
+
+    match config.format {
+        LogFormat::Json => registry
+            .with(tracing_subscriber::fmt::layer().json().flatten_event(true))
+            .init(),
+        LogFormat::Text => registry.with(tracing_subscriber::fmt::layer()).init(),
+    }
+}
+
+/// Span every request runs in. Uses the route pattern rather than the
+/// actual path so tokens in URLs don't end up in the logs, `user_id` is
+/// filled in once the request is authenticated.
+pub fn request_span(req: &Request) -> Span {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or("unmatched");
+
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "request",
+        method = %req.method(),
+        route,
+        request_id,
+        user_id = tracing::field::Empty,
+    )
+}
+
+/// The URL with its password replaced, safe to log
+pub fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut url) => {
+            if url.password().is_some() {
+                let _ = url.set_password(Some("redacted"));
+            }
+            url.to_string()
+        }
+        Err(_) => "<unparseable url>".to_string(),
+    }
+}
+
+/// Latency buckets in seconds, from a cached lookup to a slow export
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Installs the global metrics recorder. The handle renders everything
+/// recorded so far in the Prometheus text format.
+pub fn init_metrics() -> PrometheusHandle {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full("http_request_duration_seconds".to_string()), LATENCY_BUCKETS)
+        .expect("Valid latency buckets")
+        .install_recorder()
+        .expect("Metrics recorder not installed yet");
+
+    metrics::describe_counter!("http_requests_total", "HTTP requests by route and status");
+    metrics::describe_histogram!(
+        "http_request_duration_seconds",
+        metrics::Unit::Seconds,
+        "HTTP request latency by route and status"
+    );
+    metrics::describe_gauge!("db_pool_connections", "Open database connections");
+    metrics::describe_gauge!("db_pool_idle_connections", "Open database connections not in use");
+    metrics::describe_gauge!("db_pool_max_connections", "Configured database connection limit");
+    metrics::describe_counter!("jobs_run_total", "Background job runs by outcome");
+    metrics::describe_counter!("jobs_processed_total", "Queued jobs processed by kind and outcome");
+    metrics::describe_counter!("users_registered_total", "Accounts created with a password");
+    metrics::describe_counter!("workouts_started_total", "Workouts started");
+    metrics::describe_counter!("workouts_finished_total", "Workouts finished");
+    metrics::describe_counter!("sets_logged_total", "Sets logged");
+
+    handle
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{TestApp, TestUser, PASSWORD};
+use workout_backend::models::user::{Role, User};
+
+/// Registers a user and promotes them. Their access token stays valid, the
+/// role isn't part of it.
+async fn admin(app: &TestApp, email: &str) -> TestUser {
+    let admin = app.user(email).await;
+
+    let mut user = User::find_by_id(&app.state.db, admin.id.clone()).await.unwrap().unwrap();
+    user.set_role(&app.state.db, Role::Admin).await.unwrap();
+
+    admin
+}
+
+#[tokio::test]
+async fn only_admin_sessions_get_in() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let denied = app.get("/api/admin/users", &user.token).await;
+    assert_eq!(denied.status, StatusCode::FORBIDDEN);
+    assert_eq!(denied.body["code"], "auth.admin_only");
+
+    let grace = admin(&app, "grace@example.com").await;
+    assert_eq!(app.get("/api/admin/users", &grace.token).await.status, StatusCode::OK);
+
+    // API keys never get admin rights, whoever they belong to
+    let key = app
+        .post("/api/auth/api-keys", &grace.token, json!({ "name": "Sync", "scopes": ["workouts:read"] }))
+        .await;
+    assert_eq!(key.status, StatusCode::CREATED, "{}", key.body);
+    let key = key.data()["key"].as_str().unwrap();
+    assert_eq!(app.get("/api/admin/users", key).await.status, StatusCode::FORBIDDEN);
+
+    // Admins can't demote themselves, but others can, and that takes effect
+    // before the access token expires
+    let own = app
+        .put(&format!("/api/admin/users/{}/role", grace.id), &grace.token, Some(json!({ "role": "User" })))
+        .await;
+    assert_eq!(own.status, StatusCode::CONFLICT);
+
+    let linus = admin(&app, "linus@example.com").await;
+    let demoted = app
+        .put(&format!("/api/admin/users/{}/role", grace.id), &linus.token, Some(json!({ "role": "User" })))
+        .await;
+    assert_eq!(demoted.status, StatusCode::OK, "{}", demoted.body);
+    assert_eq!(demoted.data()["role"], "User");
+    assert_eq!(app.get("/api/admin/users", &grace.token).await.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn users_are_listed_with_their_usage() {
+    let app = TestApp::spawn().await;
+    let grace = admin(&app, "grace@example.com").await;
+    let ada = app.user("ada@example.com").await;
+
+    let exercise_workout_id = app.exercise_in_current_workout(&ada, "Bench press").await;
+    for quantity in [8, 6] {
+        let set = app
+            .post(
+                "/api/sets",
+                &ada.token,
+                json!({ "exercise_workout_id": exercise_workout_id, "quality": 40.0, "quantity": quantity, "set_type": "Normal" }),
+            )
+            .await;
+        assert_eq!(set.status, StatusCode::CREATED, "{}", set.body);
+    }
+
+    let users = app.get("/api/admin/users", &grace.token).await;
+    assert_eq!(users.status, StatusCode::OK);
+    let users = users.data().as_array().unwrap();
+    assert_eq!(users.len(), 2);
+
+    let usage = |email: &str| users.iter().find(|user| user["email"] == email).unwrap()["usage"].clone();
+    assert_eq!(usage("ada@example.com"), json!({ "workouts": 1, "sets": 2 }));
+    assert_eq!(usage("grace@example.com"), json!({ "workouts": 0, "sets": 0 }));
+
+    for query in ["limit=lots", "limit=500", "offset=-1"] {
+        let invalid = app.get(&format!("/api/admin/users?{query}"), &grace.token).await;
+        assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY, "{query}");
+        assert_eq!(invalid.body["code"], "request.invalid", "{query}");
+    }
+
+    let searched = app.get("/api/admin/users?search=ada", &grace.token).await;
+    assert_eq!(searched.data().as_array().unwrap().len(), 1);
+
+    let one = app.get(&format!("/api/admin/users/{}", ada.id), &grace.token).await;
+    assert_eq!(one.status, StatusCode::OK);
+    assert_eq!(one.data()["usage"], json!({ "workouts": 1, "sets": 2 }));
+}
+
+#[tokio::test]
+async fn disabled_users_are_signed_out_until_enabled() {
+    let app = TestApp::spawn().await;
+    let grace = admin(&app, "grace@example.com").await;
+    let ada = app.user("ada@example.com").await;
+
+    let own = app.put(&format!("/api/admin/users/{}/disable", grace.id), &grace.token, None).await;
+    assert_eq!(own.status, StatusCode::CONFLICT);
+
+    let disabled = app.put(&format!("/api/admin/users/{}/disable", ada.id), &grace.token, None).await;
+    assert_eq!(disabled.status, StatusCode::OK, "{}", disabled.body);
+    assert!(disabled.data()["disabled_at"].is_string());
+
+    // The access token Ada still holds stops working right away
+    let workouts = app.get("/api/workouts", &ada.token).await;
+    assert_eq!(workouts.status, StatusCode::UNAUTHORIZED, "{}", workouts.body);
+
+    let refreshed = app
+        .request(
+            Method::POST,
+            "/api/auth/refresh",
+            None,
+            Some(json!({ "refresh_token": ada.refresh_token })),
+        )
+        .await;
+    assert!(refreshed.status.is_client_error(), "{}", refreshed.body);
+
+    let login = app.login("ada@example.com", PASSWORD).await;
+    assert_eq!(login.status, StatusCode::FORBIDDEN);
+    assert_eq!(login.body["code"], "auth.account_disabled");
+
+    let enabled = app.put(&format!("/api/admin/users/{}/enable", ada.id), &grace.token, None).await;
+    assert_eq!(enabled.status, StatusCode::OK, "{}", enabled.body);
+    assert_eq!(app.login("ada@example.com", PASSWORD).await.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn logging_users_out_ends_their_sessions() {
+    let app = TestApp::spawn().await;
+    let grace = admin(&app, "grace@example.com").await;
+    let ada = app.user("ada@example.com").await;
+    assert_eq!(app.get("/api/workouts", &ada.token).await.status, StatusCode::OK);
+
+    let logged_out = app
+        .request(Method::DELETE, &format!("/api/admin/users/{}/tokens", ada.id), Some(&grace.token), None)
+        .await;
+    assert_eq!(logged_out.status, StatusCode::OK, "{}", logged_out.body);
+
+    assert_eq!(app.get("/api/workouts", &ada.token).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/api/admin/users", &grace.token).await.status, StatusCode::OK);
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use axum::http::StatusCode;
+
+use common::{TestApp, PASSWORD};
+
+/// Runs the `workout_admin` binary against a SQLite file of its own, in an
+/// empty directory so no `.env` or `config.toml` gets picked up
+struct AdminCli {
+    dir: PathBuf,
+}
+
+/// What a run printed and whether it succeeded
+struct Output {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+impl AdminCli {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("workout_admin_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        Self { dir }
+    }
+
+    fn database_url(&self) -> String {
+        format!("sqlite://{}", self.dir.join("workout.db").display())
+    }
+
+    /// `stdin` stands in for the password prompt
+    fn run(&self, args: &[&str], stdin: &str) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_workout_admin"))
+            .args(args)
+            .current_dir(&self.dir)
+            .env("DATABASE_URL", self.database_url())
+            .env("ACCESS_TOKEN_SECRET", "test-secret")
+            .env("RUST_LOG", "warn")
+            .env_remove("CONFIG_FILE")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        Output {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+}
+
+impl Drop for AdminCli {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn migrate_status_lists_pending_and_applied_migrations() {
+    let cli = AdminCli::new();
+
+    let before = cli.run(&["migrate", "status"], "");
+    assert!(before.success, "{}", before.stderr);
+    assert!(!before.stdout.is_empty());
+    assert!(before.stdout.lines().all(|line| line.contains("pending")), "{}", before.stdout);
+
+    let applied = cli.run(&["migrate", "run"], "");
+    assert!(applied.success, "{}", applied.stderr);
+    let count = before.stdout.lines().count();
+    assert_eq!(applied.stdout.trim(), format!("Applied {count} migration(s)"));
+
+    let after = cli.run(&["migrate", "status"], "");
+    assert_eq!(after.stdout.lines().count(), count);
+    assert!(after.stdout.lines().all(|line| line.contains("applied")), "{}", after.stdout);
+}
+
+#[tokio::test]
+async fn created_users_can_log_in_until_disabled() {
+    let cli = AdminCli::new();
+    assert!(cli.run(&["migrate", "run"], "").success);
+
+    let short = cli.run(&["user", "create", "ada@example.com"], "short\n");
+    assert!(!short.success);
+    assert!(short.stderr.contains("password"), "{}", short.stderr);
+
+    let created = cli.run(&["user", "create", "ada@example.com", "--admin"], &format!("{PASSWORD}\n"));
+    assert!(created.success, "{}", created.stderr);
+    assert!(created.stdout.contains("as admin"), "{}", created.stdout);
+
+    // A bad payload is reported as such, even for a taken address
+    let again = cli.run(&["user", "create", "ada@example.com"], "short\n");
+    assert!(again.stderr.contains("password"), "{}", again.stderr);
+    let taken = cli.run(&["user", "create", "ada@example.com"], &format!("{PASSWORD}\n"));
+    assert!(taken.stderr.contains("already registered"), "{}", taken.stderr);
+
+    let url = cli.database_url();
+    let app = TestApp::spawn_with(|config| config.database.url = url).await;
+    let login = app.login("ada@example.com", PASSWORD).await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.body);
+    let token = login.data()["token"].as_str().unwrap().to_string();
+    assert_eq!(app.get("/api/admin/users", &token).await.status, StatusCode::OK);
+
+    let disabled = cli.run(&["user", "disable", "ada@example.com"], "");
+    assert!(disabled.success, "{}", disabled.stderr);
+
+    assert_eq!(app.get("/api/workouts", &token).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login("ada@example.com", PASSWORD).await.status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn keys_work_within_their_scopes_and_record_their_use() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let created = app
+        .post("/api/auth/api-keys", &user.token, json!({ "name": "Sync", "scopes": ["workouts:read"] }))
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    let key = created.data()["key"].as_str().unwrap();
+    assert!(created.data()["api_key"]["last_used_at"].is_null());
+
+    // Long names are refused rather than cut short
+    let long = app
+        .post("/api/auth/api-keys", &user.token, json!({ "name": "x".repeat(101), "scopes": ["workouts:read"] }))
+        .await;
+    assert_eq!(long.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", long.body);
+
+    assert_eq!(app.get("/api/workouts", key).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/exercises", key).await.status, StatusCode::FORBIDDEN);
+
+    let last_used_at = || async {
+        let keys = app.get("/api/auth/api-keys", &user.token).await;
+        keys.data()[0]["last_used_at"].as_str().unwrap().parse::<DateTime<Utc>>().unwrap()
+    };
+    let first_use = last_used_at().await;
+
+    // Written at most every few minutes, not on every request
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    app.get("/api/workouts", key).await;
+    assert_eq!(last_used_at().await, first_use);
+}
+
+#[tokio::test]
+async fn keys_are_validated_when_created() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let unknown = app
+        .post("/api/auth/api-keys", &user.token, json!({ "name": "Sync", "scopes": ["sets:delete"] }))
+        .await;
+    assert_eq!(unknown.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", unknown.body);
+    assert!(unknown.body["errors"]["body"][0].as_str().unwrap().contains("unknown scope sets:delete"));
+
+    let expired = app
+        .post(
+            "/api/auth/api-keys",
+            &user.token,
+            json!({ "name": "Sync", "scopes": ["sets:read"], "expires_at": Utc::now() - Duration::from_secs(60) }),
+        )
+        .await;
+    assert_eq!(expired.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", expired.body);
+    assert!(expired.body["errors"]["expires_at"].is_array(), "{}", expired.body);
+
+    let expiring = app
+        .post(
+            "/api/auth/api-keys",
+            &user.token,
+            json!({ "name": "Sync", "scopes": ["sets:read"], "expires_at": Utc::now() + Duration::from_secs(24 * 60 * 60) }),
+        )
+        .await;
+    assert_eq!(expiring.status, StatusCode::CREATED, "{}", expiring.body);
+}
@@ -0,0 +1,391 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{header::RETRY_AFTER, Method, StatusCode};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use common::{totp_code, TestApp, PASSWORD};
+use workout_backend::models::job::Job;
+
+#[tokio::test]
+async fn register_verify_and_login() {
+    let app = TestApp::spawn().await;
+
+    let user = app.user("ada@example.com").await;
+
+    let sessions = app.get("/api/auth/sessions", &user.token).await;
+    assert_eq!(sessions.status, StatusCode::OK);
+    assert_eq!(sessions.data().as_array().unwrap().len(), 1);
+    assert_eq!(sessions.data()[0]["current"], true);
+}
+
+#[tokio::test]
+async fn login_ignores_email_case() {
+    let app = TestApp::spawn().await;
+    app.user("grace@example.com").await;
+
+    let login = app.login("Grace@Example.com", PASSWORD).await;
+
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.body);
+}
+
+#[tokio::test]
+async fn register_rejects_taken_email() {
+    let app = TestApp::spawn().await;
+    app.user("ada@example.com").await;
+
+    let again = app
+        .request(
+            Method::POST,
+            "/api/auth/register",
+            None,
+            Some(json!({ "email": "ada@example.com", "password": PASSWORD })),
+        )
+        .await;
+
+    assert_eq!(again.status, StatusCode::CONFLICT);
+    assert_eq!(again.body["code"], "auth.email_already_in_use");
+}
+
+#[tokio::test]
+async fn register_validates_payload() {
+    let app = TestApp::spawn().await;
+
+    let invalid = app
+        .request(
+            Method::POST,
+            "/api/auth/register",
+            None,
+            Some(json!({ "email": "not an email", "password": "short" })),
+        )
+        .await;
+
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(invalid.body["code"], "request.invalid");
+    assert!(invalid.body["errors"]["email"].is_array(), "{}", invalid.body);
+    assert!(invalid.body["errors"]["password"].is_array(), "{}", invalid.body);
+}
+
+#[tokio::test]
+async fn login_validates_payload() {
+    let app = TestApp::spawn().await;
+    app.user("ada@example.com").await;
+
+    let invalid = app
+        .request(
+            Method::POST,
+            "/api/auth/login",
+            None,
+            Some(json!({ "email": "ada@example.com", "password": PASSWORD, "device_name": "x".repeat(101) })),
+        )
+        .await;
+
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(invalid.body["errors"]["device_name"].is_array(), "{}", invalid.body);
+}
+
+#[tokio::test]
+async fn login_rejects_wrong_password() {
+    let app = TestApp::spawn().await;
+    app.user("ada@example.com").await;
+
+    let login = app.login("ada@example.com", "not the password").await;
+
+    assert_eq!(login.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(login.body["code"], "auth.login_failed");
+}
+
+#[tokio::test]
+async fn unverified_users_are_restricted() {
+    let app = TestApp::spawn().await;
+    app.request(
+        Method::POST,
+        "/api/auth/register",
+        None,
+        Some(json!({ "email": "ada@example.com", "password": PASSWORD })),
+    )
+    .await;
+    let login = app.login("ada@example.com", PASSWORD).await;
+    let token = login.data()["token"].as_str().unwrap();
+
+    let workout = app.request(Method::POST, "/api/workouts", Some(token), None).await;
+
+    assert_eq!(workout.status, StatusCode::FORBIDDEN);
+    assert_eq!(workout.body["code"], "auth.email_not_verified");
+}
+
+#[tokio::test]
+async fn requests_without_token_are_rejected() {
+    let app = TestApp::spawn().await;
+
+    let workouts = app.request(Method::GET, "/api/workouts", None, None).await;
+
+    assert_eq!(workouts.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(workouts.body["code"], "auth.invalid_token");
+}
+
+#[tokio::test]
+async fn refresh_rotates_and_revokes_on_reuse() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let refreshed = app
+        .request(
+            Method::POST,
+            "/api/auth/refresh",
+            None,
+            Some(json!({ "refresh_token": user.refresh_token })),
+        )
+        .await;
+    assert_eq!(refreshed.status, StatusCode::CREATED, "{}", refreshed.body);
+    let rotated = refreshed.data()["refresh_token"].as_str().unwrap().to_string();
+    assert_ne!(rotated, user.refresh_token);
+
+    let reused = app
+        .request(
+            Method::POST,
+            "/api/auth/refresh",
+            None,
+            Some(json!({ "refresh_token": user.refresh_token })),
+        )
+        .await;
+    assert_eq!(reused.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(reused.body["code"], "auth.refresh_token_reused");
+
+    // The whole family is gone, including the token handed out last
+    let after_reuse = app
+        .request(Method::POST, "/api/auth/refresh", None, Some(json!({ "refresh_token": rotated })))
+        .await;
+    assert_eq!(after_reuse.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn access_tokens_live_as_long_as_configured() {
+    let app = TestApp::spawn_with(|config| config.auth.access_token_ttl_secs = 60).await;
+    let user = app.user("ada@example.com").await;
+
+    let login = app.login(&user.email, PASSWORD).await;
+    let expires_at = login.data()["token_expires_at"].as_str().unwrap().parse::<DateTime<Utc>>().unwrap();
+    let ttl = expires_at - Utc::now();
+    assert!(ttl.num_seconds() > 50 && ttl.num_seconds() <= 60, "{ttl}");
+}
+
+#[tokio::test]
+async fn sessions_record_when_they_were_last_used() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+    let time = |value: &serde_json::Value| value.as_str().unwrap().parse::<DateTime<Utc>>().unwrap();
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let first = app.get("/api/auth/sessions", &user.token).await;
+    let last_used_at = time(&first.data()[0]["last_used_at"]);
+    assert!(last_used_at > time(&first.data()[0]["created_at"]));
+
+    // Written at most every few minutes, not on every request
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let second = app.get("/api/auth/sessions", &user.token).await;
+    assert_eq!(time(&second.data()[0]["last_used_at"]), last_used_at);
+}
+
+#[tokio::test]
+async fn reset_codes_are_only_in_the_sent_mail() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let forgot = app
+        .request(Method::POST, "/api/auth/password/forgot", None, Some(json!({ "email": user.email })))
+        .await;
+    assert_eq!(forgot.status, StatusCode::OK);
+
+    let mail = app.mails().await.pop().unwrap();
+    assert_eq!(mail.subject, "Reset your password");
+    let code = mail.body.lines().find_map(|line| line.strip_prefix("Your reset code is: ")).unwrap();
+
+    // Neither the job nor the reset row holds the code in plain text
+    let jobs = Job::search(&app.state.db, None, 100, 0).await.unwrap();
+    assert!(jobs.iter().any(|job| job.kind == "send_password_reset"));
+    assert!(jobs.iter().all(|job| !job.payload.contains(code)));
+
+    let reset = app
+        .request(
+            Method::POST,
+            "/api/auth/password/reset",
+            None,
+            Some(json!({ "code": code, "password": "a new password" })),
+        )
+        .await;
+    assert_eq!(reset.status, StatusCode::OK, "{}", reset.body);
+    assert_eq!(app.login(&user.email, "a new password").await.status, StatusCode::CREATED);
+
+    // Whoever knew the old password is signed out
+    assert_eq!(app.get("/api/workouts", &user.token).await.status, StatusCode::UNAUTHORIZED);
+    let refreshed = app
+        .request(Method::POST, "/api/auth/refresh", None, Some(json!({ "refresh_token": user.refresh_token })))
+        .await;
+    assert!(refreshed.status.is_client_error(), "{}", refreshed.body);
+}
+
+#[tokio::test]
+async fn mail_requests_are_throttled_per_address() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    for _ in 0..3 {
+        let forgot = app
+            .request(Method::POST, "/api/auth/password/forgot", None, Some(json!({ "email": user.email })))
+            .await;
+        assert_eq!(forgot.status, StatusCode::OK, "{}", forgot.body);
+    }
+    // Resending counts towards the same limit
+    let resend = app
+        .request(Method::POST, "/api/auth/verify/resend", None, Some(json!({ "email": user.email })))
+        .await;
+    assert_eq!(resend.status, StatusCode::OK, "{}", resend.body);
+
+    let throttled = app
+        .request(Method::POST, "/api/auth/password/forgot", None, Some(json!({ "email": user.email })))
+        .await;
+    assert_eq!(throttled.status, StatusCode::TOO_MANY_REQUESTS, "{}", throttled.body);
+    assert_eq!(throttled.body["code"], "auth.too_many_mail_requests");
+    assert!(throttled.headers.contains_key(RETRY_AFTER));
+    let resets = app.mails().await.iter().filter(|mail| mail.subject == "Reset your password").count();
+    assert_eq!(resets, 3);
+
+    // Logging in isn't affected
+    assert_eq!(app.login(&user.email, PASSWORD).await.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn two_factor_login_uses_up_recovery_codes() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let setup = app.request(Method::POST, "/api/auth/2fa/setup", Some(&user.token), None).await;
+    assert_eq!(setup.status, StatusCode::CREATED, "{}", setup.body);
+    let secret = setup.data()["secret"].as_str().unwrap();
+
+    let confirmed = app
+        .post("/api/auth/2fa/confirm", &user.token, json!({ "code": totp_code(secret) }))
+        .await;
+    assert_eq!(confirmed.status, StatusCode::OK, "{}", confirmed.body);
+    let recovery_code = confirmed.data()["recovery_codes"][0].as_str().unwrap().to_string();
+
+    let challenge = || async {
+        let login = app.login(&user.email, PASSWORD).await;
+        assert_eq!(login.data()["two_factor_required"], true, "{}", login.body);
+        login.data()["challenge"].as_str().unwrap().to_string()
+    };
+    let login_two_factor = |challenge: String, code: String| {
+        app.request(
+            Method::POST,
+            "/api/auth/login/2fa",
+            None,
+            Some(json!({ "challenge": challenge, "code": code })),
+        )
+    };
+
+    let wrong = login_two_factor(challenge().await, "000000 nope".to_string()).await;
+    assert_eq!(wrong.body["code"], "auth.two_factor_failed", "{}", wrong.body);
+
+    let login = login_two_factor(challenge().await, recovery_code.clone()).await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.body);
+    let token = login.data()["token"].as_str().unwrap();
+    assert_eq!(app.get("/api/auth/sessions", token).await.status, StatusCode::OK);
+
+    let reused = login_two_factor(challenge().await, recovery_code).await;
+    assert_eq!(reused.body["code"], "auth.two_factor_failed", "{}", reused.body);
+}
+
+#[tokio::test]
+async fn changing_the_password_signs_out_other_sessions() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+    let other = app.login(&user.email, PASSWORD).await;
+    let other_token = other.data()["token"].as_str().unwrap().to_string();
+
+    let wrong = app
+        .put(
+            "/api/auth/password",
+            &user.token,
+            Some(json!({ "current_password": "not it", "new_password": "a new password" })),
+        )
+        .await;
+    assert_eq!(wrong.body["code"], "auth.wrong_password", "{}", wrong.body);
+    assert_eq!(app.get("/api/workouts", &other_token).await.status, StatusCode::OK);
+
+    let changed = app
+        .put(
+            "/api/auth/password",
+            &user.token,
+            Some(json!({ "current_password": PASSWORD, "new_password": "a new password" })),
+        )
+        .await;
+    assert_eq!(changed.status, StatusCode::OK, "{}", changed.body);
+
+    // The session that changed it stays, every other one is gone
+    assert_eq!(app.get("/api/workouts", &user.token).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/workouts", &other_token).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login(&user.email, PASSWORD).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login(&user.email, "a new password").await.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn email_changes_once_the_new_address_is_confirmed() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let wrong = app
+        .post("/api/auth/email", &user.token, json!({ "email": "ada@new.example", "password": "not it" }))
+        .await;
+    assert_eq!(wrong.body["code"], "auth.wrong_password", "{}", wrong.body);
+
+    let requested = app
+        .post("/api/auth/email", &user.token, json!({ "email": "ada@new.example", "password": PASSWORD }))
+        .await;
+    assert_eq!(requested.status, StatusCode::OK, "{}", requested.body);
+
+    // Nothing changes until the link sent to the new address is opened
+    let mail = app.mails().await.pop().unwrap();
+    assert_eq!(mail.to, "ada@new.example");
+    assert_eq!(mail.subject, "Confirm your new email");
+    assert_eq!(app.login(&user.email, PASSWORD).await.status, StatusCode::CREATED);
+
+    let link = mail.body.lines().find(|line| line.contains("/api/auth/email/confirm/")).unwrap();
+    let path = &link[link.find("/api/").unwrap()..];
+    let confirmed = app.request(Method::GET, path, None, None).await;
+    assert_eq!(confirmed.status, StatusCode::OK, "{}", confirmed.body);
+    assert_eq!(confirmed.data()["email"], "ada@new.example");
+
+    let again = app.request(Method::GET, path, None, None).await;
+    assert!(again.status.is_client_error(), "{}", again.body);
+
+    let notice = app.mails().await.pop().unwrap();
+    assert_eq!(notice.to, "ada@example.com");
+    assert_eq!(notice.subject, "Your email was changed");
+
+    assert_eq!(app.login(&user.email, PASSWORD).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.login("ada@new.example", PASSWORD).await.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn repeated_failures_lock_the_account_for_a_while() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    // Five free attempts, the sixth failure starts a one second lockout
+    for _ in 0..6 {
+        assert_eq!(app.login(&user.email, "not it").await.status, StatusCode::UNAUTHORIZED);
+    }
+
+    // Even the right password has to wait
+    let locked = app.login(&user.email, PASSWORD).await;
+    assert_eq!(locked.status, StatusCode::TOO_MANY_REQUESTS, "{}", locked.body);
+    assert_eq!(locked.body["code"], "auth.too_many_login_attempts");
+    let retry_after: u64 = locked.headers[RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    assert_eq!(retry_after, 1);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(app.login(&user.email, PASSWORD).await.status, StatusCode::CREATED);
+}
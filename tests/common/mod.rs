@@ -0,0 +1,296 @@
+//! Runs the whole app in-process against its own in-memory SQLite database,
+//! so every test starts from a freshly migrated schema and tests can run in
+//! parallel without seeing each other's rows.
+//!
+//! With `TEST_DATABASE_URL` set to a `mysql://` URL the same suite runs
+//! against MySQL instead. Every test then creates its own database on that
+//! server and drops it when done, the user needs the rights for both.
+
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use serde_json::Value;
+use sqlx::{Connection, MySqlConnection};
+use tower::ServiceExt;
+
+use workout_backend::{
+    build_app,
+    config::{Config, DatabaseConfig},
+    db::{self, Backend},
+    error::Result,
+    mailer::{Mail, Mailer},
+    queue, telemetry, ApiState,
+};
+
+pub const PASSWORD: &str = "correct horse battery";
+
+/// The metrics macros record into one global recorder, so every app of a
+/// test binary shares it and sees the numbers of the others as well
+static METRICS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// The current code of an authenticator app set up with `secret`
+pub fn totp_code(secret: &str) -> String {
+    let bytes = totp_rs::Secret::Encoded(secret.to_string()).to_bytes().unwrap();
+    let totp = totp_rs::TOTP::new(totp_rs::Algorithm::SHA1, 6, 1, 30, bytes, None, "user".to_string()).unwrap();
+
+    totp.generate_current().unwrap()
+}
+
+pub struct TestApp {
+    pub state: ApiState,
+    router: Router,
+    mailer: Arc<MemoryMailer>,
+    database: Option<MySqlTestDatabase>,
+}
+
+/// A database created for one test on the `TEST_DATABASE_URL` server
+struct MySqlTestDatabase {
+    server_url: String,
+    name: String,
+}
+
+impl MySqlTestDatabase {
+    async fn create(server_url: String) -> Self {
+        let name = format!("workout_test_{}", uuid::Uuid::new_v4().simple());
+
+        let mut conn = MySqlConnection::connect(&server_url).await.expect("TEST_DATABASE_URL server");
+        sqlx::query(&format!("CREATE DATABASE `{name}`"))
+            .execute(&mut conn)
+            .await
+            .expect("Created test database");
+
+        Self { server_url, name }
+    }
+
+    fn url(&self) -> String {
+        let mut url = reqwest::Url::parse(&self.server_url).expect("Valid TEST_DATABASE_URL");
+        url.set_path(&self.name);
+        url.to_string()
+    }
+}
+
+/// Tests run on a runtime that is shutting down by now, so the database is
+/// dropped from a runtime of its own
+impl Drop for MySqlTestDatabase {
+    fn drop(&mut self) {
+        let server_url = self.server_url.clone();
+        let name = self.name.clone();
+
+        let dropped = std::thread::spawn(move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(async {
+                let mut conn = MySqlConnection::connect(&server_url).await?;
+                sqlx::query(&format!("DROP DATABASE `{name}`")).execute(&mut conn).await?;
+                conn.close().await
+            })
+        })
+        .join()
+        .unwrap();
+
+        if let Err(err) = dropped {
+            eprintln!("Failed to drop test database {}: {err}", self.name);
+        }
+    }
+}
+
+/// Keeps sent mails for the test to read
+#[derive(Default)]
+struct MemoryMailer {
+    sent: Mutex<Vec<Mail>>,
+}
+
+#[async_trait::async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        self.sent.lock().unwrap().push(mail);
+        Ok(())
+    }
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+impl TestResponse {
+    /// The `data` of a successful response envelope
+    pub fn data(&self) -> &Value {
+        &self.body["data"]
+    }
+}
+
+/// A registered user with a verified email and a fresh session
+pub struct TestUser {
+    pub id: String,
+    pub email: String,
+    pub token: String,
+    pub refresh_token: String,
+}
+
+impl TestApp {
+    pub async fn spawn() -> Self {
+        Self::spawn_with(|_| ()).await
+    }
+
+    /// Like `spawn`, with a chance to change the config first
+    pub async fn spawn_with(configure: impl FnOnce(&mut Config)) -> Self {
+        let database = match std::env::var("TEST_DATABASE_URL") {
+            Ok(url) if Backend::from_url(&url) == Some(Backend::MySql) => Some(MySqlTestDatabase::create(url).await),
+            Ok(url) => panic!("TEST_DATABASE_URL must be a mysql:// URL, got {url}"),
+            Err(_) => None,
+        };
+
+        let mut config = Config {
+            database: DatabaseConfig {
+                url: database.as_ref().map_or("sqlite::memory:".to_string(), MySqlTestDatabase::url),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.auth.access_token_secret = "test-secret".to_string();
+        configure(&mut config);
+
+        let db = db::connect(&config.database).await.expect("Test database");
+        db.migrate().await.expect("Migrated test database");
+
+        let metrics = METRICS.get_or_init(telemetry::init_metrics).clone();
+
+        let mailer = Arc::new(MemoryMailer::default());
+        let mut state = ApiState::new(Arc::new(config), db, metrics);
+        state.mailer = mailer.clone();
+        let router = build_app(state.clone());
+
+        Self {
+            state,
+            router,
+            mailer,
+            database,
+        }
+    }
+
+    pub async fn request(&self, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into()))
+        };
+
+        TestResponse { status, headers, body }
+    }
+
+    pub async fn get(&self, uri: &str, token: &str) -> TestResponse {
+        self.request(Method::GET, uri, Some(token), None).await
+    }
+
+    pub async fn post(&self, uri: &str, token: &str, body: Value) -> TestResponse {
+        self.request(Method::POST, uri, Some(token), Some(body)).await
+    }
+
+    pub async fn put(&self, uri: &str, token: &str, body: Option<Value>) -> TestResponse {
+        self.request(Method::PUT, uri, Some(token), body).await
+    }
+
+    pub async fn delete(&self, uri: &str, token: &str) -> TestResponse {
+        self.request(Method::DELETE, uri, Some(token), None).await
+    }
+
+    /// Runs the queued jobs, workers don't run in tests, and returns every
+    /// mail sent so far, oldest first
+    pub async fn mails(&self) -> Vec<Mail> {
+        queue::run_pending(&self.state).await.unwrap();
+
+        self.mailer.sent.lock().unwrap().clone()
+    }
+
+    pub async fn login(&self, email: &str, password: &str) -> TestResponse {
+        self.request(
+            Method::POST,
+            "/api/auth/login",
+            None,
+            Some(serde_json::json!({ "email": email, "password": password })),
+        )
+        .await
+    }
+
+    /// Registers `email`, follows the link in the verification mail and
+    /// logs in
+    pub async fn user(&self, email: &str) -> TestUser {
+        let registered = self
+            .request(
+                Method::POST,
+                "/api/auth/register",
+                None,
+                Some(serde_json::json!({ "email": email, "password": PASSWORD })),
+            )
+            .await;
+        assert_eq!(registered.status, StatusCode::CREATED, "{}", registered.body);
+
+        let mail = self.mails().await.into_iter().rev().find(|mail| mail.to == email).expect("Verification mail");
+        let link = mail.body.lines().find(|line| line.contains("/api/auth/verify/")).unwrap();
+        let path = &link[link.find("/api/").unwrap()..];
+        let verified = self.request(Method::GET, path, None, None).await;
+        assert_eq!(verified.status, StatusCode::OK, "{}", verified.body);
+
+        let login = self.login(email, PASSWORD).await;
+        assert_eq!(login.status, StatusCode::CREATED, "{}", login.body);
+
+        TestUser {
+            id: registered.data()["id"].as_str().unwrap().to_string(),
+            email: email.to_string(),
+            token: login.data()["token"].as_str().unwrap().to_string(),
+            refresh_token: login.data()["refresh_token"].as_str().unwrap().to_string(),
+        }
+    }
+
+    /// Creates an exercise, starts a workout if none is running and adds the
+    /// exercise to it. Returns the exercise workout id.
+    pub async fn exercise_in_current_workout(&self, user: &TestUser, name: &str) -> String {
+        let exercise = self
+            .post(
+                "/api/exercises",
+                &user.token,
+                serde_json::json!({ "name": name, "exercise_type": "WeightOverAmount", "targets": [] }),
+            )
+            .await;
+        assert_eq!(exercise.status, StatusCode::CREATED, "{}", exercise.body);
+
+        if self.get("/api/workouts/current", &user.token).await.status == StatusCode::NOT_FOUND {
+            let workout = self.request(Method::POST, "/api/workouts", Some(&user.token), None).await;
+            assert_eq!(workout.status, StatusCode::CREATED, "{}", workout.body);
+        }
+
+        let added = self
+            .post(
+                "/api/workouts/current/exercises",
+                &user.token,
+                serde_json::json!({ "exercise_id": exercise.data()["id"] }),
+            )
+            .await;
+        assert_eq!(added.status, StatusCode::CREATED, "{}", added.body);
+
+        added.data()["id"].as_str().unwrap().to_string()
+    }
+}
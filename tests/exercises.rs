@@ -0,0 +1,68 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+use workout_backend::models::user::User;
+
+#[tokio::test]
+async fn unknown_targets_are_rejected_without_partial_writes() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let targets = app.get("/api/targets", &user.token).await;
+    let chest = targets.data()[0]["id"].clone();
+
+    let created = app
+        .post(
+            "/api/exercises",
+            &user.token,
+            json!({ "name": "Bench press", "exercise_type": "WeightOverAmount", "targets": [chest, "nope"] }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", created.body);
+    assert_eq!(created.body["errors"]["targets"], json!(["unknown target nope"]));
+    assert_eq!(app.get("/api/exercises", &user.token).await.body["meta"]["total"], 0);
+
+    let created = app
+        .post(
+            "/api/exercises",
+            &user.token,
+            json!({ "name": "Bench press", "exercise_type": "WeightOverAmount", "targets": [chest] }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap();
+
+    let updated = app
+        .request(
+            Method::PUT,
+            &format!("/api/exercises/{id}"),
+            Some(&user.token),
+            Some(json!({ "name": "Incline press", "exercise_type": "WeightOverAmount", "targets": ["nope"] })),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", updated.body);
+
+    // Neither the name nor the targets changed
+    let exercises = app.get("/api/exercises", &user.token).await;
+    assert_eq!(exercises.data()[0]["name"], "Bench press");
+    assert_eq!(exercises.data()[0]["targets"][0]["id"], chest);
+}
+
+#[tokio::test]
+async fn transactions_only_keep_committed_writes() {
+    let app = TestApp::spawn().await;
+    let db = &app.state.db;
+
+    let tx = db.begin().await.unwrap();
+    User::create(&*tx, "dropped@example.com".to_string(), "hash".to_string()).await.unwrap();
+    drop(tx);
+    assert!(User::find_by_email(db, "dropped@example.com").await.unwrap().is_none());
+
+    let tx = db.begin().await.unwrap();
+    User::create(&*tx, "kept@example.com".to_string(), "hash".to_string()).await.unwrap();
+    tx.commit().await.unwrap();
+    assert!(User::find_by_email(db, "kept@example.com").await.unwrap().is_some());
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+
+use common::TestApp;
+use workout_backend::{
+    generator::{self, Options},
+    models::{set::Set, user::User},
+};
+
+fn options(seed: u64) -> Options {
+    Options {
+        users: 3,
+        weeks: 8,
+        seed,
+        until: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+    }
+}
+
+/// Every set of the first generated user, as comparable values
+async fn first_user_sets(app: &TestApp, seed: u64) -> Vec<(String, f32, f32, String, String)> {
+    let user = User::find_by_email(&app.state.db, &format!("generated-{seed}-0@example.com"))
+        .await
+        .unwrap()
+        .unwrap();
+
+    Set::find_all_by_user_id(&app.state.db, user.id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|set| (set.id, set.quality, set.quantity, set.set_type.to_string(), set.created_at.to_rfc3339()))
+        .collect()
+}
+
+#[tokio::test]
+async fn same_seed_generates_the_same_rows() {
+    let first = TestApp::spawn().await;
+    let second = TestApp::spawn().await;
+    let other = TestApp::spawn().await;
+
+    let summary = generator::generate(&first.state.db, &options(7)).await.unwrap();
+    assert_eq!(summary.users, 3);
+    assert!(summary.sets > 0);
+    assert_eq!(generator::generate(&second.state.db, &options(7)).await.unwrap(), summary);
+    generator::generate(&other.state.db, &options(8)).await.unwrap();
+
+    let sets = first_user_sets(&first, 7).await;
+    assert!(!sets.is_empty());
+    assert_eq!(first_user_sets(&second, 7).await, sets);
+    assert_ne!(first_user_sets(&other, 8).await, sets);
+}
+
+#[tokio::test]
+async fn generated_users_can_log_in_and_see_their_history() {
+    let app = TestApp::spawn().await;
+    let summary = generator::generate(&app.state.db, &options(1)).await.unwrap();
+
+    let login = app.login("generated-1-0@example.com", "password").await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.body);
+    let token = login.data()["token"].as_str().unwrap();
+
+    let workouts = app.get("/api/workouts", token).await;
+    assert_eq!(workouts.status, StatusCode::OK);
+    assert!(!workouts.data().as_array().unwrap().is_empty());
+    assert!(workouts.data().as_array().unwrap().len() < summary.workouts);
+
+    // Nothing is left running, a new workout can be started
+    assert_eq!(app.get("/api/workouts/current", token).await.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use axum::http::{header::CONTENT_TYPE, Method, StatusCode};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn metrics_need_the_configured_token() {
+    let app = TestApp::spawn().await;
+    let hidden = app.request(Method::GET, "/metrics", None, None).await;
+    assert_eq!(hidden.status, StatusCode::NOT_FOUND);
+
+    let app = TestApp::spawn_with(|config| config.server.metrics_token = Some("scraper".to_string())).await;
+    assert_eq!(app.request(Method::GET, "/metrics", None, None).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/metrics", "wrong").await.status, StatusCode::UNAUTHORIZED);
+
+    // Registering queues the verification mail, which `user` sends
+    app.user("ada@example.com").await;
+    assert_eq!(app.request(Method::GET, "/healthz", None, None).await.status, StatusCode::OK);
+
+    let scraped = app.get("/metrics", "scraper").await;
+    assert_eq!(scraped.status, StatusCode::OK);
+    assert!(scraped.headers[CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+    let metrics = scraped.body.as_str().unwrap();
+    assert!(
+        metrics.contains(r#"http_requests_total{method="GET",route="/healthz",status="200"}"#),
+        "{metrics}"
+    );
+    assert!(
+        metrics.contains(r#"jobs_processed_total{kind="send_verification",outcome="done"}"#),
+        "{metrics}"
+    );
+    assert!(metrics.contains("db_pool_connections"), "{metrics}");
+}
+
+#[tokio::test]
+async fn ready_once_every_migration_is_applied() {
+    let app = TestApp::spawn().await;
+    let db = &app.state.db;
+
+    let ready = app.request(Method::GET, "/readyz", None, None).await;
+    assert_eq!(ready.status, StatusCode::OK, "{}", ready.body);
+
+    db.revert_migration().await.unwrap();
+    let behind = app.request(Method::GET, "/readyz", None, None).await;
+    assert_eq!(behind.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(behind.body["reason"], "1 migrations not applied");
+
+    db.migrate().await.unwrap();
+    assert_eq!(app.request(Method::GET, "/readyz", None, None).await.status, StatusCode::OK);
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::time::Duration;
+
+use common::TestApp;
+use workout_backend::models::job::{Job, JobPayload, JobStatus};
+
+#[tokio::test]
+async fn workers_only_finish_jobs_they_still_hold() {
+    let app = TestApp::spawn().await;
+    let db = &app.state.db;
+
+    Job::enqueue(db, &JobPayload::SendVerification { user_id: "nobody".to_string() }, 3)
+        .await
+        .unwrap();
+
+    // The first worker stalls until its job is handed to another one
+    let mut stalled = Job::claim(db, "first").await.unwrap().unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    Job::requeue_stuck(db, Duration::ZERO).await.unwrap();
+    let mut current = Job::claim(db, "second").await.unwrap().unwrap();
+    assert_eq!(current.id, stalled.id);
+
+    assert!(!stalled.complete(db).await.unwrap());
+    assert!(!stalled.fail(db, "too late").await.unwrap());
+    let job = Job::find_by_id(db, current.id.clone()).await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Running);
+    assert_eq!(job.locked_by.as_deref(), Some("second"));
+
+    assert!(current.complete(db).await.unwrap());
+    let job = Job::find_by_id(db, current.id.clone()).await.unwrap().unwrap();
+    assert_eq!(job.status, JobStatus::Done);
+}
+
+#[test]
+fn unknown_statuses_are_errors() {
+    for status in [JobStatus::Pending, JobStatus::Running, JobStatus::Done, JobStatus::Dead] {
+        assert_eq!(JobStatus::try_from(status.to_string()).unwrap(), status);
+    }
+    assert!(JobStatus::try_from("paused".to_string()).is_err());
+}
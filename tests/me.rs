@@ -0,0 +1,92 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use futures::TryStreamExt;
+use serde_json::json;
+
+use common::{TestApp, TestUser, PASSWORD};
+use workout_backend::models::{exercise::Exercise, set::Set, token::Token, user::User, workout::Workout};
+
+/// Gives `user` a workout with `name` in it and two sets of it
+async fn log_sets(app: &TestApp, user: &TestUser, name: &str) {
+    let exercise_workout_id = app.exercise_in_current_workout(user, name).await;
+    for quantity in [8, 6] {
+        let set = app
+            .post(
+                "/api/sets",
+                &user.token,
+                json!({ "exercise_workout_id": exercise_workout_id, "quality": 40.0, "quantity": quantity, "set_type": "Normal" }),
+            )
+            .await;
+        assert_eq!(set.status, StatusCode::CREATED, "{}", set.body);
+    }
+}
+
+#[tokio::test]
+async fn export_holds_only_the_users_own_data() {
+    let app = TestApp::spawn().await;
+    let ada = app.user("ada@example.com").await;
+    let grace = app.user("grace@example.com").await;
+    log_sets(&app, &ada, "Bench press").await;
+    log_sets(&app, &grace, "Deadlift").await;
+
+    let export = app.get("/api/me/export", &ada.token).await;
+    assert_eq!(export.status, StatusCode::OK);
+    let document = export.body;
+
+    assert_eq!(document["user"]["email"], "ada@example.com");
+    let exercises = document["exercises"].as_array().unwrap();
+    assert_eq!(exercises.len(), 1);
+    assert_eq!(exercises[0]["name"], "Bench press");
+    assert_eq!(document["workouts"].as_array().unwrap().len(), 1);
+    assert_eq!(document["exercise_workouts"].as_array().unwrap().len(), 1);
+    assert_eq!(document["sets"].as_array().unwrap().len(), 2);
+    assert!(!document["sessions"].as_array().unwrap().is_empty());
+
+    let text = document.to_string();
+    assert!(!text.contains(&grace.id), "{text}");
+    assert!(!text.contains("grace@example.com"), "{text}");
+    assert!(!text.contains("Deadlift"), "{text}");
+}
+
+#[tokio::test]
+async fn deleting_the_account_removes_its_data_and_sessions() {
+    let app = TestApp::spawn().await;
+    let db = &app.state.db;
+    let ada = app.user("ada@example.com").await;
+    let grace = app.user("grace@example.com").await;
+    log_sets(&app, &ada, "Bench press").await;
+    log_sets(&app, &grace, "Deadlift").await;
+
+    let wrong = app
+        .request(Method::DELETE, "/api/me", Some(&ada.token), Some(json!({ "password": "not it" })))
+        .await;
+    assert_eq!(wrong.body["code"], "auth.wrong_password", "{}", wrong.body);
+
+    let deleted = app
+        .request(Method::DELETE, "/api/me", Some(&ada.token), Some(json!({ "password": PASSWORD })))
+        .await;
+    assert_eq!(deleted.status, StatusCode::OK, "{}", deleted.body);
+
+    assert!(User::find_by_id(db, ada.id.clone()).await.unwrap().is_none());
+    let rows = |user: &TestUser| {
+        let id = user.id.clone();
+        async move {
+            (
+                Exercise::stream_by_user_id(db, id.clone()).try_collect::<Vec<_>>().await.unwrap().len(),
+                Workout::stream_by_user_id(db, id.clone()).try_collect::<Vec<_>>().await.unwrap().len(),
+                Set::stream_by_user_id(db, id.clone()).try_collect::<Vec<_>>().await.unwrap().len(),
+                Token::stream_by_user_id(db, id).try_collect::<Vec<_>>().await.unwrap().len(),
+            )
+        }
+    };
+    assert_eq!(rows(&ada).await, (0, 0, 0, 0));
+    assert_eq!(rows(&grace).await, (1, 1, 2, 1));
+
+    assert_eq!(app.get("/api/workouts", &ada.token).await.status, StatusCode::UNAUTHORIZED);
+    let refreshed = app
+        .request(Method::POST, "/api/auth/refresh", None, Some(json!({ "refresh_token": ada.refresh_token })))
+        .await;
+    assert!(refreshed.status.is_client_error(), "{}", refreshed.body);
+    assert_eq!(app.login("ada@example.com", PASSWORD).await.status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn migrations_can_be_reverted_and_reapplied() {
+    let app = TestApp::spawn().await;
+    let db = &app.state.db;
+
+    let migrations = db.migrations().await.unwrap();
+    assert!(migrations.iter().all(|migration| migration.applied && migration.reversible));
+    assert_eq!(db.pending_migrations().await.unwrap(), 0);
+
+    let newest = migrations.last().unwrap();
+    let reverted = db.revert_migration().await.unwrap().unwrap();
+    assert_eq!(reverted.version, newest.version);
+    assert_eq!(db.pending_migrations().await.unwrap(), 1);
+
+    db.migrate().await.unwrap();
+    assert_eq!(db.pending_migrations().await.unwrap(), 0);
+
+    // The schema is back, registering works again
+    app.user("again@example.com").await;
+}
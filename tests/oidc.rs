@@ -0,0 +1,223 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::State,
+    http::{header::LOCATION, Method, StatusCode},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde_json::{json, Value};
+
+use common::{totp_code, TestApp};
+use workout_backend::helpers::oidc::{pkce_challenge, OidcConfig};
+
+const CLIENT_ID: &str = "workout";
+const KEY_ID: &str = "test-key";
+/// Public half of tests/fixtures/oidc_key.pem
+const KEY_MODULUS: &str = "zmmx5OwXenUz3WANxSPkMiMg2zgkzdc_SsHCGJ8M0I7FH-7PTV5Z3I2pTVTML32zP10kkwcELD75Zxgq_Pbzd0-iztNYtC7JyKXxuqGosC9bNiOTpSdE_nmUt8JDg8g1mG3Jn1y6TqV1o8UjADAkc7QDigQVXECX_-d7hLVtDDvg2IucocI9tTMe3Oeg5iTzJ9wc-qNUUNp5UkVbTz0j7tfmORCX_e1V7EyYH4OP_cUh9WxiAhz4TcFBFTDjxfbQww0MhRKPQM6cFiTbtfi0Pcev3hkn6ml_6g2zbB1k-r7XA4YGE0MyF7an2ZukUo5MUao2LA_aalEV9U11kM5F0Q";
+
+/// What the provider remembers between the login and the token request
+struct Grant {
+    subject: String,
+    email: String,
+    nonce: String,
+    code_challenge: String,
+}
+
+/// A local OpenID provider that signs in whoever the test says, with
+/// discovery, a JWKS and a token endpoint that checks PKCE
+#[derive(Clone)]
+struct MockIssuer {
+    url: String,
+    grants: Arc<Mutex<HashMap<String, Grant>>>,
+}
+
+impl MockIssuer {
+    async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            grants: Arc::default(),
+        };
+
+        let router = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(issuer.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        issuer
+    }
+
+    /// Plays the user logging in at the provider after being sent to
+    /// `authorization_url`. Returns the query of the redirect back.
+    fn sign_in(&self, authorization_url: &str, subject: &str, email: &str) -> String {
+        let url = reqwest::Url::parse(authorization_url).unwrap();
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["client_id"], CLIENT_ID);
+        assert_eq!(params["code_challenge_method"], "S256");
+
+        let code = uuid::Uuid::new_v4().to_string();
+        self.grants.lock().unwrap().insert(
+            code.clone(),
+            Grant {
+                subject: subject.to_string(),
+                email: email.to_string(),
+                nonce: params["nonce"].clone(),
+                code_challenge: params["code_challenge"].clone(),
+            },
+        );
+
+        format!("code={code}&state={}", params["state"])
+    }
+}
+
+async fn discovery(State(issuer): State<MockIssuer>) -> Json<Value> {
+    Json(json!({
+        "issuer": issuer.url,
+        "authorization_endpoint": format!("{}/authorize", issuer.url),
+        "token_endpoint": format!("{}/token", issuer.url),
+        "jwks_uri": format!("{}/jwks", issuer.url),
+    }))
+}
+
+async fn jwks() -> Json<Value> {
+    Json(json!({
+        "keys": [{ "kty": "RSA", "kid": KEY_ID, "use": "sig", "alg": "RS256", "n": KEY_MODULUS, "e": "AQAB" }]
+    }))
+}
+
+async fn token(
+    State(issuer): State<MockIssuer>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Result<Json<Value>, StatusCode> {
+    let grant = issuer
+        .grants
+        .lock()
+        .unwrap()
+        .remove(&form["code"])
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    if pkce_challenge(&form["code_verifier"]) != grant.code_challenge {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let claims = json!({
+        "iss": issuer.url,
+        "sub": grant.subject,
+        "aud": CLIENT_ID,
+        "iat": now,
+        "exp": now + 300,
+        "email": grant.email,
+        "email_verified": true,
+        "nonce": grant.nonce,
+    });
+
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(KEY_ID.to_string());
+    let key = EncodingKey::from_rsa_pem(include_bytes!("fixtures/oidc_key.pem")).unwrap();
+
+    Ok(Json(json!({
+        "access_token": "unused",
+        "token_type": "Bearer",
+        "id_token": jsonwebtoken::encode(&header, &claims, &key).unwrap(),
+    })))
+}
+
+/// Runs the whole login through the provider and returns the response of
+/// the callback
+async fn oidc_login(app: &TestApp, issuer: &MockIssuer, subject: &str, email: &str) -> common::TestResponse {
+    let authorize = app.request(Method::GET, "/api/auth/oidc/authorize", None, None).await;
+    assert!(authorize.status.is_redirection(), "{}", authorize.body);
+    let location = authorize.headers[LOCATION].to_str().unwrap();
+    assert!(location.starts_with(&issuer.url));
+
+    let callback = issuer.sign_in(location, subject, email);
+    app.request(Method::GET, &format!("/api/auth/oidc/callback?{callback}"), None, None)
+        .await
+}
+
+async fn spawn_with_issuer() -> (TestApp, MockIssuer) {
+    let issuer = MockIssuer::start().await;
+    let app = TestApp::spawn_with(|config| {
+        config.oidc = Some(OidcConfig {
+            issuer: issuer.url.clone(),
+            client_id: CLIENT_ID.to_string(),
+            redirect_url: "http://localhost/api/auth/oidc/callback".to_string(),
+            auto_register: true,
+            ..Default::default()
+        });
+    })
+    .await;
+
+    (app, issuer)
+}
+
+#[tokio::test]
+async fn oidc_login_creates_and_links_accounts() {
+    let (app, issuer) = spawn_with_issuer().await;
+
+    let first = oidc_login(&app, &issuer, "subject-1", "ada@corp.example").await;
+    assert_eq!(first.status, StatusCode::CREATED, "{}", first.body);
+    assert_eq!(first.data()["user"]["email"], "ada@corp.example");
+    let token = first.data()["token"].as_str().unwrap();
+    assert_eq!(app.get("/api/auth/sessions", token).await.status, StatusCode::OK);
+
+    // The same identity lands in the same account
+    let again = oidc_login(&app, &issuer, "subject-1", "ada@corp.example").await;
+    assert_eq!(again.status, StatusCode::CREATED, "{}", again.body);
+    assert_eq!(again.data()["user"]["id"], first.data()["user"]["id"]);
+
+    // A code only works with the verifier of the login that asked for it
+    let authorize = app.request(Method::GET, "/api/auth/oidc/authorize", None, None).await;
+    let callback = issuer.sign_in(authorize.headers[LOCATION].to_str().unwrap(), "subject-2", "grace@corp.example");
+    let state = callback.split("state=").nth(1).unwrap();
+    let other = app.request(Method::GET, "/api/auth/oidc/authorize", None, None).await;
+    let stolen = issuer.sign_in(other.headers[LOCATION].to_str().unwrap(), "subject-2", "grace@corp.example");
+    let code = stolen.split('&').next().unwrap();
+    let mixed = app
+        .request(Method::GET, &format!("/api/auth/oidc/callback?{code}&state={state}"), None, None)
+        .await;
+    assert_eq!(mixed.body["code"], "auth.oidc_failed", "{}", mixed.body);
+
+    let long_name = format!("/api/auth/oidc/authorize?device_name={}", "x".repeat(101));
+    let invalid = app.request(Method::GET, &long_name, None, None).await;
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", invalid.body);
+}
+
+#[tokio::test]
+async fn accounts_without_a_password_can_manage_themselves() {
+    let (app, issuer) = spawn_with_issuer().await;
+
+    let login = oidc_login(&app, &issuer, "subject-1", "ada@corp.example").await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.body);
+    let token = login.data()["token"].as_str().unwrap().to_string();
+
+    let changed = app.post("/api/auth/email", &token, json!({ "email": "ada@home.example" })).await;
+    assert_eq!(changed.status, StatusCode::OK, "{}", changed.body);
+
+    let setup = app.request(Method::POST, "/api/auth/2fa/setup", Some(&token), None).await;
+    let secret = setup.data()["secret"].as_str().unwrap().to_string();
+    let confirmed = app.post("/api/auth/2fa/confirm", &token, json!({ "code": totp_code(&secret) })).await;
+    assert_eq!(confirmed.status, StatusCode::OK, "{}", confirmed.body);
+    let recovery_codes = confirmed.data()["recovery_codes"].clone();
+
+    // The second factor is still needed, only the password is skipped
+    let disabled = app.post("/api/auth/2fa/disable", &token, json!({ "code": "nope" })).await;
+    assert_eq!(disabled.body["code"], "auth.two_factor_failed", "{}", disabled.body);
+    let disabled = app
+        .post("/api/auth/2fa/disable", &token, json!({ "code": recovery_codes[0] }))
+        .await;
+    assert_eq!(disabled.status, StatusCode::OK, "{}", disabled.body);
+
+    let deleted = app.request(Method::DELETE, "/api/me", Some(&token), Some(json!({}))).await;
+    assert_eq!(deleted.status, StatusCode::OK, "{}", deleted.body);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use common::TestApp;
+use workout_backend::generator::{self, Options};
+
+/// A generated user with a couple of months of history, and their token
+async fn user_with_history(app: &TestApp) -> String {
+    let options = Options {
+        users: 1,
+        weeks: 8,
+        seed: 3,
+        until: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+    };
+    generator::generate(&app.state.db, &options).await.unwrap();
+
+    let login = app.login("generated-3-0@example.com", "password").await;
+    login.data()["token"].as_str().unwrap().to_string()
+}
+
+/// Follows `next_cursor` until the last page, returning every item and the
+/// total of the first page
+async fn all_pages(app: &TestApp, token: &str, path: &str) -> (Vec<Value>, i64) {
+    let separator = if path.contains('?') { '&' } else { '?' };
+    let first = app.get(path, token).await;
+    assert!(first.status.is_success(), "{}", first.body);
+
+    let total = first.body["meta"]["total"].as_i64().unwrap();
+    let mut items = first.data().as_array().unwrap().clone();
+    let mut cursor = first.body["meta"]["next_cursor"].clone();
+
+    while let Some(next) = cursor.as_str() {
+        let page = app.get(&format!("{path}{separator}cursor={next}"), token).await;
+        assert!(page.status.is_success(), "{}", page.body);
+        assert_eq!(page.body["meta"]["total"], total);
+
+        items.extend(page.data().as_array().unwrap().iter().cloned());
+        cursor = page.body["meta"]["next_cursor"].clone();
+    }
+
+    (items, total)
+}
+
+#[tokio::test]
+async fn workouts_are_paged_newest_first() {
+    let app = TestApp::spawn().await;
+    let token = user_with_history(&app).await;
+
+    let first = app.get("/api/workouts?limit=5", &token).await;
+    assert_eq!(first.data().as_array().unwrap().len(), 5);
+    assert!(first.body["meta"]["next_cursor"].is_string());
+
+    let (workouts, total) = all_pages(&app, &token, "/api/workouts?limit=5").await;
+    assert!(total > 5);
+    assert_eq!(workouts.len() as i64, total);
+
+    let dates: Vec<&str> = workouts.iter().map(|w| w["created_at"].as_str().unwrap()).collect();
+    assert!(dates.windows(2).all(|pair| pair[0] >= pair[1]));
+
+    let mut ids: Vec<&str> = workouts.iter().map(|w| w["id"].as_str().unwrap()).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), workouts.len());
+
+    let (oldest_first, _) = all_pages(&app, &token, "/api/workouts?limit=7&order=asc").await;
+    assert_eq!(oldest_first.into_iter().rev().collect::<Vec<_>>(), workouts);
+}
+
+#[tokio::test]
+async fn workouts_can_be_filtered_by_date() {
+    let app = TestApp::spawn().await;
+    let token = user_with_history(&app).await;
+
+    let all = app.get("/api/workouts", &token).await;
+    let june = app.get("/api/workouts?from=2024-06-01&to=2024-06-30", &token).await;
+    assert_eq!(june.status, StatusCode::OK);
+
+    let workouts = june.data().as_array().unwrap();
+    assert!(!workouts.is_empty());
+    assert_eq!(june.body["meta"]["total"].as_i64().unwrap(), workouts.len() as i64);
+    assert!(june.body["meta"]["total"].as_i64() < all.body["meta"]["total"].as_i64());
+    assert!(workouts.iter().all(|w| w["created_at"].as_str().unwrap().starts_with("2024-06")));
+}
+
+#[tokio::test]
+async fn exercises_and_history_are_paged() {
+    let app = TestApp::spawn().await;
+    let token = user_with_history(&app).await;
+
+    let (exercises, total) = all_pages(&app, &token, "/api/exercises?limit=2").await;
+    assert_eq!(exercises.len() as i64, total);
+    let names: Vec<&str> = exercises.iter().map(|e| e["name"].as_str().unwrap()).collect();
+    assert!(names.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let id = exercises[0]["id"].as_str().unwrap();
+    let (history, total) = all_pages(&app, &token, &format!("/api/exercises/{id}/history?limit=3")).await;
+    assert!(total > 3);
+    assert_eq!(history.len() as i64, total);
+}
+
+#[tokio::test]
+async fn bad_list_queries_are_rejected() {
+    let app = TestApp::spawn().await;
+    let token = user_with_history(&app).await;
+
+    let too_many = app.get("/api/workouts?limit=500", &token).await;
+    assert_eq!(too_many.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(too_many.body["errors"]["limit"].is_array());
+
+    let bad_date = app.get("/api/workouts?from=yesterday", &token).await;
+    assert_eq!(bad_date.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(bad_date.body["errors"]["from"].is_array());
+
+    let bad_sort = app.get("/api/workouts?sort=weight", &token).await;
+    assert_eq!(bad_sort.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(bad_sort.body["errors"]["query"].is_array());
+
+    // A cursor only fits the sort it came from
+    let page = app.get("/api/workouts?limit=1", &token).await;
+    let cursor = page.body["meta"]["next_cursor"].as_str().unwrap();
+    let other_sort = app.get(&format!("/api/workouts?sort=updated_at&cursor={cursor}"), &token).await;
+    assert_eq!(other_sort.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(other_sort.body["errors"]["cursor"].is_array());
+}
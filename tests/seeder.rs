@@ -0,0 +1,25 @@
+mod common;
+
+use axum::http::StatusCode;
+
+use common::TestApp;
+use workout_backend::seeder::{self, DEMO_EMAIL};
+
+#[tokio::test]
+async fn seeded_demo_account_can_log_in() {
+    let app = TestApp::spawn().await;
+
+    seeder::seed(&app.state.db).await.unwrap();
+
+    let login = app.login(DEMO_EMAIL, "password").await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.body);
+    let token = login.data()["token"].as_str().unwrap();
+
+    let done = app.get("/api/workouts", token).await;
+    assert_eq!(done.data().as_array().unwrap().len(), 1);
+
+    let current = app.get("/api/workouts/current", token).await;
+    let exercises = current.data()["exercises"].as_array().unwrap();
+    let names: Vec<&str> = exercises.iter().map(|exercise| exercise["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Bench press", "Running", "Counter rotation"]);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn sets_are_listed_warmups_first() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+    let exercise_workout_id = app.exercise_in_current_workout(&user, "Bench press").await;
+
+    for (quality, set_type) in [(40.0, "Normal"), (20.0, "Warmup"), (42.5, "Normal")] {
+        let set = app
+            .post(
+                "/api/sets",
+                &user.token,
+                json!({
+                    "exercise_workout_id": exercise_workout_id,
+                    "quality": quality,
+                    "quantity": 8,
+                    "set_type": set_type,
+                }),
+            )
+            .await;
+        assert_eq!(set.status, StatusCode::CREATED, "{}", set.body);
+    }
+
+    let current = app.get("/api/workouts/current", &user.token).await;
+    let sets = current.data()["exercises"][0]["sets"].as_array().unwrap();
+    let qualities: Vec<f64> = sets.iter().map(|set| set["quality"].as_f64().unwrap()).collect();
+    assert_eq!(qualities, vec![20.0, 40.0, 42.5]);
+}
+
+#[tokio::test]
+async fn update_and_delete_a_set() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+    let exercise_workout_id = app.exercise_in_current_workout(&user, "Bench press").await;
+
+    let set = app
+        .post(
+            "/api/sets",
+            &user.token,
+            json!({ "exercise_workout_id": exercise_workout_id, "quality": 40, "quantity": 8, "set_type": "Normal" }),
+        )
+        .await;
+    let id = set.data()["id"].as_str().unwrap();
+
+    let updated = app
+        .put(
+            &format!("/api/sets/{id}"),
+            &user.token,
+            Some(json!({ "quality": 45, "quantity": 6, "set_type": "Normal" })),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.body);
+    assert_eq!(updated.data()["quality"], 45.0);
+    assert_eq!(updated.data()["quantity"], 6.0);
+
+    let deleted = app.delete(&format!("/api/sets/{id}"), &user.token).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+
+    let missing = app.delete(&format!("/api/sets/{id}"), &user.token).await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    assert_eq!(missing.body["code"], "set.not_found");
+}
+
+#[tokio::test]
+async fn sets_of_other_users_cannot_be_changed() {
+    let app = TestApp::spawn().await;
+    let ada = app.user("ada@example.com").await;
+    let grace = app.user("grace@example.com").await;
+    let exercise_workout_id = app.exercise_in_current_workout(&ada, "Bench press").await;
+
+    let set = app
+        .post(
+            "/api/sets",
+            &ada.token,
+            json!({ "exercise_workout_id": exercise_workout_id, "quality": 40, "quantity": 8, "set_type": "Normal" }),
+        )
+        .await;
+    let id = set.data()["id"].as_str().unwrap();
+
+    let updated = app
+        .put(
+            &format!("/api/sets/{id}"),
+            &grace.token,
+            Some(json!({ "quality": 1, "quantity": 1, "set_type": "Warmup" })),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::FORBIDDEN);
+    assert_eq!(updated.body["code"], "set.not_owned");
+
+    let deleted = app.delete(&format!("/api/sets/{id}"), &grace.token).await;
+    assert_eq!(deleted.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn set_values_are_validated() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+    let exercise_workout_id = app.exercise_in_current_workout(&user, "Bench press").await;
+
+    let set = app
+        .post(
+            "/api/sets",
+            &user.token,
+            json!({ "exercise_workout_id": exercise_workout_id, "quality": -1, "quantity": 8, "set_type": "Normal" }),
+        )
+        .await;
+
+    assert_eq!(set.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(set.body["errors"]["quality"].is_array(), "{}", set.body);
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn start_and_finish_a_workout() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let none = app.get("/api/workouts/current", &user.token).await;
+    assert_eq!(none.status, StatusCode::NOT_FOUND);
+    assert_eq!(none.body["code"], "workout.not_found");
+
+    let started = app.request(Method::POST, "/api/workouts", Some(&user.token), None).await;
+    assert_eq!(started.status, StatusCode::CREATED);
+    assert_eq!(started.data()["status"], "Ongoing");
+
+    let current = app.get("/api/workouts/current", &user.token).await;
+    assert_eq!(current.status, StatusCode::OK);
+    assert_eq!(current.data()["id"], started.data()["id"]);
+    assert_eq!(current.data()["exercises"], json!([]));
+
+    let finished = app.put("/api/workouts/current", &user.token, None).await;
+    assert_eq!(finished.status, StatusCode::OK);
+    assert_eq!(finished.data()["status"], "Done");
+
+    let done = app.get("/api/workouts", &user.token).await;
+    assert_eq!(done.status, StatusCode::OK);
+    assert_eq!(done.data().as_array().unwrap().len(), 1);
+    assert_eq!(done.data()[0]["id"], started.data()["id"]);
+
+    let gone = app.get("/api/workouts/current", &user.token).await;
+    assert_eq!(gone.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn exercises_show_up_in_the_current_workout() {
+    let app = TestApp::spawn().await;
+    let user = app.user("ada@example.com").await;
+
+    let bench_press = app.exercise_in_current_workout(&user, "Bench press").await;
+    let squats = app.exercise_in_current_workout(&user, "Squats").await;
+
+    let current = app.get("/api/workouts/current", &user.token).await;
+    let exercises = current.data()["exercises"].as_array().unwrap();
+    assert_eq!(exercises.len(), 2);
+    // Newest first
+    assert_eq!(exercises[0]["exercise_workout_id"], squats.as_str());
+    assert_eq!(exercises[1]["exercise_workout_id"], bench_press.as_str());
+
+    let removed = app
+        .delete(&format!("/api/workouts/current/exercises/{bench_press}"), &user.token)
+        .await;
+    assert_eq!(removed.status, StatusCode::OK);
+
+    let current = app.get("/api/workouts/current", &user.token).await;
+    assert_eq!(current.data()["exercises"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn workouts_are_private() {
+    let app = TestApp::spawn().await;
+    let ada = app.user("ada@example.com").await;
+    let grace = app.user("grace@example.com").await;
+
+    let started = app.request(Method::POST, "/api/workouts", Some(&ada.token), None).await;
+    let id = started.data()["id"].as_str().unwrap();
+
+    let current = app.get("/api/workouts/current", &grace.token).await;
+    assert_eq!(current.status, StatusCode::NOT_FOUND);
+
+    let deleted = app.put(&format!("/api/workouts/{id}"), &grace.token, None).await;
+    assert_eq!(deleted.status, StatusCode::FORBIDDEN);
+    assert_eq!(deleted.body["code"], "workout.not_owned");
+
+    let deleted = app.put(&format!("/api/workouts/{id}"), &ada.token, None).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+}